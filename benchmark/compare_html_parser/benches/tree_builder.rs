@@ -1,5 +1,7 @@
 use criterion::*;
-use fast_html;
+use fast_html::parser::{HtmlParser, Parser};
+use fast_html::sink::FastDomSink;
+use html::sink::DomSink;
 use html5ever::tendril::TendrilSink;
 use markup5ever_rcdom as rcdom;
 
@@ -40,8 +42,15 @@ pub fn criterion_benchmark(cr: &mut Criterion) {
           .read_from(&mut input.as_bytes());
     });
 
-    register_benchmark(cr, &input, &format!("my_fast_html-{}", file), || {
-      fast_html::debugger::get_document_from_html(&input);
+    // 同じ木構築の実装で、組み立てる先のDOMだけを変えて比べる
+    let fast_dom_parser = Parser::<FastDomSink>::new();
+    register_benchmark(cr, &input, &format!("fast_dom_sink-{}", file), || {
+      fast_dom_parser.parse(&input);
+    });
+
+    let dom_parser = Parser::<DomSink>::new();
+    register_benchmark(cr, &input, &format!("dom_sink-{}", file), || {
+      dom_parser.parse(&input);
     });
   }
 }
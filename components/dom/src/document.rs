@@ -7,8 +7,11 @@ pub struct Document {
   doctype: RefCell<Option<DocumentType>>,
 }
 
+#[derive(Clone)]
 pub struct DocumentType {
   pub name: EcoString,
+  pub public_id: EcoString,
+  pub system_id: EcoString,
 }
 
 impl Default for Document {
@@ -25,13 +28,25 @@ impl Document {
     }
   }
 
+  pub fn doctype(&self) -> Option<DocumentType> {
+    self.doctype.borrow().clone()
+  }
+
   pub fn set_doctype(&self, doctype: DocumentType) {
     *self.doctype.borrow_mut() = Some(doctype);
   }
 }
 
 impl DocumentType {
-  pub fn new(name: EcoString) -> Self {
-    DocumentType { name }
+  pub fn new(
+    name: EcoString,
+    public_id: EcoString,
+    system_id: EcoString,
+  ) -> Self {
+    DocumentType {
+      name,
+      public_id,
+      system_id,
+    }
   }
 }
@@ -4,16 +4,23 @@ use std::ops::{Deref, DerefMut};
 use std::{cell::RefCell, collections::HashMap};
 
 use super::elements::ElementData;
+use super::node::NodePtr;
 use super::token_list::TokenList;
 
 #[derive(Debug, Clone)]
 pub struct AttributeMap(HashMap<EcoString, EcoString>);
 
+// https://infra.spec.whatwg.org/#html-namespace
+pub const HTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
+
 pub struct Element {
   data: ElementData,
+  namespace_uri: EcoString,
   id: RefCell<Option<EcoString>>,
   attributes: RefCell<AttributeMap>,
   class_list: RefCell<TokenList>,
+  // template要素の場合のみ、中身を持つDocumentFragment
+  template_contents: RefCell<Option<NodePtr>>,
 }
 
 impl Deref for AttributeMap {
@@ -61,11 +68,17 @@ impl AttributeMap {
 
 impl Element {
   pub fn new(data: ElementData) -> Self {
+    Self::new_ns(data, HTML_NAMESPACE)
+  }
+
+  pub fn new_ns(data: ElementData, namespace_uri: &str) -> Self {
     Self {
       data,
+      namespace_uri: EcoString::from(namespace_uri),
       id: RefCell::new(None),
       attributes: RefCell::new(AttributeMap::new()),
       class_list: RefCell::new(TokenList::new()),
+      template_contents: RefCell::new(None),
     }
   }
 
@@ -73,6 +86,10 @@ impl Element {
     self.data.tag_name()
   }
 
+  pub fn namespace_uri(&self) -> &EcoString {
+    &self.namespace_uri
+  }
+
   pub fn match_tag_name_in(&self, names: &[&str]) -> bool {
     names.iter().any(|name| self.tag_name() == *name)
   }
@@ -89,6 +106,14 @@ impl Element {
     self.class_list.clone()
   }
 
+  pub fn template_contents(&self) -> Option<NodePtr> {
+    self.template_contents.borrow().clone()
+  }
+
+  pub fn set_template_contents(&self, contents: NodePtr) {
+    *self.template_contents.borrow_mut() = Some(contents);
+  }
+
   pub fn has_attribute(&self, name: &str) -> bool {
     self.attributes.borrow().contains_key(name)
   }
@@ -6,14 +6,25 @@ use super::elements::html_element::HTMLElement;
 use crate::elements::ElementData;
 use crate::node::NodeData;
 
-use super::element::Element;
+use super::element::{Element, HTML_NAMESPACE};
 
 use super::node::{Node, NodePtr};
 
 pub fn create_element(document: WeakTreeNode<Node>, tag_name: &str) -> NodePtr {
+  create_element_ns(document, tag_name, HTML_NAMESPACE)
+}
+
+pub fn create_element_ns(
+  document: WeakTreeNode<Node>,
+  tag_name: &str,
+  namespace_uri: &str,
+) -> NodePtr {
   let element_data =
     ElementData::Unknown(HTMLElement::new(EcoString::from(tag_name)));
-  let node = Node::new(NodeData::Element(Element::new(element_data)));
+  let node = Node::new(NodeData::Element(Element::new_ns(
+    element_data,
+    namespace_uri,
+  )));
 
   node.set_document(document);
   NodePtr(TreeNode::new(node))
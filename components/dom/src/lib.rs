@@ -8,4 +8,4 @@ pub mod node;
 pub mod text;
 mod token_list;
 
-pub use element_factory::create_element;
+pub use element_factory::{create_element, create_element_ns};
@@ -19,6 +19,7 @@ pub enum NodeData {
   Element(Element),
   Text(Text),
   Document(Document),
+  DocumentFragment,
   Comment(Comment),
 }
 
@@ -77,6 +78,7 @@ impl core::fmt::Debug for NodeData {
         write!(f, "Comment({:?})", comment.get_data())
       }
       NodeData::Document(_) => write!(f, "Document"),
+      NodeData::DocumentFragment => write!(f, "DocumentFragment"),
       NodeData::Element(element) => write!(f, "{:?}", element),
     }
   }
@@ -151,6 +153,13 @@ impl Node {
     }
   }
 
+  pub fn as_maybe_comment(&self) -> Option<&Comment> {
+    match &self.data {
+      Some(NodeData::Comment(comment)) => Some(comment),
+      _ => None,
+    }
+  }
+
   pub fn as_maybe_text(&self) -> Option<&Text> {
     match &self.data {
       Some(NodeData::Text(text)) => Some(text),
@@ -4,11 +4,21 @@ use std::cell::RefCell;
 
 pub struct Document {
   title: RefCell<EcoString>,
-  doctype: RefCell<Option<DocumentType>>,
+  mode: RefCell<QuirksMode>,
 }
 
+#[derive(Debug, Clone)]
 pub struct DocumentType {
   pub name: EcoString,
+  pub public_id: EcoString,
+  pub system_id: EcoString,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuirksMode {
+  NoQuirks,
+  LimitedQuirks,
+  Quirks,
 }
 
 impl Default for Document {
@@ -21,17 +31,37 @@ impl Document {
   pub fn new() -> Self {
     Document {
       title: RefCell::new(EcoString::new()),
-      doctype: RefCell::new(None),
+      mode: RefCell::new(QuirksMode::NoQuirks),
     }
   }
 
-  pub fn set_doctype(&self, doctype: DocumentType) {
-    *self.doctype.borrow_mut() = Some(doctype);
+  pub fn mode(&self) -> QuirksMode {
+    *self.mode.borrow()
+  }
+
+  pub fn set_mode(&self, mode: QuirksMode) {
+    *self.mode.borrow_mut() = mode;
+  }
+
+  pub fn is_quirks_mode(&self) -> bool {
+    self.mode() == QuirksMode::Quirks
+  }
+
+  pub fn is_limited_quirks_mode(&self) -> bool {
+    self.mode() == QuirksMode::LimitedQuirks
   }
 }
 
 impl DocumentType {
-  pub fn new(name: EcoString) -> Self {
-    DocumentType { name }
+  pub fn new(
+    name: EcoString,
+    public_id: EcoString,
+    system_id: EcoString,
+  ) -> Self {
+    DocumentType {
+      name,
+      public_id,
+      system_id,
+    }
   }
 }
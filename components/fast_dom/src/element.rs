@@ -4,23 +4,89 @@ use std::cell::RefCell;
 use ecow::EcoString;
 use ecow::EcoVec;
 
+use super::namespace::Namespace;
+use super::node::{DOMNode, NodePtr};
+use super::tree::WeakTreeNode;
+
 type AttributeMap = FxHashMap<EcoString, EcoString>;
+type AttributeList = EcoVec<Attr>;
 type ClassList = EcoVec<EcoString>;
 
+// https://dom.spec.whatwg.org/#attr
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attr {
+  pub namespace: Option<Namespace>,
+  pub prefix: Option<EcoString>,
+  pub local_name: EcoString,
+  pub value: EcoString,
+}
+
+impl Attr {
+  pub fn new(name: &str, value: &str) -> Self {
+    Self {
+      namespace: None,
+      prefix: None,
+      local_name: EcoString::from(name),
+      value: EcoString::from(value),
+    }
+  }
+
+  // 接頭辞がある場合は「接頭辞:ローカル名」
+  pub fn qualified_name(&self) -> EcoString {
+    match &self.prefix {
+      Some(prefix) => ecow::eco_format!("{}:{}", prefix, self.local_name),
+      None => self.local_name.clone(),
+    }
+  }
+}
+
 pub struct Element {
+  // 接頭辞がある場合は「接頭辞:ローカル名」
   pub tag_name: EcoString,
+  pub local_name: EcoString,
+  pub prefix: Option<EcoString>,
+  pub namespace: Namespace,
+  // id・class属性は、attributesにも出現順に入っている
   pub id: RefCell<Option<EcoString>>,
-  pub attributes: RefCell<AttributeMap>,
+  // 出現順を保つため、マップではなく配列で持つ
+  pub attributes: RefCell<AttributeList>,
   pub class_list: RefCell<ClassList>,
+  // template要素の場合のみ、中身を持つDocumentFragment
+  template_contents: RefCell<Option<NodePtr>>,
+  // パーサーがform要素ポインタを使って関連付けたform要素
+  // 循環参照にならないように、弱参照で持つ
+  associated_form: RefCell<Option<WeakTreeNode<DOMNode>>>,
 }
 
 impl Element {
   pub fn new(tag_name: &str) -> Self {
+    Self::new_ns(tag_name, Namespace::Html)
+  }
+
+  pub fn new_ns(local_name: &str, namespace: Namespace) -> Self {
+    Self::new_qualified(local_name, namespace, None)
+  }
+
+  pub fn new_qualified(
+    local_name: &str,
+    namespace: Namespace,
+    prefix: Option<&str>,
+  ) -> Self {
+    let tag_name = match prefix {
+      Some(prefix) => ecow::eco_format!("{}:{}", prefix, local_name),
+      None => EcoString::from(local_name),
+    };
+
     Self {
-      tag_name: EcoString::from(tag_name),
+      tag_name,
+      local_name: EcoString::from(local_name),
+      prefix: prefix.map(EcoString::from),
+      namespace,
       id: RefCell::new(None),
-      attributes: RefCell::new(AttributeMap::default()),
+      attributes: RefCell::new(AttributeList::new()),
       class_list: RefCell::new(ClassList::new()),
+      template_contents: RefCell::new(None),
+      associated_form: RefCell::new(None),
     }
   }
 
@@ -28,14 +94,105 @@ impl Element {
     &self.tag_name
   }
 
+  pub fn local_name(&self) -> &EcoString {
+    &self.local_name
+  }
+
+  pub fn prefix(&self) -> Option<&EcoString> {
+    self.prefix.as_ref()
+  }
+
+  pub fn namespace(&self) -> Namespace {
+    self.namespace
+  }
+
+  pub fn namespace_uri(&self) -> &'static str {
+    self.namespace.url()
+  }
+
+  pub fn is_html(&self) -> bool {
+    self.namespace == Namespace::Html
+  }
+
+  // HTML名前空間の要素で、ローカル名がnamesのいずれかに一致する場合にtrueを返す
   pub fn match_tag_name_in(&self, names: &[&str]) -> bool {
-    names.iter().any(|name| self.tag_name() == *name)
+    self.match_name_in(Namespace::Html, names)
+  }
+
+  pub fn match_name_in(&self, namespace: Namespace, names: &[&str]) -> bool {
+    self.namespace == namespace
+      && names.iter().any(|name| self.local_name() == *name)
   }
 
+  pub fn template_contents(&self) -> Option<NodePtr> {
+    self.template_contents.borrow().clone()
+  }
+
+  pub fn set_template_contents(&self, contents: NodePtr) {
+    *self.template_contents.borrow_mut() = Some(contents);
+  }
+
+  pub fn associated_form(&self) -> Option<NodePtr> {
+    self
+      .associated_form
+      .borrow()
+      .as_ref()
+      .and_then(|form| form.upgrade())
+      .map(NodePtr)
+  }
+
+  pub fn set_associated_form(&self, form: &NodePtr) {
+    *self.associated_form.borrow_mut() = Some(WeakTreeNode::from(&form.0));
+  }
+
+  // https://html.spec.whatwg.org/multipage/forms.html#form-associated-element
+  pub fn is_form_associated(&self) -> bool {
+    self.is_listed() || self.match_tag_name_in(&["img"])
+  }
+
+  // https://html.spec.whatwg.org/multipage/forms.html#category-listed
+  pub fn is_listed(&self) -> bool {
+    self.match_tag_name_in(&[
+      "button", "fieldset", "input", "object", "output", "select", "textarea",
+    ])
+  }
+
+  // 修飾名をキーにした属性のマップ
   pub fn attributes(&self) -> AttributeMap {
+    self
+      .attributes
+      .borrow()
+      .iter()
+      .map(|attr| (attr.qualified_name(), attr.value.clone()))
+      .collect()
+  }
+
+  pub fn attribute_list(&self) -> AttributeList {
     self.attributes.borrow().clone()
   }
 
+  pub fn get_attribute(&self, name: &str) -> Option<EcoString> {
+    self
+      .attributes
+      .borrow()
+      .iter()
+      .find(|attr| attr.qualified_name() == name)
+      .map(|attr| attr.value.clone())
+  }
+
+  pub fn get_attribute_ns(
+    &self,
+    namespace: Option<Namespace>,
+    local_name: &str,
+  ) -> Option<EcoString> {
+    self
+      .attributes
+      .borrow()
+      .iter()
+      .find(|attr| attr.namespace == namespace && attr.local_name == local_name)
+      .map(|attr| attr.value.clone())
+  }
+
   pub fn id(&self) -> Option<EcoString> {
     self.id.borrow().clone()
   }
@@ -45,40 +202,67 @@ impl Element {
   }
 
   pub fn has_attribute(&self, name: &str) -> bool {
-    self.attributes.borrow().contains_key(name)
+    self.get_attribute(name).is_some()
   }
 
+  // id・class属性は、取り出しやすいように別にも持っておく
   pub fn set_attribute(&self, name: &str, value: &str) {
     match name {
       "id" => {
         *self.id.borrow_mut() = Some(EcoString::from(value));
       }
       "class" => {
-        self.class_list.borrow_mut().push(EcoString::from(value));
-      }
-      _ => {
-        self
-          .attributes
-          .borrow_mut()
-          .insert(EcoString::from(name), EcoString::from(value));
+        *self.class_list.borrow_mut() =
+          ClassList::from([EcoString::from(value)]);
       }
+      _ => {}
+    }
+
+    self.set_attribute_node(Attr::new(name, value));
+  }
+
+  pub fn set_attribute_ns(
+    &self,
+    namespace: Namespace,
+    prefix: Option<&str>,
+    local_name: &str,
+    value: &str,
+  ) {
+    self.set_attribute_node(Attr {
+      namespace: Some(namespace),
+      prefix: prefix.map(EcoString::from),
+      local_name: EcoString::from(local_name),
+      value: EcoString::from(value),
+    });
+  }
+
+  // 名前空間とローカル名が同じ属性があれば、値を置き換える
+  fn set_attribute_node(&self, new_attr: Attr) {
+    let mut attributes = self.attributes.borrow_mut();
+
+    let existing = attributes.make_mut().iter_mut().find(|attr| {
+      attr.namespace == new_attr.namespace
+        && attr.local_name == new_attr.local_name
+    });
+
+    match existing {
+      Some(attr) => attr.value = new_attr.value,
+      None => attributes.push(new_attr),
     }
   }
 }
 
 impl core::fmt::Debug for Element {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-    // id, attribute, class_list は空の場合は表示しない
+    // attributesは空の場合は表示しない
+    // id・class属性もattributesに含まれる
     let mut debug_struct = f.debug_struct("Element");
     debug_struct.field("tag_name", &self.tag_name);
-    if let Some(id) = &*self.id.borrow() {
-      debug_struct.field("id", id);
+    if !self.is_html() {
+      debug_struct.field("namespace", &self.namespace);
     }
     if !self.attributes.borrow().is_empty() {
-      debug_struct.field("attributes", &self.attributes.borrow());
-    }
-    if !self.class_list.borrow().is_empty() {
-      debug_struct.field("class_list", &self.class_list.borrow());
+      debug_struct.field("attributes", &self.attributes());
     }
     debug_struct.finish()
   }
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use ecow::EcoString;
+
+use super::element::Element;
+use super::node::NodePtr;
+
+impl NodePtr {
+  // https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#form-owner
+  // フォーム関連要素でない場合はNoneを返す
+  pub fn form_owner(&self) -> Option<NodePtr> {
+    let root = NodePtr(self.root());
+
+    self.form_owner_with(
+      &root,
+      |form_id| {
+        descendants(&root).into_iter().map(|(node, _)| node).find(|node| {
+          node
+            .as_maybe_element()
+            .is_some_and(|el| el.id().as_ref() == Some(form_id))
+        })
+      },
+      || self.find_first_anecestor(|node| is_form(&NodePtr(node))).map(NodePtr),
+    )
+  }
+
+  // https://html.spec.whatwg.org/multipage/forms.html#dom-form-elements
+  // このform要素をフォームオーナーとする、listedカテゴリの要素をツリー順に返す
+  // form要素でない場合は空
+  pub fn form_elements(&self) -> Vec<NodePtr> {
+    if !is_form(self) {
+      return Vec::new();
+    }
+
+    let root = NodePtr(self.root());
+    let nodes = descendants(&root);
+
+    // 要素ごとにツリーを辿ると要素数の2乗になるので、IDから最初の要素への対応を1度だけ作る
+    let mut elements_by_id: HashMap<EcoString, NodePtr> = HashMap::new();
+    for (node, _) in &nodes {
+      if let Some(id) = node.as_maybe_element().and_then(Element::id) {
+        elements_by_id.entry(id).or_insert_with(|| node.clone());
+      }
+    }
+
+    nodes
+      .into_iter()
+      .filter(|(node, _)| {
+        node.as_maybe_element().is_some_and(|element| {
+          element.is_listed() && !is_image_button(element)
+        })
+      })
+      .filter(|(node, form_ancestor)| {
+        node
+          .form_owner_with(
+            &root,
+            |form_id| elements_by_id.get(form_id).cloned(),
+            || form_ancestor.clone(),
+          )
+          .is_some_and(|owner| Rc::ptr_eq(&owner, self))
+      })
+      .map(|(node, _)| node)
+      .collect()
+  }
+
+  // IDから同じツリーで最初の要素を探す方法と、祖先のform要素を探す方法を渡す
+  fn form_owner_with(
+    &self,
+    root: &NodePtr,
+    first_element_with_id: impl Fn(&EcoString) -> Option<NodePtr>,
+    form_ancestor: impl FnOnce() -> Option<NodePtr>,
+  ) -> Option<NodePtr> {
+    let element = self.as_maybe_element()?;
+
+    if !element.is_form_associated() {
+      return None;
+    }
+
+    // form属性がある場合は、同じツリーで最初にそのIDを持つ要素がform要素なら、それがフォームオーナー
+    if let Some(form_id) = element
+      .get_attribute("form")
+      .filter(|_| element.is_listed() && root.is_document())
+    {
+      return first_element_with_id(&form_id).filter(is_form);
+    }
+
+    // パーサーが関連付けたform要素は、同じツリーにある限り祖先でなくてもフォームオーナーになる
+    if let Some(form) =
+      element.associated_form().filter(|form| Rc::ptr_eq(&form.root(), root))
+    {
+      return Some(form);
+    }
+
+    form_ancestor()
+  }
+}
+
+fn is_form(node: &NodePtr) -> bool {
+  node
+    .as_maybe_element()
+    .is_some_and(|element| element.match_tag_name_in(&["form"]))
+}
+
+fn is_image_button(element: &Element) -> bool {
+  element.match_tag_name_in(&["input"])
+    && element
+      .get_attribute("type")
+      .is_some_and(|value| value.eq_ignore_ascii_case("image"))
+}
+
+// rootを除く子孫を、最も近い祖先のform要素と組にしてツリー順に返す
+// 深いツリーでもスタックを使い切らないように、再帰せずに辿る
+fn descendants(root: &NodePtr) -> Vec<(NodePtr, Option<NodePtr>)> {
+  let mut nodes = Vec::new();
+  // 辿っている途中のノードの祖先にあるform要素
+  let mut forms = Vec::new();
+  if is_form(root) {
+    forms.push(root.clone());
+  }
+
+  let mut next = root.first_child().map(NodePtr);
+
+  while let Some(node) = next {
+    nodes.push((node.clone(), forms.last().cloned()));
+
+    if let Some(child) = node.first_child() {
+      if is_form(&node) {
+        forms.push(node);
+      }
+      next = Some(NodePtr(child));
+      continue;
+    }
+
+    next = next_sibling_or_ancestor_sibling(node, root, &mut forms);
+  }
+
+  nodes
+}
+
+// 子のないnodeの次に辿るノードを返す
+// 子をすべて辿り終えたform要素から出るときは、formsから取り除く
+fn next_sibling_or_ancestor_sibling(
+  node: NodePtr,
+  root: &NodePtr,
+  forms: &mut Vec<NodePtr>,
+) -> Option<NodePtr> {
+  let mut current = node;
+
+  loop {
+    if Rc::ptr_eq(&current, root) {
+      return None;
+    }
+    if let Some(sibling) = current.next_sibling() {
+      return Some(NodePtr(sibling));
+    }
+    current = NodePtr(current.parent()?);
+    if is_form(&current) {
+      forms.pop();
+    }
+  }
+}
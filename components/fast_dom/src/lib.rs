@@ -1,11 +1,15 @@
 pub mod document;
 pub mod element;
+pub mod form;
+pub mod namespace;
 pub mod node;
+pub mod span;
 pub mod text;
 pub mod tree;
 
-use document::Document;
+use document::{Document, DocumentType};
 use element::Element;
+use namespace::Namespace;
 
 use node::DOMNode;
 use node::DOMNodeData;
@@ -23,6 +27,32 @@ pub fn create_element(
   NodePtr(TreeNode::new(node))
 }
 
+pub fn create_element_ns(
+  document: WeakTreeNode<DOMNode>,
+  tag_name: &str,
+  namespace: Namespace,
+) -> NodePtr {
+  let node =
+    DOMNode::new(DOMNodeData::Element(Element::new_ns(tag_name, namespace)));
+  node.set_document(document);
+  NodePtr(TreeNode::new(node))
+}
+
+pub fn create_document_fragment(document: WeakTreeNode<DOMNode>) -> NodePtr {
+  let node = DOMNode::new(DOMNodeData::DocumentFragment);
+  node.set_document(document);
+  NodePtr(TreeNode::new(node))
+}
+
+pub fn create_doctype(
+  document: WeakTreeNode<DOMNode>,
+  doctype: DocumentType,
+) -> NodePtr {
+  let node = DOMNode::new(DOMNodeData::DocumentType(doctype));
+  node.set_document(document);
+  NodePtr(TreeNode::new(node))
+}
+
 pub fn create_document() -> NodePtr {
   NodePtr(TreeNode::new(DOMNode::new(DOMNodeData::Document(
     Document::new(),
@@ -0,0 +1,37 @@
+// https://infra.spec.whatwg.org/#namespaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Namespace {
+  #[default]
+  Html,
+  MathMl,
+  Svg,
+  XLink,
+  Xml,
+  Xmlns,
+}
+
+impl Namespace {
+  pub fn url(&self) -> &'static str {
+    match self {
+      Namespace::Html => "http://www.w3.org/1999/xhtml",
+      Namespace::MathMl => "http://www.w3.org/1998/Math/MathML",
+      Namespace::Svg => "http://www.w3.org/2000/svg",
+      Namespace::XLink => "http://www.w3.org/1999/xlink",
+      Namespace::Xml => "http://www.w3.org/XML/1998/namespace",
+      Namespace::Xmlns => "http://www.w3.org/2000/xmlns/",
+    }
+  }
+
+  pub fn from_url(url: &str) -> Option<Self> {
+    [
+      Namespace::Html,
+      Namespace::MathMl,
+      Namespace::Svg,
+      Namespace::XLink,
+      Namespace::Xml,
+      Namespace::Xmlns,
+    ]
+    .into_iter()
+    .find(|namespace| namespace.url() == url)
+  }
+}
@@ -1,12 +1,13 @@
-use std::cell::{Ref, RefCell};
+use std::cell::{Cell, Ref, RefCell};
 use std::ops::Deref;
 
 use ecow::EcoString;
 use re_css::value::property::{Properties, Property};
 use re_css::value::Value;
 
-use super::document::Document;
+use super::document::{Document, DocumentType};
 use super::element::Element;
+use super::span::Span;
 use super::text::Text;
 use super::tree::{TreeNode, WeakTreeNode};
 
@@ -16,12 +17,15 @@ pub struct DOMNode {
   owner_document: RefCell<Option<WeakTreeNode<DOMNode>>>,
   data: Option<DOMNodeData>,
   computed_styles: RefCell<Properties>,
+  span: Cell<Option<Span>>,
 }
 
 pub enum DOMNodeData {
   Element(Element),
   Text(Text),
   Document(Document),
+  DocumentType(DocumentType),
+  DocumentFragment,
   Comment(EcoString),
 }
 
@@ -37,6 +41,7 @@ impl DOMNode {
       owner_document: RefCell::new(None),
       data: None,
       computed_styles: RefCell::new(Properties::default()),
+      span: Cell::new(None),
     }
   }
 
@@ -55,6 +60,16 @@ impl DOMNode {
     }
   }
 
+  // パース時に、このノードの元になった入力中の範囲
+  // 要素の場合は開始タグの範囲を指す
+  pub fn span(&self) -> Option<Span> {
+    self.span.get()
+  }
+
+  pub fn set_span(&self, span: Span) {
+    self.span.set(Some(span));
+  }
+
   pub fn as_maybe_document(&self) -> Option<&Document> {
     match &self.data {
       Some(DOMNodeData::Document(doc)) => Some(doc),
@@ -62,6 +77,13 @@ impl DOMNode {
     }
   }
 
+  pub fn as_maybe_doctype(&self) -> Option<&DocumentType> {
+    match &self.data {
+      Some(DOMNodeData::DocumentType(doctype)) => Some(doctype),
+      _ => None,
+    }
+  }
+
   pub fn as_maybe_element(&self) -> Option<&Element> {
     match &self.data {
       Some(DOMNodeData::Element(element)) => Some(element),
@@ -99,6 +121,10 @@ impl DOMNode {
     self.as_maybe_document().is_some()
   }
 
+  pub fn is_document_fragment(&self) -> bool {
+    matches!(self.data, Some(DOMNodeData::DocumentFragment))
+  }
+
   pub fn is_text(&self) -> bool {
     self.as_maybe_text().is_some()
   }
@@ -116,6 +142,13 @@ impl DOMNode {
   }
 }
 
+impl NodePtr {
+  // Documentの子のうち、DOCTYPEのノードが持つ情報
+  pub fn doctype(&self) -> Option<DocumentType> {
+    self.iterate_children().find_map(|child| child.as_maybe_doctype().cloned())
+  }
+}
+
 impl Clone for NodePtr {
   fn clone(&self) -> Self {
     NodePtr(self.0.clone())
@@ -153,6 +186,10 @@ impl core::fmt::Debug for DOMNodeData {
         write!(f, "Comment({:?})", comment)
       }
       DOMNodeData::Document(_) => write!(f, "Document"),
+      DOMNodeData::DocumentType(doctype) => {
+        write!(f, "DocumentType({:?})", doctype.name)
+      }
+      DOMNodeData::DocumentFragment => write!(f, "DocumentFragment"),
       DOMNodeData::Element(element) => write!(f, "{:?}", element),
     }
   }
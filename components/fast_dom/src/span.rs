@@ -0,0 +1,30 @@
+// 入力中のバイト範囲 [start, end)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+  pub start: usize,
+  pub end: usize,
+}
+
+impl Span {
+  pub fn new(start: usize, end: usize) -> Self {
+    Self { start, end }
+  }
+
+  // 省略されたタグから暗黙に生成された要素など、入力上に対応する範囲がないときに使う
+  pub fn at(offset: usize) -> Self {
+    Self::new(offset, offset)
+  }
+
+  pub fn len(&self) -> usize {
+    self.end - self.start
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.start == self.end
+  }
+
+  // 両方を含む最小の範囲
+  pub fn union(&self, other: Span) -> Span {
+    Span::new(self.start.min(other.start), self.end.max(other.end))
+  }
+}
@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use fast_html::encoding::decode;
+use fast_html::tokenizer::Tokenizer;
+use fast_html::tree_builder::TreeBuilder;
+
+// どんな入力でもpanicせずに文書を返す
+fuzz_target!(|data: &[u8]| {
+  let decoded = decode(data, None);
+
+  let tokenizer = Tokenizer::new(decoded.text.as_bytes());
+  let tree_builder = TreeBuilder::new(tokenizer, fast_dom::create_document());
+
+  let (document, _) = tree_builder.run();
+  assert!(document.first_child().is_some());
+});
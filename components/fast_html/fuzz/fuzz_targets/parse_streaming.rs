@@ -0,0 +1,25 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use fast_html::tokenizer::Tokenizer;
+use fast_html::tree_builder::TreeBuilder;
+
+// 入力をチャンクに分けて渡しても、panicせずに文書を返す
+// 先頭の1バイトをチャンクの大きさに使う
+fuzz_target!(|data: &[u8]| {
+  let Some((&chunk_size, data)) = data.split_first() else {
+    return;
+  };
+  let text = String::from_utf8_lossy(data);
+
+  let mut tree_builder =
+    TreeBuilder::new(Tokenizer::streaming(), fast_dom::create_document());
+
+  for chunk in text.as_bytes().chunks(usize::from(chunk_size).max(1)) {
+    tree_builder.feed(chunk);
+  }
+
+  let (document, _) = tree_builder.finish();
+  assert!(document.first_child().is_some());
+});
@@ -1,12 +1,14 @@
-use super::tokenizer::Tokenizer;
-use super::tree_builder::TreeBuilder;
+use super::encoding::decode;
+use super::error::ParseError;
+use super::options::ParserOptions;
+use super::parser::{HtmlParser, Parser};
+use super::sink::FastDomSink;
 
 use rustc_hash::FxHashMap;
 
-use fast_dom::document::Document;
 use fast_dom::element::Element;
+use fast_dom::namespace::Namespace;
 use fast_dom::node::DOMNode;
-use fast_dom::node::DOMNodeData;
 use fast_dom::node::NodePtr;
 use fast_dom::text::Text;
 use fast_dom::tree::TreeNode;
@@ -16,17 +18,25 @@ use serde_json::json;
 
 /* -------------------------------------------- */
 
-pub fn get_document_from_html(html: &str) -> NodePtr {
-  let target = html.as_bytes();
-
-  let empty_document = NodePtr(TreeNode::new(DOMNode::new(
-    DOMNodeData::Document(Document::new()),
-  )));
+pub fn get_document_from_html(html: &str) -> (NodePtr, Vec<ParseError>) {
+  get_document_from_html_with_options(html, ParserOptions::default())
+}
 
-  let tokenizer = Tokenizer::new(target);
-  let tree_builder = TreeBuilder::new(tokenizer, empty_document);
+pub fn get_document_from_html_with_options(
+  html: &str,
+  options: ParserOptions,
+) -> (NodePtr, Vec<ParseError>) {
+  Parser::<FastDomSink>::with_options(options).parse(html)
+}
 
-  tree_builder.run()
+// エンコーディングを推定してからパースする
+// transport_encodingはHTTPのContent-Typeなどで指定されたcharset
+pub fn get_document_from_bytes(
+  bytes: &[u8],
+  transport_encoding: Option<&str>,
+) -> (NodePtr, Vec<ParseError>) {
+  let decoded = decode(bytes, transport_encoding);
+  get_document_from_html(&decoded.text)
 }
 
 /* print dom tree ----------------------------- */
@@ -65,22 +75,13 @@ fn text_node_to_json(node: &Text) -> serde_json::Value {
 }
 
 fn element_node_to_json(node: &Element) -> serde_json::Value {
-  let mut attributes = node
+  let attributes = node
     .attributes()
     .iter()
     .map(|(key, value)| (String::from(key), String::from(value)))
     .collect::<FxHashMap<String, String>>();
 
-  let class_attribute = node.class_list().borrow().join(" ");
-  if !class_attribute.is_empty() {
-    attributes.insert("class".to_string(), class_attribute);
-  }
-
-  if let Some(id) = node.id().as_ref() {
-    attributes.insert("id".to_string(), id.to_string());
-  }
-
-  if attributes.is_empty() {
+  let mut json = if attributes.is_empty() {
     json!({
       "type": "element",
       "tag": node.tag_name().as_str(),
@@ -91,7 +92,14 @@ fn element_node_to_json(node: &Element) -> serde_json::Value {
       "tag": node.tag_name().as_str(),
       "attributes": attributes,
     })
+  };
+
+  // HTML以外の名前空間の要素のみ、名前空間を出力する
+  if !node.is_html() {
+    json["namespace"] = json!(node.namespace().url());
   }
+
+  json
 }
 
 fn document_node_to_json() -> serde_json::Value {
@@ -123,6 +131,14 @@ fn dom_to_json_core(
 
   if let Some(element_node) = root.as_maybe_element() {
     json = element_node_to_json(element_node);
+
+    // template要素の中身は、childrenとは別にcontentとして出力する
+    if let Some(contents) = element_node.template_contents() {
+      let content = dom_to_json_core(&contents, depth + 1);
+      if let Some(children) = content.get("children") {
+        json["content"] = children.clone();
+      }
+    }
   }
 
   if let Some(comment) = root.as_maybe_comment() {
@@ -136,6 +152,12 @@ fn dom_to_json_core(
     json = document_node_to_json();
   }
 
+  if root.is_document_fragment() {
+    json = json!({
+      "type": "document-fragment",
+    });
+  }
+
   if !children.is_empty() {
     json["children"] = json!(children);
   }
@@ -154,7 +176,11 @@ pub fn dom_to_json_string(document: &NodePtr) -> String {
 pub fn dom_body_to_json(document: &NodePtr) -> serde_json::Value {
   let root = document.0.clone();
 
-  let html = root.first_child().unwrap();
+  // html要素の前に、DOCTYPEやコメントがあることもある
+  let html = root
+    .iterate_children()
+    .find(|child| child.as_maybe_element().is_some())
+    .unwrap();
   let head = html.first_child().unwrap();
   let body = head.next_sibling().unwrap();
 
@@ -164,3 +190,88 @@ pub fn dom_body_to_json(document: &NodePtr) -> serde_json::Value {
 pub fn dom_body_to_json_string(document: &NodePtr) -> String {
   serde_json::to_string_pretty(&dom_body_to_json(document)).unwrap()
 }
+
+/* dom to html5lib tree ----------------------- */
+
+// html5lib形式で、名前空間に属する要素・属性の名前の前に付ける略称
+fn html5lib_namespace_prefix(namespace: Namespace) -> &'static str {
+  match namespace {
+    Namespace::Html => "",
+    Namespace::MathMl => "math ",
+    Namespace::Svg => "svg ",
+    Namespace::XLink => "xlink ",
+    Namespace::Xml => "xml ",
+    Namespace::Xmlns => "xmlns ",
+  }
+}
+
+// https://github.com/html5lib/html5lib-tests/tree/master/tree-construction
+fn html5lib_tree_core(
+  root: &TreeNode<DOMNode>,
+  depth: usize,
+  out: &mut String,
+) {
+  for child in root.iterate_children() {
+    let indent = format!("| {}", "  ".repeat(depth));
+
+    if let Some(element) = child.as_maybe_element() {
+      out.push_str(&format!(
+        "{}<{}{}>\n",
+        indent,
+        html5lib_namespace_prefix(element.namespace()),
+        element.local_name()
+      ));
+
+      let mut attributes = element
+        .attribute_list()
+        .iter()
+        .map(|attr| {
+          let prefix = attr.namespace.map_or("", html5lib_namespace_prefix);
+          (
+            format!("{}{}", prefix, attr.local_name),
+            attr.value.to_string(),
+          )
+        })
+        .collect::<Vec<_>>();
+
+      attributes.sort();
+
+      for (key, value) in attributes {
+        out.push_str(&format!("{}  {}=\"{}\"\n", indent, key, value));
+      }
+
+      if let Some(contents) = element.template_contents() {
+        out.push_str(&format!("{}  content\n", indent));
+        html5lib_tree_core(&contents, depth + 2, out);
+      }
+    }
+
+    if let Some(text) = child.as_maybe_text() {
+      out.push_str(&format!("{}\"{}\"\n", indent, text.get_data()));
+    }
+
+    if let Some(comment) = child.as_maybe_comment() {
+      out.push_str(&format!("{}<!-- {} -->\n", indent, comment));
+    }
+
+    if let Some(doctype) = child.as_maybe_doctype() {
+      if doctype.public_id.is_empty() && doctype.system_id.is_empty() {
+        out.push_str(&format!("{}<!DOCTYPE {}>\n", indent, doctype.name));
+      } else {
+        out.push_str(&format!(
+          "{}<!DOCTYPE {} \"{}\" \"{}\">\n",
+          indent, doctype.name, doctype.public_id, doctype.system_id
+        ));
+      }
+    }
+
+    html5lib_tree_core(&child, depth + 1, out);
+  }
+}
+
+// html5lib-testsのtree-constructionテストと同じ形式で、DOMツリーを文字列にする
+pub fn dom_to_html5lib_tree(document: &NodePtr) -> String {
+  let mut out = String::new();
+  html5lib_tree_core(document, 0, &mut out);
+  out
+}
@@ -0,0 +1,357 @@
+use std::borrow::Cow;
+
+use encoding_rs::{
+  Encoding, EUC_JP, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252,
+  X_USER_DEFINED,
+};
+
+// https://html.spec.whatwg.org/multipage/parsing.html#concept-encoding-confidence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confidence {
+  Tentative,
+  Certain,
+}
+
+#[derive(Debug)]
+pub struct Decoded<'a> {
+  pub text: Cow<'a, str>,
+  pub encoding: &'static Encoding,
+  pub confidence: Confidence,
+}
+
+// prescanで読むのは先頭の1024バイトまで
+const PRESCAN_LIMIT: usize = 1024;
+
+// バイト列の文字エンコーディングを推定し、トークナイザに渡せるUTF-8の文字列にする
+// transport_encodingはContent-Typeのcharsetなど、HTTP層で指定されたラベル
+// 戻り値のテキストはBOMを取り除いたもので、Spanなどのオフセットはこのテキスト上の位置になる
+pub fn decode<'a>(
+  bytes: &'a [u8],
+  transport_encoding: Option<&str>,
+) -> Decoded<'a> {
+  let (encoding, confidence, bom_len) = sniff(bytes, transport_encoding);
+  let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+
+  Decoded {
+    text,
+    encoding,
+    confidence,
+  }
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#encoding-sniffing-algorithm
+// エンコーディング、確信度、BOMの長さを返す
+pub fn sniff(
+  bytes: &[u8],
+  transport_encoding: Option<&str>,
+) -> (&'static Encoding, Confidence, usize) {
+  // BOMはほかのどの指定よりも優先される
+  if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+    return (encoding, Confidence::Certain, bom_len);
+  }
+
+  if let Some(encoding) =
+    transport_encoding.and_then(|label| Encoding::for_label(label.as_bytes()))
+  {
+    return (encoding, Confidence::Certain, 0);
+  }
+
+  if let Some(encoding) = prescan(&bytes[..bytes.len().min(PRESCAN_LIMIT)]) {
+    return (encoding, Confidence::Tentative, 0);
+  }
+
+  (detect(bytes), Confidence::Tentative, 0)
+}
+
+// 手がかりがない場合は、UTF-8として正しく読めるならUTF-8とみなす
+// 次に、日本語のエンコーディングとして正しく読めて、かなを含むならそのエンコーディングにする
+// それ以外は仕様が多くのロケールで既定とするwindows-1252にする
+fn detect(bytes: &[u8]) -> &'static Encoding {
+  if std::str::from_utf8(bytes).is_ok() {
+    return UTF_8;
+  }
+
+  detect_japanese(bytes).unwrap_or(WINDOWS_1252)
+}
+
+// Shift_JISのかなはEUC-JPとしては読めないが、EUC-JPのかなはShift_JISの半角カナとして読めてしまう
+// そのため、EUC-JPを先に試す
+// ラテン文字のwindows-1252の文書がShift_JISの漢字として読めてしまうことがあるので、かなを含む場合に限る
+fn detect_japanese(bytes: &[u8]) -> Option<&'static Encoding> {
+  [EUC_JP, SHIFT_JIS].into_iter().find(|encoding| {
+    encoding
+      .decode_without_bom_handling_and_without_replacement(bytes)
+      .is_some_and(|text| text.chars().any(is_kana))
+  })
+}
+
+// ひらがなと全角のカタカナ
+fn is_kana(c: char) -> bool {
+  matches!(c, '\u{3041}'..='\u{309F}' | '\u{30A0}'..='\u{30FF}')
+}
+
+/* prescan ------------------------------------ */
+
+fn is_whitespace(b: u8) -> bool {
+  matches!(b, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+fn starts_with_ignore_case(
+  bytes: &[u8],
+  position: usize,
+  pattern: &[u8],
+) -> bool {
+  bytes
+    .get(position..position + pattern.len())
+    .is_some_and(|slice| slice.eq_ignore_ascii_case(pattern))
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding
+pub fn prescan(bytes: &[u8]) -> Option<&'static Encoding> {
+  let mut position = 0;
+
+  while position < bytes.len() {
+    if bytes[position..].starts_with(b"<!--") {
+      // "-->"の"--"は"<!--"の"--"と重なってもよい
+      let end = find(bytes, position + 2, b"-->")?;
+      position = end + 3;
+      continue;
+    }
+
+    if starts_with_ignore_case(bytes, position, b"<meta")
+      && bytes.get(position + 5).is_some_and(|&b| is_whitespace(b) || b == b'/')
+    {
+      position += 6;
+      if let Some(encoding) = prescan_meta(bytes, &mut position) {
+        return Some(encoding);
+      }
+      continue;
+    }
+
+    let next = bytes.get(position + 1).copied();
+    let after_next = bytes.get(position + 2).copied();
+
+    let is_tag = bytes[position] == b'<'
+      && (next.is_some_and(|b| b.is_ascii_alphabetic())
+        || (next == Some(b'/')
+          && after_next.is_some_and(|b| b.is_ascii_alphabetic())));
+
+    if is_tag {
+      position += if next == Some(b'/') { 2 } else { 1 };
+
+      while position < bytes.len()
+        && !is_whitespace(bytes[position])
+        && bytes[position] != b'>'
+      {
+        position += 1;
+      }
+
+      while get_attribute(bytes, &mut position).is_some() {}
+      continue;
+    }
+
+    if bytes[position] == b'<' && matches!(next, Some(b'!' | b'/' | b'?')) {
+      position = find(bytes, position, b">")? + 1;
+      continue;
+    }
+
+    position += 1;
+  }
+
+  None
+}
+
+fn prescan_meta(
+  bytes: &[u8],
+  position: &mut usize,
+) -> Option<&'static Encoding> {
+  let mut attribute_names: Vec<Vec<u8>> = Vec::new();
+  let mut got_pragma = false;
+  let mut need_pragma = None;
+  let mut charset = None;
+
+  while let Some((name, value)) = get_attribute(bytes, position) {
+    if attribute_names.contains(&name) {
+      continue;
+    }
+
+    match name.as_slice() {
+      b"http-equiv" if value == b"content-type" => {
+        got_pragma = true;
+      }
+      b"content" if charset.is_none() => {
+        if let Some(encoding) =
+          extract_encoding_from_meta(&value).and_then(Encoding::for_label)
+        {
+          charset = Some(Some(encoding));
+          need_pragma = Some(true);
+        }
+      }
+      // contentから先に文字コードを得ている場合は、そちらを使う
+      b"charset" if charset.is_none() => {
+        charset = Some(Encoding::for_label(&value));
+        need_pragma = Some(false);
+      }
+      _ => {}
+    }
+
+    attribute_names.push(name);
+  }
+
+  match need_pragma {
+    None => return None,
+    Some(true) if !got_pragma => return None,
+    _ => {}
+  }
+
+  // ラベルが不明な場合は、このmeta要素を無視する
+  let encoding = charset.flatten()?;
+
+  if encoding == UTF_16BE || encoding == UTF_16LE {
+    return Some(UTF_8);
+  }
+
+  if encoding == X_USER_DEFINED {
+    return Some(WINDOWS_1252);
+  }
+
+  Some(encoding)
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#concept-get-attributes-when-sniffing
+// 属性名と属性値は小文字にして返す
+fn get_attribute(
+  bytes: &[u8],
+  position: &mut usize,
+) -> Option<(Vec<u8>, Vec<u8>)> {
+  let current = |position: usize| bytes.get(position).copied();
+
+  while current(*position).is_some_and(|b| is_whitespace(b) || b == b'/') {
+    *position += 1;
+  }
+
+  match current(*position) {
+    None | Some(b'>') => return None,
+    _ => {}
+  }
+
+  let mut name = Vec::new();
+  let mut value = Vec::new();
+
+  // 属性名
+  loop {
+    match current(*position)? {
+      b'=' if !name.is_empty() => {
+        *position += 1;
+        break;
+      }
+      b if is_whitespace(b) => {
+        while current(*position).is_some_and(is_whitespace) {
+          *position += 1;
+        }
+        if current(*position)? != b'=' {
+          return Some((name, value));
+        }
+        *position += 1;
+        break;
+      }
+      b'/' | b'>' => return Some((name, value)),
+      b => {
+        name.push(b.to_ascii_lowercase());
+        *position += 1;
+      }
+    }
+  }
+
+  while current(*position).is_some_and(is_whitespace) {
+    *position += 1;
+  }
+
+  // 属性値
+  match current(*position)? {
+    quote @ (b'"' | b'\'') => {
+      *position += 1;
+      loop {
+        let b = current(*position)?;
+        *position += 1;
+        if b == quote {
+          return Some((name, value));
+        }
+        value.push(b.to_ascii_lowercase());
+      }
+    }
+    b'>' => Some((name, value)),
+    _ => loop {
+      match current(*position)? {
+        b if is_whitespace(b) || b == b'>' => return Some((name, value)),
+        b => {
+          value.push(b.to_ascii_lowercase());
+          *position += 1;
+        }
+      }
+    },
+  }
+}
+
+// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#algorithm-for-extracting-a-character-encoding-from-a-meta-element
+fn extract_encoding_from_meta(content: &[u8]) -> Option<&[u8]> {
+  let mut position = 0;
+
+  loop {
+    position = find_ignore_case(content, position, b"charset")? + 7;
+
+    while content.get(position).is_some_and(|&b| is_whitespace(b)) {
+      position += 1;
+    }
+
+    if content.get(position) == Some(&b'=') {
+      position += 1;
+      break;
+    }
+  }
+
+  while content.get(position).is_some_and(|&b| is_whitespace(b)) {
+    position += 1;
+  }
+
+  match content.get(position)? {
+    &quote @ (b'"' | b'\'') => {
+      let start = position + 1;
+      let end = find(content, start, &[quote])?;
+      Some(&content[start..end])
+    }
+    _ => {
+      let start = position;
+      let end = content[start..]
+        .iter()
+        .position(|&b| is_whitespace(b) || b == b';')
+        .map_or(content.len(), |len| start + len);
+
+      if start == end {
+        None
+      } else {
+        Some(&content[start..end])
+      }
+    }
+  }
+}
+
+fn find(bytes: &[u8], from: usize, pattern: &[u8]) -> Option<usize> {
+  bytes
+    .get(from..)?
+    .windows(pattern.len())
+    .position(|window| window == pattern)
+    .map(|i| from + i)
+}
+
+fn find_ignore_case(
+  bytes: &[u8],
+  from: usize,
+  pattern: &[u8],
+) -> Option<usize> {
+  bytes
+    .get(from..)?
+    .windows(pattern.len())
+    .position(|window| window.eq_ignore_ascii_case(pattern))
+    .map(|i| from + i)
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+
+// 入力中の位置
+// lineとcolumnは1始まりで、columnは文字単位で数える
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourceLocation {
+  pub offset: usize,
+  pub line: usize,
+  pub column: usize,
+}
+
+impl SourceLocation {
+  pub fn start() -> Self {
+    Self {
+      offset: 0,
+      line: 1,
+      column: 1,
+    }
+  }
+
+  pub fn from_offset(data: &[u8], offset: usize) -> Self {
+    Self::start().advance_to(data, offset)
+  }
+
+  // selfからoffsetまで読み進めた位置を返す
+  // 後戻りする場合は先頭から数え直す
+  pub fn advance_to(self, data: &[u8], offset: usize) -> Self {
+    let offset = offset.min(data.len());
+
+    if offset < self.offset {
+      return Self::from_offset(data, offset);
+    }
+
+    self.advance(&data[self.offset..offset])
+  }
+
+  // selfからbytesを読み進めた位置を返す
+  pub fn advance(self, bytes: &[u8]) -> Self {
+    // UTF-8の継続バイトは数えない
+    let count_chars =
+      |bytes: &[u8]| bytes.iter().filter(|&&b| (b & 0xC0) != 0x80).count();
+
+    let mut location = self;
+
+    match bytes.iter().rposition(|&b| b == b'\n') {
+      Some(last_line_feed) => {
+        location.line += bytes.iter().filter(|&&b| b == b'\n').count();
+        location.column = 1 + count_chars(&bytes[last_line_feed + 1..]);
+      }
+      None => location.column += count_chars(bytes),
+    }
+
+    location.offset += bytes.len();
+    location
+  }
+}
+
+macro_rules! parse_errors {
+  ($($variant:ident => $code:literal,)*) => {
+    // https://html.spec.whatwg.org/multipage/parsing.html#parse-errors
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ParseError {
+      $($variant(SourceLocation),)*
+    }
+
+    impl ParseError {
+      pub fn code(&self) -> &'static str {
+        match self {
+          $(ParseError::$variant(_) => $code,)*
+        }
+      }
+
+      pub fn location(&self) -> SourceLocation {
+        match self {
+          $(ParseError::$variant(location))|* => *location,
+        }
+      }
+    }
+  };
+}
+
+parse_errors! {
+  AbruptClosingOfEmptyComment => "abrupt-closing-of-empty-comment",
+  AbruptDoctypePublicIdentifier => "abrupt-doctype-public-identifier",
+  AbruptDoctypeSystemIdentifier => "abrupt-doctype-system-identifier",
+  AbsenceOfDigitsInNumericCharacterReference =>
+    "absence-of-digits-in-numeric-character-reference",
+  CdataInHtmlContent => "cdata-in-html-content",
+  CharacterReferenceOutsideUnicodeRange =>
+    "character-reference-outside-unicode-range",
+  ControlCharacterInInputStream => "control-character-in-input-stream",
+  ControlCharacterReference => "control-character-reference",
+  DuplicateAttribute => "duplicate-attribute",
+  EndTagWithAttributes => "end-tag-with-attributes",
+  EndTagWithTrailingSolidus => "end-tag-with-trailing-solidus",
+  EofBeforeTagName => "eof-before-tag-name",
+  EofInCdata => "eof-in-cdata",
+  EofInComment => "eof-in-comment",
+  EofInDoctype => "eof-in-doctype",
+  EofInScriptHtmlCommentLikeText => "eof-in-script-html-comment-like-text",
+  EofInTag => "eof-in-tag",
+  IncorrectlyClosedComment => "incorrectly-closed-comment",
+  IncorrectlyOpenedComment => "incorrectly-opened-comment",
+  InvalidCharacterSequenceAfterDoctypeName =>
+    "invalid-character-sequence-after-doctype-name",
+  InvalidFirstCharacterOfTagName => "invalid-first-character-of-tag-name",
+  MissingAttributeValue => "missing-attribute-value",
+  MissingDoctypeName => "missing-doctype-name",
+  MissingDoctypePublicIdentifier => "missing-doctype-public-identifier",
+  MissingDoctypeSystemIdentifier => "missing-doctype-system-identifier",
+  MissingEndTagName => "missing-end-tag-name",
+  MissingQuoteBeforeDoctypePublicIdentifier =>
+    "missing-quote-before-doctype-public-identifier",
+  MissingQuoteBeforeDoctypeSystemIdentifier =>
+    "missing-quote-before-doctype-system-identifier",
+  MissingSemicolonAfterCharacterReference =>
+    "missing-semicolon-after-character-reference",
+  MissingWhitespaceAfterDoctypePublicKeyword =>
+    "missing-whitespace-after-doctype-public-keyword",
+  MissingWhitespaceAfterDoctypeSystemKeyword =>
+    "missing-whitespace-after-doctype-system-keyword",
+  MissingWhitespaceBeforeDoctypeName => "missing-whitespace-before-doctype-name",
+  MissingWhitespaceBetweenAttributes => "missing-whitespace-between-attributes",
+  MissingWhitespaceBetweenDoctypePublicAndSystemIdentifiers =>
+    "missing-whitespace-between-doctype-public-and-system-identifiers",
+  NestedComment => "nested-comment",
+  NoncharacterCharacterReference => "noncharacter-character-reference",
+  NoncharacterInInputStream => "noncharacter-in-input-stream",
+  NonVoidHtmlElementStartTagWithTrailingSolidus =>
+    "non-void-html-element-start-tag-with-trailing-solidus",
+  NullCharacterReference => "null-character-reference",
+  SurrogateCharacterReference => "surrogate-character-reference",
+  UnexpectedCharacterAfterDoctypeSystemIdentifier =>
+    "unexpected-character-after-doctype-system-identifier",
+  UnexpectedCharacterInAttributeName =>
+    "unexpected-character-in-attribute-name",
+  UnexpectedCharacterInUnquotedAttributeValue =>
+    "unexpected-character-in-unquoted-attribute-value",
+  UnexpectedEqualsSignBeforeAttributeName =>
+    "unexpected-equals-sign-before-attribute-name",
+  UnexpectedNullCharacter => "unexpected-null-character",
+  UnexpectedQuestionMarkInsteadOfTagName =>
+    "unexpected-question-mark-instead-of-tag-name",
+  UnexpectedSolidusInTag => "unexpected-solidus-in-tag",
+  UnknownNamedCharacterReference => "unknown-named-character-reference",
+  // 入力はUTF-8で受け取るので、surrogate-in-input-streamは起こらない
+
+  // ツリー構築段階のエラーには仕様上のコードがないので、html5lib-testsに倣って名付ける
+  EndTagTooEarly => "end-tag-too-early",
+  ExpectedDoctype => "expected-doctype",
+  UnexpectedCharacterInTable => "unexpected-character-in-table",
+  UnexpectedComment => "unexpected-comment",
+  UnexpectedDoctype => "unexpected-doctype",
+  UnexpectedEndTag => "unexpected-end-tag",
+  UnexpectedEof => "unexpected-eof",
+  UnexpectedStartTag => "unexpected-start-tag",
+  UnexpectedText => "unexpected-text",
+  UnknownDoctype => "unknown-doctype",
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let location = self.location();
+    write!(
+      f,
+      "{} at {}:{}",
+      self.code(),
+      location.line,
+      location.column
+    )
+  }
+}
@@ -0,0 +1,64 @@
+use super::error::ParseError;
+use super::options::ParserOptions;
+use super::tokenizer::Tokenizer;
+use super::tree_builder::TreeBuilder;
+
+use fast_dom::node::DOMNode;
+use fast_dom::node::NodePtr;
+use fast_dom::tree::TreeNode;
+use fast_dom::tree::WeakTreeNode;
+
+// https://html.spec.whatwg.org/multipage/parsing.html#parsing-html-fragments
+// context_elementの子として解析した結果を、DocumentFragmentにまとめて返す
+// innerHTMLへの代入と同じように、既存のDOMに挿入するための断片を作れる
+pub fn parse_fragment(
+  context_element: &NodePtr,
+  html: &str,
+) -> (NodePtr, Vec<ParseError>) {
+  parse_fragment_with_options(context_element, html, ParserOptions::default())
+}
+
+pub fn parse_fragment_with_options(
+  context_element: &NodePtr,
+  html: &str,
+  options: ParserOptions,
+) -> (NodePtr, Vec<ParseError>) {
+  let tokenizer = Tokenizer::new(html.as_bytes());
+  let tree_builder = TreeBuilder::new_fragment_with_options(
+    tokenizer,
+    fast_dom::create_document(),
+    context_element.clone(),
+    options,
+  );
+  let (document, errors) = tree_builder.run();
+
+  // 解析に使った文書はここで捨てるので、コンテキスト要素の文書に移す
+  let owner_document = match context_element.owner_document() {
+    Some(owner) => WeakTreeNode::from(&owner),
+    None => WeakTreeNode::from(&document.0),
+  };
+
+  let fragment = fast_dom::create_document_fragment(owner_document.clone());
+  let root = document.first_child().unwrap();
+  root.transfer_children_to(fragment.0.clone());
+
+  for child in fragment.iterate_children() {
+    adopt(&child, &owner_document);
+  }
+
+  (fragment, errors)
+}
+
+fn adopt(node: &TreeNode<DOMNode>, document: &WeakTreeNode<DOMNode>) {
+  node.set_document(document.clone());
+
+  if let Some(contents) =
+    node.as_maybe_element().and_then(|element| element.template_contents())
+  {
+    adopt(&contents, document);
+  }
+
+  for child in node.iterate_children() {
+    adopt(&child, document);
+  }
+}
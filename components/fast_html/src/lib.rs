@@ -1,3 +1,11 @@
 pub mod debugger;
+pub mod encoding;
+pub mod error;
+pub mod fragment;
+pub mod line_index;
+pub mod options;
+pub mod parser;
+pub mod serializer;
+pub mod sink;
 pub mod tokenizer;
 pub mod tree_builder;
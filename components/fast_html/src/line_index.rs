@@ -0,0 +1,49 @@
+use fast_dom::span::Span;
+
+use crate::error::SourceLocation;
+
+// バイトオフセットから行・列を引くための索引
+// 各行の先頭位置を持っておき、二分探索で行を求める
+pub struct LineIndex<'a> {
+  source: &'a [u8],
+  line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+  pub fn new(source: &'a str) -> Self {
+    let source = source.as_bytes();
+
+    let line_starts = std::iter::once(0)
+      .chain(
+        source
+          .iter()
+          .enumerate()
+          .filter(|(_, &b)| b == b'\n')
+          .map(|(i, _)| i + 1),
+      )
+      .collect();
+
+    Self {
+      source,
+      line_starts,
+    }
+  }
+
+  // 行の先頭からoffsetまでは、SourceLocation::advance_toで数える
+  pub fn location(&self, offset: usize) -> SourceLocation {
+    let offset = offset.min(self.source.len());
+
+    let line = self.line_starts.partition_point(|&start| start <= offset);
+    let line_start = SourceLocation {
+      offset: self.line_starts[line - 1],
+      line,
+      column: 1,
+    };
+
+    line_start.advance_to(self.source, offset)
+  }
+
+  pub fn span_location(&self, span: Span) -> (SourceLocation, SourceLocation) {
+    (self.location(span.start), self.location(span.end))
+  }
+}
@@ -0,0 +1,34 @@
+// パーサーの動作と、使うリソースの上限の指定
+// TreeBuilder::with_optionsで渡すと、トークナイザにも同じ指定が渡される
+// TreeBuilderはすべての分岐を実装していて、どの指定でもpanicしないので、
+// 未実装の分岐をパースエラーにするnever_panicのような指定は持たない
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserOptions {
+  // https://html.spec.whatwg.org/multipage/parsing.html#scripting-flag
+  // trueの場合、noscript要素の中身は解析せずにテキストとして扱う
+  pub scripting: bool,
+  // https://html.spec.whatwg.org/multipage/iframe-embed-object.html#an-iframe-srcdoc-document
+  // trueの場合、DOCTYPEがなくても後方互換モードにしない
+  pub iframe_srcdoc: bool,
+  // DOMツリーの要素の深さの上限(html要素が1、template要素の中身はtemplate要素の子として数える)
+  // 超える場合は、それ以上深くせずに、上限の深さの要素の兄弟として挿入する
+  // テキストとコメントは、上限の深さの要素の中にも挿入する
+  pub max_tree_depth: Option<usize>,
+  // 1つのタグが持てる属性の数の上限
+  // 超えた分の属性は捨てる
+  pub max_attributes: Option<usize>,
+  // falseの場合、パースエラーを記録しない
+  pub collect_errors: bool,
+}
+
+impl Default for ParserOptions {
+  fn default() -> Self {
+    Self {
+      scripting: false,
+      iframe_srcdoc: false,
+      max_tree_depth: None,
+      max_attributes: None,
+      collect_errors: true,
+    }
+  }
+}
@@ -0,0 +1,57 @@
+use std::marker::PhantomData;
+
+use super::error::ParseError;
+use super::options::ParserOptions;
+use super::sink::TreeSink;
+use super::tokenizer::Tokenizer;
+use super::tree_builder::TreeBuilder;
+
+// 文字列のHTMLを解析して、文書とパースエラーを返す
+// 組み立てる先のDOMによらず、同じ呼び出し方で使える
+pub trait HtmlParser {
+  type Output;
+
+  fn parse(&self, html: &str) -> (Self::Output, Vec<ParseError>);
+}
+
+// 空の文書から始めるsinkで、同じ木構築の実装を使って解析する
+pub struct Parser<S> {
+  options: ParserOptions,
+  sink: PhantomData<S>,
+}
+
+impl<S: TreeSink + Default> Default for Parser<S> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<S: TreeSink + Default> Parser<S> {
+  pub fn new() -> Self {
+    Self::with_options(ParserOptions::default())
+  }
+
+  pub fn with_options(options: ParserOptions) -> Self {
+    Self {
+      options,
+      sink: PhantomData,
+    }
+  }
+}
+
+impl<S: TreeSink + Default> HtmlParser for Parser<S> {
+  type Output = S::Output;
+
+  fn parse(&self, html: &str) -> (S::Output, Vec<ParseError>) {
+    parse_with_sink(html, S::default(), self.options.clone())
+  }
+}
+
+pub fn parse_with_sink<S: TreeSink>(
+  html: &str,
+  sink: S,
+  options: ParserOptions,
+) -> (S::Output, Vec<ParseError>) {
+  let tokenizer = Tokenizer::new(html.as_bytes());
+  TreeBuilder::with_sink(tokenizer, sink, options).run()
+}
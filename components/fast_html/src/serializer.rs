@@ -0,0 +1,238 @@
+use fast_dom::element::{Attr, Element};
+use fast_dom::namespace::Namespace;
+use fast_dom::node::{DOMNode, NodePtr};
+use fast_dom::tree::TreeNode;
+
+use super::options::ParserOptions;
+
+// https://html.spec.whatwg.org/multipage/parsing.html#serializes-as-void
+const VOID_ELEMENTS: [&str; 18] = [
+  "area", "base", "basefont", "bgsound", "br", "col", "embed", "frame", "hr",
+  "img", "input", "keygen", "link", "meta", "param", "source", "track", "wbr",
+];
+
+// 子のテキストをエスケープせずに出力する要素
+// noscriptは、スクリプトが有効な場合のみエスケープしない
+const RAW_TEXT_ELEMENTS: [&str; 7] = [
+  "style",
+  "script",
+  "xmp",
+  "iframe",
+  "noembed",
+  "noframes",
+  "plaintext",
+];
+
+/* -------------------------------------------- */
+
+// https://html.spec.whatwg.org/multipage/parsing.html#serialising-html-fragments
+// nodeの子をHTMLに変換する(innerHTMLの取得に相当)
+pub fn serialize_children(node: &NodePtr) -> String {
+  serialize_children_with_options(node, ParserOptions::default())
+}
+
+// 解析したときと同じoptionsを渡すと、スクリプトの有効・無効に合わせてnoscriptの中身を出力する
+pub fn serialize_children_with_options(
+  node: &NodePtr,
+  options: ParserOptions,
+) -> String {
+  let mut out = String::new();
+  serialize_children_core(node, options.scripting, &mut out);
+  out
+}
+
+// node自身も含めてHTMLに変換する(outerHTMLの取得に相当)
+// 文書とDocumentFragmentは、子だけを出力する
+pub fn serialize(node: &NodePtr) -> String {
+  serialize_with_options(node, ParserOptions::default())
+}
+
+pub fn serialize_with_options(
+  node: &NodePtr,
+  options: ParserOptions,
+) -> String {
+  let mut out = String::new();
+
+  if node.is_document() || node.is_document_fragment() {
+    serialize_children_core(node, options.scripting, &mut out);
+  } else {
+    serialize_steps(
+      vec![Step::Node(node.0.clone())],
+      options.scripting,
+      &mut out,
+    );
+  }
+
+  out
+}
+
+/* -------------------------------------------- */
+
+// 深いツリーでもスタックを使い切らないように、再帰せずに出力する
+enum Step {
+  Node(TreeNode<DOMNode>),
+  EndTag(String),
+}
+
+fn serialize_children_core(
+  node: &TreeNode<DOMNode>,
+  scripting: bool,
+  out: &mut String,
+) {
+  let mut steps = Vec::new();
+  push_children(node, &mut steps);
+  serialize_steps(steps, scripting, out);
+}
+
+fn serialize_steps(mut steps: Vec<Step>, scripting: bool, out: &mut String) {
+  while let Some(step) = steps.pop() {
+    match step {
+      Step::Node(node) => serialize_node(&node, &mut steps, scripting, out),
+      Step::EndTag(tag_name) => {
+        out.push_str("</");
+        out.push_str(&tag_name);
+        out.push('>');
+      }
+    }
+  }
+}
+
+// 先頭の子から取り出せるように、逆順に積む
+fn push_children(node: &TreeNode<DOMNode>, steps: &mut Vec<Step>) {
+  if let Some(element) = node.as_maybe_element() {
+    if is_void_element(element) {
+      return;
+    }
+
+    // template要素の場合は、中身のDocumentFragmentの子を出力する
+    if let Some(contents) = element.template_contents() {
+      return push_children(&contents, steps);
+    }
+  }
+
+  let mut child = node.last_child();
+  while let Some(node) = child {
+    child = node.prev_sibling();
+    steps.push(Step::Node(node));
+  }
+}
+
+// 要素の場合は開始タグだけを出力し、終了タグと子は後で出力するように積む
+fn serialize_node(
+  node: &TreeNode<DOMNode>,
+  steps: &mut Vec<Step>,
+  scripting: bool,
+  out: &mut String,
+) {
+  if let Some(element) = node.as_maybe_element() {
+    let tag_name = serialized_tag_name(element);
+
+    out.push('<');
+    out.push_str(tag_name);
+
+    for (name, value) in serialized_attributes(element) {
+      out.push(' ');
+      out.push_str(&name);
+      out.push_str("=\"");
+      out.push_str(&escape(&value, true));
+      out.push('"');
+    }
+
+    out.push('>');
+
+    if is_void_element(element) {
+      return;
+    }
+
+    steps.push(Step::EndTag(tag_name.to_string()));
+    push_children(node, steps);
+    return;
+  }
+
+  if let Some(text) = node.as_maybe_text() {
+    let data = text.get_data();
+
+    let is_raw_text = node.parent().is_some_and(|parent| {
+      parent.as_maybe_element().is_some_and(|element| {
+        element.match_tag_name_in(&RAW_TEXT_ELEMENTS)
+          || (scripting && element.match_tag_name_in(&["noscript"]))
+      })
+    });
+
+    if is_raw_text {
+      out.push_str(&data);
+    } else {
+      out.push_str(&escape(&data, false));
+    }
+    return;
+  }
+
+  if let Some(comment) = node.as_maybe_comment() {
+    out.push_str("<!--");
+    out.push_str(comment);
+    out.push_str("-->");
+    return;
+  }
+
+  if let Some(doctype) = node.as_maybe_doctype() {
+    out.push_str("<!DOCTYPE ");
+    out.push_str(&doctype.name);
+    out.push('>');
+  }
+}
+
+/* -------------------------------------------- */
+
+fn is_void_element(element: &Element) -> bool {
+  element.match_tag_name_in(&VOID_ELEMENTS)
+}
+
+// HTML・SVG・MathMLの要素はローカル名、それ以外は修飾名を使う
+fn serialized_tag_name(element: &Element) -> &str {
+  match element.namespace() {
+    Namespace::Html | Namespace::Svg | Namespace::MathMl => {
+      element.local_name()
+    }
+    _ => element.tag_name(),
+  }
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#attribute's-serialized-name
+fn serialized_attribute_name(attr: &Attr) -> String {
+  match attr.namespace {
+    None => attr.local_name.to_string(),
+    Some(Namespace::Xml) => format!("xml:{}", attr.local_name),
+    Some(Namespace::Xmlns) if attr.local_name == "xmlns" => "xmlns".to_string(),
+    Some(Namespace::Xmlns) => format!("xmlns:{}", attr.local_name),
+    Some(Namespace::XLink) => format!("xlink:{}", attr.local_name),
+    Some(_) => attr.qualified_name().to_string(),
+  }
+}
+
+// 属性は、入力に現れた順に出力する
+fn serialized_attributes(element: &Element) -> Vec<(String, String)> {
+  element
+    .attribute_list()
+    .iter()
+    .map(|attr| (serialized_attribute_name(attr), attr.value.to_string()))
+    .collect()
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#escapingString
+// 属性値でも、<と>はエスケープする
+fn escape(str: &str, attribute_mode: bool) -> String {
+  let mut escaped = String::with_capacity(str.len());
+
+  for c in str.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '\u{00A0}' => escaped.push_str("&nbsp;"),
+      '"' if attribute_mode => escaped.push_str("&quot;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      _ => escaped.push(c),
+    }
+  }
+
+  escaped
+}
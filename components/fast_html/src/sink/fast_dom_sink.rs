@@ -0,0 +1,236 @@
+use std::rc::Rc;
+
+use ecow::{EcoString, EcoVec};
+
+use fast_dom::document::{DocumentType, QuirksMode};
+use fast_dom::namespace::Namespace;
+use fast_dom::node::{DOMNode, DOMNodeData, NodePtr};
+use fast_dom::span::Span;
+use fast_dom::text::Text;
+use fast_dom::tree::{TreeNode, WeakTreeNode};
+
+use crate::tokenizer::token::Attribute;
+
+use super::{NodeHandle, TreeSink};
+
+impl NodeHandle for NodePtr {
+  fn same_node(&self, other: &Self) -> bool {
+    Rc::ptr_eq(self, other)
+  }
+
+  fn local_name(&self) -> &str {
+    self.as_maybe_element().map_or("", |element| element.local_name().as_str())
+  }
+
+  fn namespace(&self) -> Namespace {
+    self
+      .as_maybe_element()
+      .map_or(Namespace::Html, |element| element.namespace())
+  }
+
+  fn is_element(&self) -> bool {
+    DOMNode::is_element(self)
+  }
+
+  fn is_text(&self) -> bool {
+    DOMNode::is_text(self)
+  }
+
+  fn is_document(&self) -> bool {
+    DOMNode::is_document(self)
+  }
+}
+
+// fast_domのツリーを組み立てる
+pub struct FastDomSink {
+  document: NodePtr,
+}
+
+impl Default for FastDomSink {
+  fn default() -> Self {
+    Self::new(fast_dom::create_document())
+  }
+}
+
+impl FastDomSink {
+  // documentには空のDocumentを渡す
+  pub fn new(document: NodePtr) -> Self {
+    Self { document }
+  }
+
+  fn owner_document(&self) -> WeakTreeNode<DOMNode> {
+    WeakTreeNode::from(&self.document.0)
+  }
+}
+
+impl TreeSink for FastDomSink {
+  type Handle = NodePtr;
+  type Output = NodePtr;
+
+  /* document ----------------------------------- */
+
+  fn document(&self) -> NodePtr {
+    self.document.clone()
+  }
+
+  fn finish(self) -> NodePtr {
+    self.document
+  }
+
+  // DOCTYPEの前にあるコメントとの順番を保つため、ノードとして追加する
+  fn set_doctype(
+    &self,
+    name: EcoString,
+    public_identifier: EcoString,
+    system_identifier: EcoString,
+  ) {
+    let doctype = fast_dom::create_doctype(
+      self.owner_document(),
+      DocumentType::new(name, public_identifier, system_identifier),
+    );
+    self.document.append_child(doctype.0);
+  }
+
+  fn set_quirks_mode(&self, mode: QuirksMode) {
+    if let Some(doc) = self.document.as_maybe_document() {
+      doc.set_mode(mode);
+    }
+  }
+
+  /* create ------------------------------------- */
+
+  fn create_element(&self, local_name: &str, namespace: Namespace) -> NodePtr {
+    let document = self.owner_document();
+    let element =
+      fast_dom::create_element_ns(document.clone(), local_name, namespace);
+
+    // template要素の子は、メインのツリーではなくDocumentFragmentに入れる
+    if namespace == Namespace::Html && local_name == "template" {
+      let contents = fast_dom::create_document_fragment(document);
+      element.as_element().set_template_contents(contents);
+    }
+
+    element
+  }
+
+  fn create_comment(&self, data: EcoString) -> NodePtr {
+    let comment = TreeNode::new(DOMNode::new(DOMNodeData::Comment(data)));
+    comment.set_document(self.owner_document());
+    NodePtr(comment)
+  }
+
+  fn create_text(&self, data: EcoString) -> NodePtr {
+    let text = TreeNode::new(DOMNode::new(DOMNodeData::Text(Text::new(data))));
+    text.set_document(self.owner_document());
+    NodePtr(text)
+  }
+
+  /* attribute ---------------------------------- */
+
+  fn attributes(&self, element: &NodePtr) -> EcoVec<Attribute> {
+    element
+      .as_element()
+      .attribute_list()
+      .iter()
+      .map(|attr| Attribute {
+        name: attr.qualified_name(),
+        value: attr.value.clone(),
+      })
+      .collect()
+  }
+
+  fn get_attribute(&self, element: &NodePtr, name: &str) -> Option<EcoString> {
+    element.as_element().get_attribute(name)
+  }
+
+  fn set_attribute(&self, element: &NodePtr, name: &str, value: &str) {
+    element.as_element().set_attribute(name, value);
+  }
+
+  fn set_attribute_ns(
+    &self,
+    element: &NodePtr,
+    namespace: Namespace,
+    prefix: Option<&str>,
+    local_name: &str,
+    value: &str,
+  ) {
+    element.as_element().set_attribute_ns(namespace, prefix, local_name, value);
+  }
+
+  /* tree --------------------------------------- */
+
+  fn parent(&self, node: &NodePtr) -> Option<NodePtr> {
+    node.parent().map(NodePtr)
+  }
+
+  fn last_child(&self, node: &NodePtr) -> Option<NodePtr> {
+    node.last_child().map(NodePtr)
+  }
+
+  fn prev_sibling(&self, node: &NodePtr) -> Option<NodePtr> {
+    node.prev_sibling().map(NodePtr)
+  }
+
+  fn template_contents(&self, element: &NodePtr) -> Option<NodePtr> {
+    element.as_maybe_element().and_then(|element| element.template_contents())
+  }
+
+  fn append(&self, parent: &NodePtr, child: NodePtr) {
+    parent.append_child(child.0);
+  }
+
+  fn insert_before(&self, parent: &NodePtr, sibling: &NodePtr, child: NodePtr) {
+    parent.insert_before(child.0, Some(sibling.0.clone()));
+  }
+
+  fn remove_from_parent(&self, node: &NodePtr) {
+    node.detach();
+  }
+
+  fn reparent_children(&self, from: &NodePtr, to: &NodePtr) {
+    from.transfer_children_to(to.0.clone());
+  }
+
+  /* text --------------------------------------- */
+
+  fn text_data(&self, text: &NodePtr) -> EcoString {
+    text.as_text().get_data()
+  }
+
+  fn set_text_data(&self, text: &NodePtr, data: EcoString) {
+    text.as_text().set_data(data);
+  }
+
+  /* optional ----------------------------------- */
+
+  fn associate_with_form(
+    &self,
+    element: &NodePtr,
+    form: &NodePtr,
+    intended_parent: &NodePtr,
+  ) {
+    let el = element.as_element();
+
+    if !el.is_form_associated() {
+      return;
+    }
+
+    // form属性がある場合は、パースの後でIDから探す
+    if el.is_listed() && el.has_attribute("form") {
+      return;
+    }
+
+    if Rc::ptr_eq(&intended_parent.root(), &form.root()) {
+      el.set_associated_form(form);
+    }
+  }
+
+  fn set_span(&self, node: &NodePtr, span: Span) {
+    node.set_span(span);
+  }
+
+  fn span(&self, node: &NodePtr) -> Option<Span> {
+    node.span()
+  }
+}
@@ -0,0 +1,170 @@
+mod fast_dom_sink;
+
+pub use self::fast_dom_sink::FastDomSink;
+
+use ecow::{EcoString, EcoVec};
+
+use fast_dom::document::QuirksMode;
+use fast_dom::namespace::Namespace;
+use fast_dom::span::Span;
+
+use crate::tokenizer::token::Attribute;
+
+// TreeBuilderが組み立てるノードへの参照
+// 木構築の分岐に必要な、要素の名前と名前空間だけを問い合わせる
+pub trait NodeHandle: Clone {
+  // 同じノードを指しているかどうか
+  fn same_node(&self, other: &Self) -> bool;
+
+  // 要素でない場合は空文字列
+  fn local_name(&self) -> &str;
+
+  // 要素でない場合はHTML名前空間
+  fn namespace(&self) -> Namespace;
+
+  fn is_element(&self) -> bool;
+
+  fn is_text(&self) -> bool;
+
+  fn is_document(&self) -> bool;
+
+  fn is_html(&self) -> bool {
+    self.is_element() && self.namespace() == Namespace::Html
+  }
+
+  // HTML名前空間の要素で、ローカル名がnamesのいずれかに一致する場合にtrueを返す
+  fn match_tag_name_in(&self, names: &[&str]) -> bool {
+    self.match_name_in(Namespace::Html, names)
+  }
+
+  fn match_name_in(&self, namespace: Namespace, names: &[&str]) -> bool {
+    self.is_element()
+      && self.namespace() == namespace
+      && names.contains(&self.local_name())
+  }
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#tree-construction
+// TreeBuilderが木を組み立てるときに使う操作
+// 実装ごとに、組み立てる先のDOMを変えられる
+pub trait TreeSink {
+  type Handle: NodeHandle;
+  // 構築が終わったときに返すもの
+  type Output;
+
+  /* document ----------------------------------- */
+
+  // 組み立てる先のDocument
+  fn document(&self) -> Self::Handle;
+
+  fn finish(self) -> Self::Output;
+
+  fn set_doctype(
+    &self,
+    name: EcoString,
+    public_identifier: EcoString,
+    system_identifier: EcoString,
+  );
+
+  fn set_quirks_mode(&self, mode: QuirksMode);
+
+  /* create ------------------------------------- */
+
+  // template要素の場合は、中身を入れるDocumentFragmentも作る
+  fn create_element(
+    &self,
+    local_name: &str,
+    namespace: Namespace,
+  ) -> Self::Handle;
+
+  fn create_comment(&self, data: EcoString) -> Self::Handle;
+
+  fn create_text(&self, data: EcoString) -> Self::Handle;
+
+  /* attribute ---------------------------------- */
+
+  // 要素が持つ属性を、修飾名と値の組で返す
+  fn attributes(&self, element: &Self::Handle) -> EcoVec<Attribute>;
+
+  fn get_attribute(
+    &self,
+    element: &Self::Handle,
+    name: &str,
+  ) -> Option<EcoString>;
+
+  fn set_attribute(&self, element: &Self::Handle, name: &str, value: &str);
+
+  // 名前空間を持たないDOMでは、修飾名の属性として扱う
+  #[allow(unused_variables)]
+  fn set_attribute_ns(
+    &self,
+    element: &Self::Handle,
+    namespace: Namespace,
+    prefix: Option<&str>,
+    local_name: &str,
+    value: &str,
+  ) {
+    match prefix {
+      Some(prefix) => self.set_attribute(
+        element,
+        &ecow::eco_format!("{}:{}", prefix, local_name),
+        value,
+      ),
+      None => self.set_attribute(element, local_name, value),
+    }
+  }
+
+  /* tree --------------------------------------- */
+
+  fn parent(&self, node: &Self::Handle) -> Option<Self::Handle>;
+
+  fn last_child(&self, node: &Self::Handle) -> Option<Self::Handle>;
+
+  fn prev_sibling(&self, node: &Self::Handle) -> Option<Self::Handle>;
+
+  // template要素でない場合や、DocumentFragmentを持たないDOMではNone
+  fn template_contents(&self, element: &Self::Handle) -> Option<Self::Handle>;
+
+  // 親がある場合は、そこから切り離してから追加する
+  fn append(&self, parent: &Self::Handle, child: Self::Handle);
+
+  fn insert_before(
+    &self,
+    parent: &Self::Handle,
+    sibling: &Self::Handle,
+    child: Self::Handle,
+  );
+
+  fn remove_from_parent(&self, node: &Self::Handle);
+
+  // fromの子をすべて、順番を保ったままtoの末尾に移す
+  fn reparent_children(&self, from: &Self::Handle, to: &Self::Handle);
+
+  /* text --------------------------------------- */
+
+  fn text_data(&self, text: &Self::Handle) -> EcoString;
+
+  fn set_text_data(&self, text: &Self::Handle, data: EcoString);
+
+  /* optional ----------------------------------- */
+
+  // form要素ポインタが指すform要素を、フォームオーナーとして関連付ける
+  // intended_parentは、elementを挿入しようとしている位置の親
+  #[allow(unused_variables)]
+  fn associate_with_form(
+    &self,
+    element: &Self::Handle,
+    form: &Self::Handle,
+    intended_parent: &Self::Handle,
+  ) {
+  }
+
+  // 入力中の範囲を記録しないDOMでは何もしない
+  #[allow(unused_variables)]
+  fn set_span(&self, node: &Self::Handle, span: Span) {}
+
+  #[allow(unused_variables)]
+  fn span(&self, node: &Self::Handle) -> Option<Span> {
+    None
+  }
+}
@@ -2239,3 +2239,55 @@ pub static ENTITIES: Lazy<FxHashMap<&str, (u32, u32)>> = Lazy::new(|| {
   .cloned()
   .collect()
 });
+
+// 最も長い参照名の長さ
+// 参照名を探すときに、これより先は読まなくてよい
+pub static LONGEST_ENTITY_NAME: Lazy<usize> =
+  Lazy::new(|| ENTITIES.keys().map(|name| name.len()).max().unwrap_or(0));
+
+// 数値文字参照で指定されたC1制御文字を置き換える文字
+// https://html.spec.whatwg.org/multipage/parsing.html#numeric-character-reference-end-state
+const C1_REPLACEMENTS: [(u32, u32); 27] = [
+  (0x80, 0x20AC),
+  (0x82, 0x201A),
+  (0x83, 0x0192),
+  (0x84, 0x201E),
+  (0x85, 0x2026),
+  (0x86, 0x2020),
+  (0x87, 0x2021),
+  (0x88, 0x02C6),
+  (0x89, 0x2030),
+  (0x8A, 0x0160),
+  (0x8B, 0x2039),
+  (0x8C, 0x0152),
+  (0x8E, 0x017D),
+  (0x91, 0x2018),
+  (0x92, 0x2019),
+  (0x93, 0x201C),
+  (0x94, 0x201D),
+  (0x95, 0x2022),
+  (0x96, 0x2013),
+  (0x97, 0x2014),
+  (0x98, 0x02DC),
+  (0x99, 0x2122),
+  (0x9A, 0x0161),
+  (0x9B, 0x203A),
+  (0x9C, 0x0153),
+  (0x9E, 0x017E),
+  (0x9F, 0x0178),
+];
+
+pub fn replace_c1_control(code: u32) -> Option<u32> {
+  C1_REPLACEMENTS.iter().find(|(from, _)| *from == code).map(|(_, to)| *to)
+}
+
+pub fn is_noncharacter(code: u32) -> bool {
+  (0xFDD0..=0xFDEF).contains(&code)
+    || (code <= 0x10FFFF && code & 0xFFFE == 0xFFFE)
+}
+
+// C0制御文字とU+007F〜U+009Fのうち、ASCIIの空白文字でないもの
+pub fn is_control_except_whitespace(code: u32) -> bool {
+  let is_control = code <= 0x1F || (0x7F..=0x9F).contains(&code);
+  is_control && !matches!(code, 0x09 | 0x0A | 0x0C | 0x0D | 0x20)
+}
@@ -10,11 +10,20 @@ use std::collections::VecDeque;
 use std::str::from_utf8;
 
 use self::byte_string::*;
-use self::entities::ENTITIES;
+use self::entities::{
+  is_control_except_whitespace, is_noncharacter, replace_c1_control, ENTITIES,
+  LONGEST_ENTITY_NAME,
+};
 use self::state::State;
-use self::stream::Stream;
+use self::stream::{Stream, StreamSlice};
 use self::token::Attribute;
 use self::token::Token;
+use self::token::TokenMark;
+
+use crate::error::{ParseError, SourceLocation};
+use crate::options::ParserOptions;
+
+use fast_dom::span::Span;
 
 use log::{debug, trace, warn};
 
@@ -23,14 +32,54 @@ use ecow::EcoVec;
 
 const REPLACEMENT_CHARACTER: char = '\u{FFFD}';
 
+#[derive(Clone, Copy)]
+enum DoctypeIdentifier {
+  Public,
+  System,
+}
+
 pub struct Tokenizer<'a> {
   state: State,
   return_state: Option<State>,
   stream: Stream<'a, u8>,
-  output: VecDeque<Token>,
+  output: VecDeque<(Token, Span)>,
   current_token: Option<Token>,
+  current_span: Span,
+  last_emitted_end: usize,
   last_emitted_start_tag: Option<Token>,
+  // 入力待ちで発行を保留している、テキストの連なりの開始位置
+  text_run_start: Option<usize>,
   tmp_buffer: EcoVec<u8>,
+  character_reference_code: u32,
+  allow_cdata: bool,
+  errors: Vec<ParseError>,
+  last_location: SourceLocation,
+  // 制御文字と非文字を調べ終えた位置
+  checked_location: SourceLocation,
+  // 捨てずに残している入力の、先頭の位置
+  first_location: SourceLocation,
+  incomplete_char: Vec<u8>,
+  // 巻き戻すときのために、ステップ中に置き換えたトークンと消したtmp_bufferを取っておく
+  replaced_token: Option<Option<Token>>,
+  cleared_tmp_buffer: Option<EcoVec<u8>>,
+  max_attributes: Option<usize>,
+  collect_errors: bool,
+}
+
+// 入力待ちで巻き戻すときに復元する状態
+// 組み立て中のトークンとtmp_bufferは、複製せずに長さだけを覚えておく
+struct Checkpoint {
+  state: State,
+  return_state: Option<State>,
+  idx: usize,
+  current_token: Option<TokenMark>,
+  current_span: Span,
+  last_emitted_end: usize,
+  last_emitted_start_tag: Option<Token>,
+  text_run_start: Option<usize>,
+  tmp_buffer_len: usize,
+  character_reference_code: u32,
+  errors_len: usize,
 }
 
 impl<'a> Tokenizer<'a> {
@@ -41,113 +90,395 @@ impl<'a> Tokenizer<'a> {
       stream: Stream::new(data),
       output: VecDeque::new(),
       current_token: None,
+      current_span: Span::default(),
+      last_emitted_end: 0,
       last_emitted_start_tag: None,
+      text_run_start: None,
       tmp_buffer: EcoVec::new(),
+      character_reference_code: 0,
+      allow_cdata: false,
+      errors: Vec::new(),
+      last_location: SourceLocation::start(),
+      checked_location: SourceLocation::start(),
+      first_location: SourceLocation::start(),
+      incomplete_char: Vec::new(),
+      replaced_token: None,
+      cleared_tmp_buffer: None,
+      max_attributes: None,
+      collect_errors: true,
     }
   }
 
+  // トークナイザに関係する指定だけを取り込む
+  pub fn set_options(&mut self, options: &ParserOptions) {
+    self.max_attributes = options.max_attributes;
+    self.collect_errors = options.collect_errors;
+  }
+
+  // CDATAセクションを許可するかどうか
+  // 調整済み現在ノードがHTML名前空間にない場合のみ、ツリー構築器から許可される
+  pub fn set_allow_cdata(&mut self, allow: bool) {
+    self.allow_cdata = allow;
+  }
+
+  // 直前に出力された開始タグを設定する
+  // RCDATAなどの状態から始める場合に、適切な終了タグの判定に使われる
+  pub fn set_last_start_tag(&mut self, tag_name: &str) {
+    self.last_emitted_start_tag = Some(Token::new_start_tag_of(tag_name));
+  }
+
+  // 次のトークンと、その入力中の範囲を返す
+  pub fn next_token_with_span(&mut self) -> (Token, Span) {
+    let token = self.next_token();
+    (token, self.current_span)
+  }
+
+  // 直前にnext_tokenが返したトークンの、入力中の範囲
+  pub fn current_span(&self) -> Span {
+    self.current_span
+  }
+
   pub fn next_token(&mut self) -> Token {
+    self
+      .try_next_token()
+      .expect("Tokenizer needs more input. Call finish() after the last feed()")
+  }
+
+  // 入力が足りずにトークンを確定できない場合はNoneを返す
+  pub fn try_next_token(&mut self) -> Option<Token> {
+    self.check_input_stream();
+
     if !self.output.is_empty() {
-      return self.output.pop_front().unwrap();
+      return Some(self.pop_token());
     }
 
-    loop {
-      let token = match self.state {
-        State::Data => self.process_data_state(),
-        State::TagOpen => self.process_tag_open_state(),
-        State::TagName => self.process_tag_name_state(),
-        State::EndTagOpen => self.process_end_tag_open_state(),
-        State::SelfClosingStartTag => {
-          self.process_self_closing_start_tag_state()
-        }
-        State::BeforeAttributeName => {
-          self.process_before_attribute_name_state()
-        }
-        State::AttributeName => self.process_attribute_name_state(),
-        State::AfterAttributeName => self.process_after_attribute_name_state(),
-        State::BeforeAttributeValue => {
-          self.process_before_attribute_value_state()
-        }
-        State::AttributeValueDoubleQuoted => {
-          self.process_attribute_value_double_quoted_state()
-        }
-        State::AttributeValueSingleQuoted => {
-          self.process_attribute_value_single_quoted_state()
-        }
-        State::AttributeValueUnQuoted => {
-          self.process_attribute_value_unquoted_state()
-        }
-        State::AfterAttributeValueQuoted => {
-          self.process_after_attribute_value_quoted_state()
-        }
-        State::MarkupDeclarationOpen => {
-          self.process_markup_declaration_open_state()
-        }
-        State::DOCTYPE => self.process_doctype_state(),
-        State::BeforeDOCTYPEName => self.process_before_doctype_name_state(),
-        State::DOCTYPEName => self.process_doctype_name_state(),
-        State::AfterDOCTYPEName => self.process_after_doctype_name_state(),
-        State::BogusDOCTYPE => self.process_bogus_doctype_state(),
-        State::RAWTEXT => self.process_rawtext_state(),
-        State::RAWTEXTLessThanSign => {
-          self.process_rawtext_less_than_sign_state()
-        }
-        State::RAWTEXTEndTagOpen => self.process_rawtext_end_tag_open_state(),
-        State::RAWTEXTEndTagName => self.process_rawtext_end_tag_name_state(),
-        State::RCDATA => self.process_rcdata_state(),
-        State::RCDATALessThanSign => self.process_rcdata_less_than_sign_state(),
-        State::RCDATAEndTagOpen => self.process_rcdata_end_tag_open_state(),
-        State::RCDATAEndTagName => self.process_rcdata_end_tag_name_state(),
-        State::CommentStart => self.process_comment_start_state(),
-        State::CommentStartDash => self.process_comment_start_dash_state(),
-        State::Comment => self.process_comment_state(),
-        State::CommentLessThanSign => {
-          self.process_comment_less_than_sign_state()
+    if self.stream.is_complete() {
+      loop {
+        if let Some(token) = self.step() {
+          return Some(token);
         }
-        State::CommentLessThanSignBang => {
-          self.process_comment_less_than_sign_bang_state()
-        }
-        State::CommentLessThanSignBangDash => {
-          self.process_comment_less_than_sign_bang_dash_state()
-        }
-        State::CommentLessThanSignBangDashDash => {
-          self.process_comment_less_than_sign_bang_dash_dash_state()
-        }
-        State::CommentEndDash => self.process_comment_end_dash_state(),
-        State::CommentEnd => self.process_comment_end_state(),
-        State::CommentEndBang => self.process_comment_end_bang_state(),
-        State::BogusComment => self.process_bogus_comment_state(),
-        State::CharacterReference => self.process_character_reference_state(),
-        State::NamedCharacterReference => {
-          self.process_named_character_reference_state()
-        }
-        State::AmbiguousAmpersand => self.process_ambiguous_ampersand_state(),
-        State::NumericCharacterReference => {
-          self.process_numeric_character_reference_state()
-        }
-        State::HexadecimalCharacterReferenceStart => {
-          self.process_hexadecimal_character_reference_start_state()
-        }
-        State::HexadecimalCharacterReference => {
-          self.process_hexadecimal_character_reference_state()
-        }
-        State::DecimalCharacterReferenceStart => {
-          self.process_decimal_character_reference_start_state()
-        }
-        State::DecimalCharacterReference => {
-          self.process_decimal_character_reference_state()
-        }
-        State::NumericCharacterReferenceEnd => {
-          self.process_numeric_character_reference_end_state()
-        }
-      };
+      }
+    }
+
+    // 入力の末尾に達したら、そのステップの前まで巻き戻して続きの入力を待つ
+    // 各状態は読める分だけ読み進めてから待つので、読み直すのは末尾の数バイトで済む
+    // 途切れたテキストは続きと合わせて発行するので(emit_text_run)、
+    // チャンクの区切り方によってトークンの分かれ方やパースエラーは変わらない
+    loop {
+      if self.stream.is_waiting() {
+        return None;
+      }
+
+      let checkpoint = self.checkpoint();
+      let token = self.step();
+
+      if self.stream.reached_end() {
+        self.restore(checkpoint);
+        return None;
+      }
 
       if let Some(token) = token {
-        return token;
+        return Some(token);
+      }
+
+      // 次のステップで巻き戻しても消えないように、確定したトークンを先に返す
+      if !self.output.is_empty() {
+        return Some(self.pop_token());
+      }
+    }
+  }
+
+  pub fn try_next_token_with_span(&mut self) -> Option<(Token, Span)> {
+    let token = self.try_next_token()?;
+    Some((token, self.current_span))
+  }
+
+  /* streaming ---------------------------------- */
+
+  // 入力を少しずつ与えるトークナイザを作る
+  // feedで入力を追加し、最後にfinishを呼ぶ
+  pub fn streaming() -> Self {
+    let mut tokenizer = Self::new(&[]);
+    tokenizer.stream = Stream::incomplete();
+    tokenizer
+  }
+
+  pub fn feed(&mut self, chunk: &[u8]) {
+    self.discard_consumed_input();
+
+    let mut bytes = std::mem::take(&mut self.incomplete_char);
+    bytes.extend_from_slice(chunk);
+
+    // チャンクの境界で分断されたUTF-8の文字は、続きが届くまで取っておく
+    let valid_len = match from_utf8(&bytes) {
+      Err(error) if error.error_len().is_none() => error.valid_up_to(),
+      _ => bytes.len(),
+    };
+
+    self.incomplete_char = bytes.split_off(valid_len);
+    self.stream.push(&bytes);
+  }
+
+  pub fn finish(&mut self) {
+    let rest = std::mem::take(&mut self.incomplete_char);
+    if !rest.is_empty() {
+      self.stream.push(&rest);
+    }
+    self.stream.complete();
+  }
+
+  // 読み終えた入力は、位置を数えるのにも使わないので捨てる
+  // これから発行するトークンの範囲は、last_emitted_endより後になる
+  fn discard_consumed_input(&mut self) {
+    let until = self.stream.idx.min(self.last_emitted_end);
+    self.first_location = self.location_at(until);
+    self.stream.discard(until);
+  }
+
+  fn checkpoint(&mut self) -> Checkpoint {
+    self.stream.clear_reached_end();
+    self.replaced_token = None;
+    self.cleared_tmp_buffer = None;
+
+    Checkpoint {
+      state: self.state.clone(),
+      return_state: self.return_state.clone(),
+      idx: self.stream.idx,
+      current_token: self.current_token.as_ref().map(Token::mark),
+      current_span: self.current_span,
+      last_emitted_end: self.last_emitted_end,
+      last_emitted_start_tag: self.last_emitted_start_tag.clone(),
+      text_run_start: self.text_run_start,
+      tmp_buffer_len: self.tmp_buffer.len(),
+      character_reference_code: self.character_reference_code,
+      errors_len: self.errors.len(),
+    }
+  }
+
+  fn restore(&mut self, checkpoint: Checkpoint) {
+    if let Some(token) = self.replaced_token.take() {
+      self.current_token = token;
+    }
+    if let (Some(token), Some(mark)) =
+      (self.current_token.as_mut(), checkpoint.current_token)
+    {
+      token.rewind(mark);
+    }
+
+    if let Some(tmp_buffer) = self.cleared_tmp_buffer.take() {
+      self.tmp_buffer = tmp_buffer;
+    }
+    self.tmp_buffer.truncate(checkpoint.tmp_buffer_len);
+
+    self.state = checkpoint.state;
+    self.return_state = checkpoint.return_state;
+    self.stream.idx = checkpoint.idx;
+    self.output.clear();
+    self.current_span = checkpoint.current_span;
+    self.last_emitted_end = checkpoint.last_emitted_end;
+    self.last_emitted_start_tag = checkpoint.last_emitted_start_tag;
+    self.text_run_start = checkpoint.text_run_start;
+    self.character_reference_code = checkpoint.character_reference_code;
+    self.errors.truncate(checkpoint.errors_len);
+  }
+
+  /* -------------------------------------------- */
+
+  fn step(&mut self) -> Option<Token> {
+    match self.state {
+      State::Data => self.process_data_state(),
+      State::TagOpen => self.process_tag_open_state(),
+      State::TagName => self.process_tag_name_state(),
+      State::EndTagOpen => self.process_end_tag_open_state(),
+      State::SelfClosingStartTag => self.process_self_closing_start_tag_state(),
+      State::BeforeAttributeName => self.process_before_attribute_name_state(),
+      State::AttributeName => self.process_attribute_name_state(),
+      State::AfterAttributeName => self.process_after_attribute_name_state(),
+      State::BeforeAttributeValue => {
+        self.process_before_attribute_value_state()
+      }
+      State::AttributeValueDoubleQuoted => {
+        self.process_attribute_value_double_quoted_state()
+      }
+      State::AttributeValueSingleQuoted => {
+        self.process_attribute_value_single_quoted_state()
+      }
+      State::AttributeValueUnQuoted => {
+        self.process_attribute_value_unquoted_state()
+      }
+      State::AfterAttributeValueQuoted => {
+        self.process_after_attribute_value_quoted_state()
+      }
+      State::MarkupDeclarationOpen => {
+        self.process_markup_declaration_open_state()
+      }
+      State::DOCTYPE => self.process_doctype_state(),
+      State::BeforeDOCTYPEName => self.process_before_doctype_name_state(),
+      State::DOCTYPEName => self.process_doctype_name_state(),
+      State::AfterDOCTYPEName => self.process_after_doctype_name_state(),
+      State::AfterDOCTYPEPublicKeyword => {
+        self.process_after_doctype_public_keyword_state()
+      }
+      State::BeforeDOCTYPEPublicIdentifier => {
+        self.process_before_doctype_public_identifier_state()
+      }
+      State::DOCTYPEPublicIdentifierDoubleQuoted => {
+        self.process_doctype_public_identifier_double_quoted_state()
+      }
+      State::DOCTYPEPublicIdentifierSingleQuoted => {
+        self.process_doctype_public_identifier_single_quoted_state()
+      }
+      State::AfterDOCTYPEPublicIdentifier => {
+        self.process_after_doctype_public_identifier_state()
+      }
+      State::BetweenDOCTYPEPublicAndSystemIdentifiers => {
+        self.process_between_doctype_public_and_system_identifiers_state()
+      }
+      State::AfterDOCTYPESystemKeyword => {
+        self.process_after_doctype_system_keyword_state()
+      }
+      State::BeforeDOCTYPESystemIdentifier => {
+        self.process_before_doctype_system_identifier_state()
+      }
+      State::DOCTYPESystemIdentifierDoubleQuoted => {
+        self.process_doctype_system_identifier_double_quoted_state()
+      }
+      State::DOCTYPESystemIdentifierSingleQuoted => {
+        self.process_doctype_system_identifier_single_quoted_state()
+      }
+      State::AfterDOCTYPESystemIdentifier => {
+        self.process_after_doctype_system_identifier_state()
+      }
+      State::BogusDOCTYPE => self.process_bogus_doctype_state(),
+      State::RAWTEXT => self.process_rawtext_state(),
+      State::RAWTEXTLessThanSign => self.process_rawtext_less_than_sign_state(),
+      State::RAWTEXTEndTagOpen => self.process_rawtext_end_tag_open_state(),
+      State::RAWTEXTEndTagName => self.process_rawtext_end_tag_name_state(),
+      State::RCDATA => self.process_rcdata_state(),
+      State::RCDATALessThanSign => self.process_rcdata_less_than_sign_state(),
+      State::RCDATAEndTagOpen => self.process_rcdata_end_tag_open_state(),
+      State::RCDATAEndTagName => self.process_rcdata_end_tag_name_state(),
+      State::ScriptData => self.process_script_data_state(),
+      State::ScriptDataLessThanSign => {
+        self.process_script_data_less_than_sign_state()
+      }
+      State::ScriptDataEndTagOpen => {
+        self.process_script_data_end_tag_open_state()
+      }
+      State::ScriptDataEndTagName => {
+        self.process_script_data_end_tag_name_state()
+      }
+      State::ScriptDataEscapeStart => {
+        self.process_script_data_escape_start_state()
+      }
+      State::ScriptDataEscapeStartDash => {
+        self.process_script_data_escape_start_dash_state()
+      }
+      State::ScriptDataEscaped => self.process_script_data_escaped_state(),
+      State::ScriptDataEscapedDash => {
+        self.process_script_data_escaped_dash_state()
+      }
+      State::ScriptDataEscapedDashDash => {
+        self.process_script_data_escaped_dash_dash_state()
+      }
+      State::ScriptDataEscapedLessThanSign => {
+        self.process_script_data_escaped_less_than_sign_state()
+      }
+      State::ScriptDataEscapedEndTagOpen => {
+        self.process_script_data_escaped_end_tag_open_state()
+      }
+      State::ScriptDataEscapedEndTagName => {
+        self.process_script_data_escaped_end_tag_name_state()
+      }
+      State::ScriptDataDoubleEscapeStart => {
+        self.process_script_data_double_escape_start_state()
+      }
+      State::ScriptDataDoubleEscaped => {
+        self.process_script_data_double_escaped_state()
+      }
+      State::ScriptDataDoubleEscapedDash => {
+        self.process_script_data_double_escaped_dash_state()
+      }
+      State::ScriptDataDoubleEscapedDashDash => {
+        self.process_script_data_double_escaped_dash_dash_state()
+      }
+      State::ScriptDataDoubleEscapedLessThanSign => {
+        self.process_script_data_double_escaped_less_than_sign_state()
+      }
+      State::ScriptDataDoubleEscapeEnd => {
+        self.process_script_data_double_escape_end_state()
+      }
+      State::PLAINTEXT => self.process_plaintext_state(),
+      State::CommentStart => self.process_comment_start_state(),
+      State::CommentStartDash => self.process_comment_start_dash_state(),
+      State::Comment => self.process_comment_state(),
+      State::CommentLessThanSign => self.process_comment_less_than_sign_state(),
+      State::CommentLessThanSignBang => {
+        self.process_comment_less_than_sign_bang_state()
+      }
+      State::CommentLessThanSignBangDash => {
+        self.process_comment_less_than_sign_bang_dash_state()
+      }
+      State::CommentLessThanSignBangDashDash => {
+        self.process_comment_less_than_sign_bang_dash_dash_state()
+      }
+      State::CommentEndDash => self.process_comment_end_dash_state(),
+      State::CommentEnd => self.process_comment_end_state(),
+      State::CommentEndBang => self.process_comment_end_bang_state(),
+      State::BogusComment => self.process_bogus_comment_state(),
+      State::CDATASection => self.process_cdata_section_state(),
+      State::CDATASectionBracket => self.process_cdata_section_bracket_state(),
+      State::CDATASectionEnd => self.process_cdata_section_end_state(),
+      State::CharacterReference => self.process_character_reference_state(),
+      State::NamedCharacterReference => {
+        self.process_named_character_reference_state()
+      }
+      State::AmbiguousAmpersand => self.process_ambiguous_ampersand_state(),
+      State::NumericCharacterReference => {
+        self.process_numeric_character_reference_state()
+      }
+      State::HexadecimalCharacterReferenceStart => {
+        self.process_hexadecimal_character_reference_start_state()
+      }
+      State::HexadecimalCharacterReference => {
+        self.process_hexadecimal_character_reference_state()
+      }
+      State::DecimalCharacterReferenceStart => {
+        self.process_decimal_character_reference_start_state()
+      }
+      State::DecimalCharacterReference => {
+        self.process_decimal_character_reference_state()
+      }
+      State::NumericCharacterReferenceEnd => {
+        self.process_numeric_character_reference_end_state()
       }
     }
   }
 
+  // 現在読んでいる位置
+  pub fn current_location(&mut self) -> SourceLocation {
+    self.location_at(self.stream.idx)
+  }
+
+  pub fn location_at(&mut self, offset: usize) -> SourceLocation {
+    // 直前に計算した位置から数えることで、毎回先頭から走査するのを避ける
+    // 後戻りする場合は、残している入力の先頭から数え直す
+    if offset < self.last_location.offset {
+      self.last_location = self.first_location;
+    }
+
+    let bytes = self.stream.range(self.last_location.offset, offset);
+    self.last_location = self.last_location.advance(bytes);
+    self.last_location
+  }
+
+  // これまでに発生したパースエラーを、入力中の位置順に取り出す
+  pub fn take_errors(&mut self) -> Vec<ParseError> {
+    let mut errors = std::mem::take(&mut self.errors);
+    errors.sort_by_key(|error| error.location().offset);
+    errors
+  }
+
   /* -------------------------------------------- */
 
   pub fn switch_to(&mut self, state: State) {
@@ -161,11 +492,6 @@ impl<'a> Tokenizer<'a> {
     self.state = state;
   }
 
-  fn switch_to_return_state(&mut self) {
-    let state = self.return_state.take().unwrap();
-    self.switch_to(state);
-  }
-
   fn reconsume_in_return_state(&mut self) {
     let state = self.return_state.take().unwrap();
     self.reconsume_in(state);
@@ -176,10 +502,10 @@ impl<'a> Tokenizer<'a> {
   fn process_data_state(&mut self) -> Option<Token> {
     let bytes = self.read_to_oneof(&[b'<', b'&', b'\0']);
 
-    trace!("-- Data: {}", bytes_to_string(bytes));
+    trace!("-- Data: {}", bytes_to_string(&bytes));
 
-    if !bytes.is_empty() {
-      return Some(self.emit_text(bytes));
+    if !bytes.is_empty() || self.text_run_start.is_some() {
+      return self.emit_text_run(bytes.len());
     }
 
     // read_currentに進む前にEOFチェック
@@ -199,8 +525,10 @@ impl<'a> Tokenizer<'a> {
         self.switch_to(State::CharacterReference);
       }
       b'\0' => {
-        warn!("unexpected-null-character");
-        return Some(self.emit_text(bytes));
+        // NULL文字はそのまま発行し、ツリー構築段階で扱いを決める
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.skip(1);
+        return Some(self.emit_char('\0'));
       }
       _ => {
         // noop
@@ -229,17 +557,17 @@ impl<'a> Tokenizer<'a> {
         self.switch_to(State::EndTagOpen);
       }
       b'?' => {
-        warn!("unexpected-question-mark-instead-of-tag-name");
+        self.parse_error(ParseError::UnexpectedQuestionMarkInsteadOfTagName);
         self.new_token(Token::new_comment(""));
         self.reconsume_in(State::BogusComment);
       }
       _ if self.stream.is_eof() => {
-        warn!("eof-before-tag-name");
+        self.parse_error(ParseError::EofBeforeTagName);
         self.will_emit(Token::new_text("<"));
         return Some(self.emit_eof());
       }
       _ => {
-        warn!("invalid-first-character-of-tag-name");
+        self.parse_error(ParseError::InvalidFirstCharacterOfTagName);
         self.will_emit(Token::new_text("<"));
         self.reconsume_in(State::Data);
       }
@@ -252,15 +580,18 @@ impl<'a> Tokenizer<'a> {
     let bytes =
       self.read_to_oneof(&[b'/', b'>', b'\0', b'\t', b'\n', b' ', b'\x0C']);
 
-    trace!("-- TagName: {}", bytes_to_string(bytes));
+    trace!("-- TagName: {}", bytes_to_string(&bytes));
 
-    if bytes.iter().all(|&b| b.is_ascii_alphanumeric()) {
-      self.set_tag_name(bytes);
+    // annotation-xmlのように、英数字以外を含むタグ名もある
+    self.concat_to_tag_name(&bytes.to_ascii_lowercase());
+
+    if self.stream.is_waiting() {
+      return None;
     }
 
     // read_currentに進む前にEOFチェック
     if self.stream.is_eof() {
-      warn!("eof-in-tag");
+      self.parse_error(ParseError::EofInTag);
       return Some(self.emit_eof());
     }
 
@@ -275,8 +606,9 @@ impl<'a> Tokenizer<'a> {
         return Some(self.emit_current_token());
       }
       b'\0' => {
-        warn!("unexpected-null-character");
+        self.parse_error(ParseError::UnexpectedNullCharacter);
         self.append_replacement_char_to_tag_name();
+        self.skip(1);
       }
       b if b.is_ascii_whitespace() => {
         self.switch_to(State::BeforeAttributeName);
@@ -302,16 +634,16 @@ impl<'a> Tokenizer<'a> {
 
     match c {
       b'>' => {
-        warn!("missing-end-tag-name");
+        self.parse_error(ParseError::MissingEndTagName);
         self.switch_to(State::Data);
       }
       _ if self.stream.is_eof() => {
-        warn!("eof-before-tag-name");
+        self.parse_error(ParseError::EofBeforeTagName);
         self.will_emit(Token::new_text("</"));
         return Some(self.emit_eof());
       }
       _ => {
-        warn!("invalid-first-character-of-tag-name");
+        self.parse_error(ParseError::InvalidFirstCharacterOfTagName);
         self.new_token(Token::new_comment(""));
         self.reconsume_in(State::BogusComment);
       }
@@ -339,11 +671,11 @@ impl<'a> Tokenizer<'a> {
         return Some(self.emit_current_token());
       }
       _ if self.stream.is_eof() => {
-        warn!("eof-in-tag");
+        self.parse_error(ParseError::EofInTag);
         return Some(self.emit_eof());
       }
       _ => {
-        warn!("unexpected-solidus-in-tag");
+        self.parse_error(ParseError::UnexpectedSolidusInTag);
         self.reconsume_in(State::BeforeAttributeName);
       }
     }
@@ -352,7 +684,16 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn process_before_attribute_name_state(&mut self) -> Option<Token> {
-    let c = self.read_current_skipped_whitespace();
+    self.skip_whitespace();
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    let Some(c) = self.stream.current_cpy() else {
+      self.reconsume_in(State::AfterAttributeName);
+      return None;
+    };
 
     trace!("-- BeforeAttributeName: {}", c as char);
 
@@ -360,14 +701,14 @@ impl<'a> Tokenizer<'a> {
       b'/' | b'>' => {
         self.reconsume_in(State::AfterAttributeName);
       }
-      _ if self.stream.is_eof() => {
-        self.reconsume_in(State::AfterAttributeName);
-      }
       b'=' => {
-        warn!("unexpected-equals-sign-before-attribute-name");
+        self.parse_error(ParseError::UnexpectedEqualsSignBeforeAttributeName);
+        self.start_new_attribute();
+        self.append_char_to_attribute_name('=');
         self.switch_to(State::AttributeName);
       }
       _ => {
+        self.start_new_attribute();
         self.reconsume_in(State::AttributeName);
       }
     }
@@ -380,15 +721,19 @@ impl<'a> Tokenizer<'a> {
       b'/', b'>', b'=', b'\0', b'"', b'\'', b'<',
     ]);
 
-    trace!("-- AttributeName: {}", bytes_to_string(bytes));
+    trace!("-- AttributeName: {}", bytes_to_string(&bytes));
 
     if !bytes.is_empty() {
-      self.set_attribute_name(bytes);
+      self.concat_to_attribute_name(&bytes.to_ascii_lowercase());
+    }
+
+    if self.stream.is_waiting() {
+      return None;
     }
 
     // read_currentに進む前にEOFチェック
     if self.stream.is_eof() {
-      self.switch_to(State::AfterAttributeName);
+      self.reconsume_in(State::AfterAttributeName);
       return None;
     }
 
@@ -405,12 +750,14 @@ impl<'a> Tokenizer<'a> {
         self.switch_to(State::BeforeAttributeValue);
       }
       b'\0' => {
-        warn!("unexpected-null-character");
+        self.parse_error(ParseError::UnexpectedNullCharacter);
         self.append_char_to_attribute_name(REPLACEMENT_CHARACTER);
+        self.skip(1);
       }
       b'"' | b'\'' | b'<' => {
-        warn!("unexpected-character-in-attribute-name");
+        self.parse_error(ParseError::UnexpectedCharacterInAttributeName);
         self.append_char_to_attribute_name(c as char);
+        self.skip(1);
       }
       _ => {
         // noop
@@ -421,7 +768,16 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn process_after_attribute_name_state(&mut self) -> Option<Token> {
-    let b = self.read_current_skipped_whitespace();
+    self.skip_whitespace();
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    let Some(b) = self.stream.current_cpy() else {
+      self.parse_error(ParseError::EofInTag);
+      return Some(self.emit_eof());
+    };
 
     trace!("-- AfterAttributeName: {}", b as char);
 
@@ -436,11 +792,8 @@ impl<'a> Tokenizer<'a> {
         self.switch_to(State::Data);
         return Some(self.emit_current_token());
       }
-      _ if self.stream.is_eof() => {
-        warn!("eof-in-tag");
-        return Some(self.emit_eof());
-      }
       _ => {
+        self.start_new_attribute();
         self.reconsume_in(State::AttributeName);
       }
     }
@@ -449,19 +802,25 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn process_before_attribute_value_state(&mut self) -> Option<Token> {
-    let c = self.read_current_skipped_whitespace();
+    self.skip_whitespace();
 
-    trace!("-- BeforeAttributeValue: {}", c as char);
+    if self.stream.is_waiting() {
+      return None;
+    }
 
-    match c {
-      b'"' => {
+    // EOFはAttributeValueUnQuoted状態で扱う
+    match self.stream.current_cpy() {
+      Some(b'"') => {
+        trace!("-- BeforeAttributeValue: \"");
         self.switch_to(State::AttributeValueDoubleQuoted);
       }
-      b'\'' => {
+      Some(b'\'') => {
+        trace!("-- BeforeAttributeValue: '");
         self.switch_to(State::AttributeValueSingleQuoted);
       }
-      b'>' => {
-        warn!("missing-attribute-value");
+      Some(b'>') => {
+        trace!("-- BeforeAttributeValue: >");
+        self.parse_error(ParseError::MissingAttributeValue);
         self.switch_to(State::Data);
         return Some(self.emit_current_token());
       }
@@ -476,15 +835,19 @@ impl<'a> Tokenizer<'a> {
   fn process_attribute_value_double_quoted_state(&mut self) -> Option<Token> {
     let bytes = self.read_to_oneof(&[b'"', b'&', b'\0']);
 
-    trace!("-- AttributeValueDoubleQuoted: {}", bytes_to_string(bytes));
+    trace!("-- AttributeValueDoubleQuoted: {}", bytes_to_string(&bytes));
 
     if !bytes.is_empty() {
-      self.set_attribute_value(bytes);
+      self.concat_to_attribute_value(&bytes);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
     }
 
     // read_currentに進む前にEOFチェック
     if self.stream.is_eof() {
-      warn!("eof-in-tag");
+      self.parse_error(ParseError::EofInTag);
       return Some(self.emit_eof());
     }
 
@@ -499,8 +862,9 @@ impl<'a> Tokenizer<'a> {
         self.switch_to(State::CharacterReference);
       }
       b'\0' => {
-        warn!("unexpected-null-character");
+        self.parse_error(ParseError::UnexpectedNullCharacter);
         self.append_char_to_attribute_value(REPLACEMENT_CHARACTER);
+        self.skip(1);
       }
       _ => {
         // noop
@@ -513,15 +877,19 @@ impl<'a> Tokenizer<'a> {
   fn process_attribute_value_single_quoted_state(&mut self) -> Option<Token> {
     let bytes = self.read_to_oneof(&[b'\'', b'&', b'\0']);
 
-    trace!("-- AttributeValueSingleQuoted: {}", bytes_to_string(bytes));
+    trace!("-- AttributeValueSingleQuoted: {}", bytes_to_string(&bytes));
 
     if !bytes.is_empty() {
-      self.concat_to_attribute_value(bytes);
+      self.concat_to_attribute_value(&bytes);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
     }
 
     // read_currentに進む前にEOFチェック
     if self.stream.is_eof() {
-      warn!("eof-in-tag");
+      self.parse_error(ParseError::EofInTag);
       return Some(self.emit_eof());
     }
 
@@ -536,8 +904,9 @@ impl<'a> Tokenizer<'a> {
         self.switch_to(State::CharacterReference);
       }
       b'\0' => {
-        warn!("unexpected-null-character");
+        self.parse_error(ParseError::UnexpectedNullCharacter);
         self.append_char_to_attribute_value(REPLACEMENT_CHARACTER);
+        self.skip(1);
       }
       _ => {
         // noop
@@ -552,15 +921,19 @@ impl<'a> Tokenizer<'a> {
       b'&', b'>', b'=', b'\0', b'"', b'\'', b'<', b'`',
     ]);
 
-    trace!("-- AttributeValueUnQuoted: {}", bytes_to_string(bytes));
+    trace!("-- AttributeValueUnQuoted: {}", bytes_to_string(&bytes));
 
     if !bytes.is_empty() {
-      self.concat_to_attribute_value(bytes);
+      self.concat_to_attribute_value(&bytes);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
     }
 
     // read_currentに進む前にEOFチェック
     if self.stream.is_eof() {
-      warn!("eof-in-tag");
+      self.parse_error(ParseError::EofInTag);
       return Some(self.emit_eof());
     }
 
@@ -579,12 +952,15 @@ impl<'a> Tokenizer<'a> {
         return Some(self.emit_current_token());
       }
       b'\0' => {
-        warn!("unexpected-null-character");
+        self.parse_error(ParseError::UnexpectedNullCharacter);
         self.append_char_to_attribute_value(REPLACEMENT_CHARACTER);
+        self.skip(1);
       }
       b'"' | b'\'' | b'<' | b'=' | b'`' => {
-        warn!("unexpected-character-in-unquoted-attribute-value");
+        self
+          .parse_error(ParseError::UnexpectedCharacterInUnquotedAttributeValue);
         self.append_char_to_attribute_value(b as char);
+        self.skip(1);
       }
       _ => {
         // noop
@@ -611,11 +987,11 @@ impl<'a> Tokenizer<'a> {
         return Some(self.emit_current_token());
       }
       _ if self.stream.is_eof() => {
-        warn!("eof-in-tag");
+        self.parse_error(ParseError::EofInTag);
         return Some(self.emit_eof());
       }
       _ => {
-        warn!("missing-whitespace-between-attributes");
+        self.parse_error(ParseError::MissingWhitespaceBetweenAttributes);
         self.reconsume_in(State::BeforeAttributeName);
       }
     }
@@ -636,12 +1012,21 @@ impl<'a> Tokenizer<'a> {
     }
 
     if self.read_if_match(b"[CDATA[", false) {
-      unimplemented!("self.switch_to(State::CDATASection);");
+      if self.allow_cdata {
+        self.switch_to(State::CDATASection);
+        return None;
+      }
+
+      self.parse_error(ParseError::CdataInHtmlContent);
+      self.new_token(Token::new_comment("[CDATA["));
+      self.switch_to(State::BogusComment);
+      return None;
     }
 
-    warn!("incorrectly-opened-comment");
+    // 何も読まずに、現在の文字からBogusCommentとして扱う
+    self.parse_error(ParseError::IncorrectlyOpenedComment);
     self.new_token(Token::new_comment(""));
-    self.switch_to(State::BogusComment);
+    self.reconsume_in(State::BogusComment);
 
     None
   }
@@ -659,14 +1044,14 @@ impl<'a> Tokenizer<'a> {
         self.reconsume_in(State::BeforeDOCTYPEName);
       }
       _ if self.stream.is_eof() => {
-        warn!("eof-in-doctype");
+        self.parse_error(ParseError::EofInDoctype);
         let token = Token::new_doctype_with_force_quirks();
         self.new_token(token);
         self.will_emit(self.current_token.clone().unwrap());
         return Some(self.emit_eof());
       }
       _ => {
-        warn!("missing-whitespace-before-doctype-name");
+        self.parse_error(ParseError::MissingWhitespaceBeforeDoctypeName);
         self.reconsume_in(State::BeforeDOCTYPEName);
       }
     }
@@ -675,35 +1060,40 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn process_before_doctype_name_state(&mut self) -> Option<Token> {
-    let b = self.read_current_skipped_whitespace();
+    self.skip_whitespace();
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    let Some(b) = self.stream.current_cpy() else {
+      self.parse_error(ParseError::EofInDoctype);
+      let token = Token::new_doctype_with_force_quirks();
+      self.new_token(token);
+      self.will_emit(self.current_token.clone().unwrap());
+      return Some(self.emit_eof());
+    };
 
     trace!("-- BeforeDOCTYPEName: {}", b as char);
 
     match b {
       b'>' => {
-        warn!("missing-doctype-name");
+        self.parse_error(ParseError::MissingDoctypeName);
         let token = Token::new_doctype_with_force_quirks();
         self.new_token(token);
         self.switch_to(State::Data);
         return Some(self.emit_current_token());
       }
       b'\0' => {
-        warn!("unexpected-null-character");
+        self.parse_error(ParseError::UnexpectedNullCharacter);
         let token = Token::new_doctype_char_of(REPLACEMENT_CHARACTER);
         self.new_token(token);
         self.switch_to(State::DOCTYPEName);
       }
-      _ if self.stream.is_eof() => {
-        warn!("eof-in-doctype");
-        let token = Token::new_doctype_with_force_quirks();
-        self.new_token(token);
-        self.will_emit(self.current_token.clone().unwrap());
-        return Some(self.emit_eof());
-      }
       _ => {
-        let token = Token::new_doctype_char_of(b as char);
-        self.new_token(token);
-        self.switch_to(State::DOCTYPEName);
+        // 複数バイトの文字もあるので、DOCTYPEName状態でまとめて読む
+        self.new_token(Token::new_doctype());
+        self.reconsume_in(State::DOCTYPEName);
       }
     }
 
@@ -713,15 +1103,19 @@ impl<'a> Tokenizer<'a> {
   fn process_doctype_name_state(&mut self) -> Option<Token> {
     let bytes = self.read_to_whitespace_or_oneof(&[b'>', b'\0']);
 
-    trace!("-- DOCTYPEName: {}", bytes_to_string(bytes));
+    trace!("-- DOCTYPEName: {}", bytes_to_string(&bytes));
 
     if !bytes.is_empty() {
-      self.concat_to_doctype_name(bytes);
+      self.concat_to_doctype_name(&bytes);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
     }
 
     // read_currentに進む前にEOFチェック
     if self.stream.is_eof() {
-      warn!("eof-in-doctype");
+      self.parse_error(ParseError::EofInDoctype);
       let mut token = self.current_token.clone().unwrap();
       token.set_force_quirks(true);
       self.will_emit(token);
@@ -739,8 +1133,9 @@ impl<'a> Tokenizer<'a> {
         return Some(self.emit_current_token());
       }
       b'\0' => {
-        warn!("unexpected-null-character");
+        self.parse_error(ParseError::UnexpectedNullCharacter);
         self.append_char_to_doctype_name(REPLACEMENT_CHARACTER);
+        self.skip(1);
       }
       _ => {
         // noop
@@ -751,164 +1146,1025 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn process_after_doctype_name_state(&mut self) -> Option<Token> {
-    todo!("process_after_doctype_name_state");
-  }
-
-  fn process_bogus_doctype_state(&mut self) -> Option<Token> {
-    todo!("process_bogus_doctype_state");
-  }
+    self.skip_whitespace();
 
-  fn process_rawtext_state(&mut self) -> Option<Token> {
-    let bytes = self.read_to_oneof(&[b'<', b'\0']);
-
-    trace!("-- RAWTEXT: {}", bytes_to_string(bytes));
-
-    if !bytes.is_empty() {
-      return Some(self.emit_text(bytes));
+    if self.stream.is_waiting() {
+      return None;
     }
 
     // read_currentに進む前にEOFチェック
     if self.stream.is_eof() {
-      return Some(self.emit_eof());
+      return Some(self.emit_doctype_in_eof());
     }
 
     let b = self.read_current();
 
+    trace!("-- AfterDOCTYPEName: {}", b as char);
+
     match b {
-      b'<' => {
-        self.switch_to(State::RAWTEXTLessThanSign);
+      b'>' => {
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
       }
-      b'\0' => {
-        warn!("unexpected-null-character");
-        return Some(self.emit_char(REPLACEMENT_CHARACTER));
+      _ if self.read_if_match(b"PUBLIC", true) => {
+        self.switch_to(State::AfterDOCTYPEPublicKeyword);
+      }
+      _ if self.read_if_match(b"SYSTEM", true) => {
+        self.switch_to(State::AfterDOCTYPESystemKeyword);
       }
       _ => {
-        // noop
+        self.parse_error(ParseError::InvalidCharacterSequenceAfterDoctypeName);
+        self.set_force_quirks();
+        self.reconsume_in(State::BogusDOCTYPE);
       }
     }
 
     None
   }
 
-  fn process_rawtext_less_than_sign_state(&mut self) -> Option<Token> {
+  fn process_after_doctype_public_keyword_state(&mut self) -> Option<Token> {
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_doctype_in_eof());
+    }
+
     let b = self.read_current();
 
-    trace!("-- RAWTEXTLessThanSign: {}", b as char);
+    trace!("-- AfterDOCTYPEPublicKeyword: {}", b as char);
 
     match b {
-      b'/' => {
-        self.clear_tmp_buffer();
-        self.switch_to(State::RAWTEXTEndTagOpen);
+      _ if b.is_ascii_whitespace() => {
+        self.switch_to(State::BeforeDOCTYPEPublicIdentifier);
+      }
+      b'"' => {
+        self
+          .parse_error(ParseError::MissingWhitespaceAfterDoctypePublicKeyword);
+        self.set_doctype_identifier(DoctypeIdentifier::Public);
+        self.switch_to(State::DOCTYPEPublicIdentifierDoubleQuoted);
+      }
+      b'\'' => {
+        self
+          .parse_error(ParseError::MissingWhitespaceAfterDoctypePublicKeyword);
+        self.set_doctype_identifier(DoctypeIdentifier::Public);
+        self.switch_to(State::DOCTYPEPublicIdentifierSingleQuoted);
+      }
+      b'>' => {
+        self.parse_error(ParseError::MissingDoctypePublicIdentifier);
+        self.set_force_quirks();
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
       }
       _ => {
-        self.will_emit(Token::new_text("<"));
-        self.reconsume_in(State::RAWTEXT);
+        self.parse_error(ParseError::MissingQuoteBeforeDoctypePublicIdentifier);
+        self.set_force_quirks();
+        self.reconsume_in(State::BogusDOCTYPE);
       }
     }
 
     None
   }
 
-  fn process_rawtext_end_tag_open_state(&mut self) -> Option<Token> {
+  fn process_before_doctype_public_identifier_state(
+    &mut self,
+  ) -> Option<Token> {
+    self.skip_whitespace();
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_doctype_in_eof());
+    }
+
     let b = self.read_current();
 
-    trace!("-- RAWTEXTEndTagOpen: {}", b as char);
+    trace!("-- BeforeDOCTYPEPublicIdentifier: {}", b as char);
 
     match b {
-      _ if b.is_ascii_alphabetic() => {
-        self.new_token(Token::new_end_tag());
-        self.reconsume_in(State::RAWTEXTEndTagName);
+      b'"' => {
+        self.set_doctype_identifier(DoctypeIdentifier::Public);
+        self.switch_to(State::DOCTYPEPublicIdentifierDoubleQuoted);
       }
-      _ => {
-        self.will_emit(Token::new_text("</"));
-        self.reconsume_in(State::RAWTEXT);
+      b'\'' => {
+        self.set_doctype_identifier(DoctypeIdentifier::Public);
+        self.switch_to(State::DOCTYPEPublicIdentifierSingleQuoted);
+      }
+      b'>' => {
+        self.parse_error(ParseError::MissingDoctypePublicIdentifier);
+        self.set_force_quirks();
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
+      }
+      _ => {
+        self.parse_error(ParseError::MissingQuoteBeforeDoctypePublicIdentifier);
+        self.set_force_quirks();
+        self.reconsume_in(State::BogusDOCTYPE);
+      }
+    }
+
+    None
+  }
+
+  fn process_doctype_public_identifier_double_quoted_state(
+    &mut self,
+  ) -> Option<Token> {
+    self.process_doctype_identifier_quoted(
+      DoctypeIdentifier::Public,
+      b'"',
+      State::AfterDOCTYPEPublicIdentifier,
+    )
+  }
+
+  fn process_doctype_public_identifier_single_quoted_state(
+    &mut self,
+  ) -> Option<Token> {
+    self.process_doctype_identifier_quoted(
+      DoctypeIdentifier::Public,
+      b'\'',
+      State::AfterDOCTYPEPublicIdentifier,
+    )
+  }
+
+  fn process_after_doctype_public_identifier_state(&mut self) -> Option<Token> {
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_doctype_in_eof());
+    }
+
+    let b = self.read_current();
+
+    trace!("-- AfterDOCTYPEPublicIdentifier: {}", b as char);
+
+    match b {
+      _ if b.is_ascii_whitespace() => {
+        self.switch_to(State::BetweenDOCTYPEPublicAndSystemIdentifiers);
+      }
+      b'>' => {
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
+      }
+      b'"' => {
+        self.parse_error(
+          ParseError::MissingWhitespaceBetweenDoctypePublicAndSystemIdentifiers,
+        );
+        self.set_doctype_identifier(DoctypeIdentifier::System);
+        self.switch_to(State::DOCTYPESystemIdentifierDoubleQuoted);
+      }
+      b'\'' => {
+        self.parse_error(
+          ParseError::MissingWhitespaceBetweenDoctypePublicAndSystemIdentifiers,
+        );
+        self.set_doctype_identifier(DoctypeIdentifier::System);
+        self.switch_to(State::DOCTYPESystemIdentifierSingleQuoted);
+      }
+      _ => {
+        self.parse_error(ParseError::MissingQuoteBeforeDoctypeSystemIdentifier);
+        self.set_force_quirks();
+        self.reconsume_in(State::BogusDOCTYPE);
+      }
+    }
+
+    None
+  }
+
+  fn process_between_doctype_public_and_system_identifiers_state(
+    &mut self,
+  ) -> Option<Token> {
+    self.skip_whitespace();
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_doctype_in_eof());
+    }
+
+    let b = self.read_current();
+
+    trace!("-- BetweenDOCTYPEPublicAndSystemIdentifiers: {}", b as char);
+
+    match b {
+      b'>' => {
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
+      }
+      b'"' => {
+        self.set_doctype_identifier(DoctypeIdentifier::System);
+        self.switch_to(State::DOCTYPESystemIdentifierDoubleQuoted);
+      }
+      b'\'' => {
+        self.set_doctype_identifier(DoctypeIdentifier::System);
+        self.switch_to(State::DOCTYPESystemIdentifierSingleQuoted);
+      }
+      _ => {
+        self.parse_error(ParseError::MissingQuoteBeforeDoctypeSystemIdentifier);
+        self.set_force_quirks();
+        self.reconsume_in(State::BogusDOCTYPE);
+      }
+    }
+
+    None
+  }
+
+  fn process_after_doctype_system_keyword_state(&mut self) -> Option<Token> {
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_doctype_in_eof());
+    }
+
+    let b = self.read_current();
+
+    trace!("-- AfterDOCTYPESystemKeyword: {}", b as char);
+
+    match b {
+      _ if b.is_ascii_whitespace() => {
+        self.switch_to(State::BeforeDOCTYPESystemIdentifier);
+      }
+      b'"' => {
+        self
+          .parse_error(ParseError::MissingWhitespaceAfterDoctypeSystemKeyword);
+        self.set_doctype_identifier(DoctypeIdentifier::System);
+        self.switch_to(State::DOCTYPESystemIdentifierDoubleQuoted);
+      }
+      b'\'' => {
+        self
+          .parse_error(ParseError::MissingWhitespaceAfterDoctypeSystemKeyword);
+        self.set_doctype_identifier(DoctypeIdentifier::System);
+        self.switch_to(State::DOCTYPESystemIdentifierSingleQuoted);
+      }
+      b'>' => {
+        self.parse_error(ParseError::MissingDoctypeSystemIdentifier);
+        self.set_force_quirks();
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
+      }
+      _ => {
+        self.parse_error(ParseError::MissingQuoteBeforeDoctypeSystemIdentifier);
+        self.set_force_quirks();
+        self.reconsume_in(State::BogusDOCTYPE);
+      }
+    }
+
+    None
+  }
+
+  fn process_before_doctype_system_identifier_state(
+    &mut self,
+  ) -> Option<Token> {
+    self.skip_whitespace();
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_doctype_in_eof());
+    }
+
+    let b = self.read_current();
+
+    trace!("-- BeforeDOCTYPESystemIdentifier: {}", b as char);
+
+    match b {
+      b'"' => {
+        self.set_doctype_identifier(DoctypeIdentifier::System);
+        self.switch_to(State::DOCTYPESystemIdentifierDoubleQuoted);
+      }
+      b'\'' => {
+        self.set_doctype_identifier(DoctypeIdentifier::System);
+        self.switch_to(State::DOCTYPESystemIdentifierSingleQuoted);
+      }
+      b'>' => {
+        self.parse_error(ParseError::MissingDoctypeSystemIdentifier);
+        self.set_force_quirks();
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
+      }
+      _ => {
+        self.parse_error(ParseError::MissingQuoteBeforeDoctypeSystemIdentifier);
+        self.set_force_quirks();
+        self.reconsume_in(State::BogusDOCTYPE);
+      }
+    }
+
+    None
+  }
+
+  fn process_doctype_system_identifier_double_quoted_state(
+    &mut self,
+  ) -> Option<Token> {
+    self.process_doctype_identifier_quoted(
+      DoctypeIdentifier::System,
+      b'"',
+      State::AfterDOCTYPESystemIdentifier,
+    )
+  }
+
+  fn process_doctype_system_identifier_single_quoted_state(
+    &mut self,
+  ) -> Option<Token> {
+    self.process_doctype_identifier_quoted(
+      DoctypeIdentifier::System,
+      b'\'',
+      State::AfterDOCTYPESystemIdentifier,
+    )
+  }
+
+  fn process_after_doctype_system_identifier_state(&mut self) -> Option<Token> {
+    self.skip_whitespace();
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_doctype_in_eof());
+    }
+
+    let b = self.read_current();
+
+    trace!("-- AfterDOCTYPESystemIdentifier: {}", b as char);
+
+    match b {
+      b'>' => {
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
+      }
+      _ => {
+        // force-quirksは設定しない
+        self.parse_error(
+          ParseError::UnexpectedCharacterAfterDoctypeSystemIdentifier,
+        );
+        self.reconsume_in(State::BogusDOCTYPE);
+      }
+    }
+
+    None
+  }
+
+  fn process_bogus_doctype_state(&mut self) -> Option<Token> {
+    let bytes = self.read_to_oneof(b">\0");
+
+    trace!("-- BogusDOCTYPE: {}", bytes_to_string(&bytes));
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      self.will_emit(self.current_token.clone().unwrap());
+      return Some(self.emit_eof());
+    }
+
+    let b = self.read_current();
+
+    match b {
+      b'>' => {
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
+      }
+      b'\0' => {
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.skip(1);
+      }
+      _ => {
+        // noop
+      }
+    }
+
+    None
+  }
+
+  // 公開識別子・システム識別子の引用符内の共通処理
+  fn process_doctype_identifier_quoted(
+    &mut self,
+    identifier: DoctypeIdentifier,
+    quote: u8,
+    after: State,
+  ) -> Option<Token> {
+    let bytes = self.read_to_oneof(&[quote, b'\0', b'>']);
+
+    trace!("-- DOCTYPEIdentifierQuoted: {}", bytes_to_string(&bytes));
+
+    if !bytes.is_empty() {
+      self.concat_to_doctype_identifier(identifier, &bytes);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_doctype_in_eof());
+    }
+
+    let b = self.read_current();
+
+    match b {
+      _ if b == quote => {
+        self.switch_to(after);
+      }
+      b'\0' => {
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.skip(1);
+        self.concat_to_doctype_identifier(
+          identifier,
+          REPLACEMENT_CHARACTER.encode_utf8(&mut [0; 3]).as_bytes(),
+        );
+      }
+      b'>' => {
+        match identifier {
+          DoctypeIdentifier::Public => {
+            self.parse_error(ParseError::AbruptDoctypePublicIdentifier)
+          }
+          DoctypeIdentifier::System => {
+            self.parse_error(ParseError::AbruptDoctypeSystemIdentifier)
+          }
+        }
+        self.set_force_quirks();
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
+      }
+      _ => {
+        // noop
+      }
+    }
+
+    None
+  }
+
+  fn process_rawtext_state(&mut self) -> Option<Token> {
+    let bytes = self.read_to_oneof(&[b'<', b'\0']);
+
+    trace!("-- RAWTEXT: {}", bytes_to_string(&bytes));
+
+    if !bytes.is_empty() || self.text_run_start.is_some() {
+      return self.emit_text_run(bytes.len());
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_eof());
+    }
+
+    let b = self.read_current();
+
+    match b {
+      b'<' => {
+        self.switch_to(State::RAWTEXTLessThanSign);
+      }
+      b'\0' => {
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.skip(1);
+        return Some(self.emit_char(REPLACEMENT_CHARACTER));
+      }
+      _ => {
+        // noop
+      }
+    }
+
+    None
+  }
+
+  fn process_rawtext_less_than_sign_state(&mut self) -> Option<Token> {
+    let b = self.read_current();
+
+    trace!("-- RAWTEXTLessThanSign: {}", b as char);
+
+    match b {
+      b'/' => {
+        self.clear_tmp_buffer();
+        self.switch_to(State::RAWTEXTEndTagOpen);
+      }
+      _ => {
+        self.will_emit(Token::new_text("<"));
+        self.reconsume_in(State::RAWTEXT);
+      }
+    }
+
+    None
+  }
+
+  fn process_rawtext_end_tag_open_state(&mut self) -> Option<Token> {
+    let b = self.read_current();
+
+    trace!("-- RAWTEXTEndTagOpen: {}", b as char);
+
+    match b {
+      _ if b.is_ascii_alphabetic() => {
+        self.new_token(Token::new_end_tag());
+        self.reconsume_in(State::RAWTEXTEndTagName);
+      }
+      _ => {
+        self.will_emit(Token::new_text("</"));
+        self.reconsume_in(State::RAWTEXT);
+      }
+    }
+
+    None
+  }
+
+  fn process_rawtext_end_tag_name_state(&mut self) -> Option<Token> {
+    let bytes = self.read_to_whitespace_or_oneof(&[b'/', b'>', b'\0']);
+
+    trace!("-- RAWTEXTEndTagName: {}", bytes_to_string(&bytes));
+
+    if !bytes.is_empty() {
+      self.concat_to_tag_name(&bytes);
+      self.push_many_to_tmp_buffer(&bytes);
+    }
+
+    fn invalid(this: &mut Tokenizer<'_>) {
+      this.will_emit(Token::new_text("</"));
+      this.emit_tmp_buffer();
+      this.reconsume_in(State::RAWTEXT);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      invalid(self);
+      return None;
+    }
+
+    let b = self.read_current();
+
+    match b {
+      b'/' => {
+        if !self.is_appropriate_end_tag() {
+          invalid(self);
+        } else {
+          self.switch_to(State::SelfClosingStartTag);
+        }
+      }
+      b'>' => {
+        if !self.is_appropriate_end_tag() {
+          invalid(self);
+        } else {
+          self.switch_to(State::Data);
+          return Some(self.emit_current_token());
+        }
+      }
+      _ if b.is_ascii_whitespace() => {
+        if !self.is_appropriate_end_tag() {
+          invalid(self);
+        } else {
+          self.switch_to(State::BeforeAttributeName);
+        }
+      }
+      _ => {
+        invalid(self);
+      }
+    }
+
+    None
+  }
+
+  fn process_rcdata_state(&mut self) -> Option<Token> {
+    let bytes = self.read_to_oneof(&[b'&', b'<', b'\0']);
+
+    trace!("-- RCDATA: {}", bytes_to_string(&bytes));
+
+    if !bytes.is_empty() || self.text_run_start.is_some() {
+      return self.emit_text_run(bytes.len());
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_eof());
+    }
+
+    let b = self.read_current();
+
+    match b {
+      b'&' => {
+        self.return_state = Some(State::RCDATA);
+        self.switch_to(State::CharacterReference);
+      }
+      b'<' => {
+        self.switch_to(State::RCDATALessThanSign);
+      }
+      b'\0' => {
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.skip(1);
+        return Some(self.emit_char(REPLACEMENT_CHARACTER));
+      }
+      _ => {
+        // noop
+      }
+    }
+
+    None
+  }
+
+  fn process_rcdata_less_than_sign_state(&mut self) -> Option<Token> {
+    let b = self.read_current();
+
+    trace!("-- RCDATALessThanSign: {}", b as char);
+
+    match b {
+      b'/' => {
+        self.clear_tmp_buffer();
+        self.switch_to(State::RCDATAEndTagOpen);
+      }
+      _ => {
+        self.will_emit(Token::new_text("<"));
+        self.reconsume_in(State::RCDATA);
+      }
+    }
+
+    None
+  }
+
+  fn process_rcdata_end_tag_open_state(&mut self) -> Option<Token> {
+    let b = self.read_current();
+
+    trace!("-- RCDATAEndTagOpen: {}", b as char);
+
+    match b {
+      _ if b.is_ascii_alphabetic() => {
+        self.new_token(Token::new_end_tag());
+        self.reconsume_in(State::RCDATAEndTagName);
+      }
+      _ => {
+        self.will_emit(Token::new_text("</"));
+        self.reconsume_in(State::RCDATA);
+      }
+    }
+
+    None
+  }
+
+  fn process_rcdata_end_tag_name_state(&mut self) -> Option<Token> {
+    let bytes = self.read_to_whitespace_or_oneof(&[b'/', b'>', b'\0']);
+
+    trace!("-- RCDATAEndTagName: {}", bytes_to_string(&bytes));
+
+    if !bytes.is_empty() {
+      self.concat_to_tag_name(&bytes);
+      self.push_many_to_tmp_buffer(&bytes);
+    }
+
+    fn invalid(this: &mut Tokenizer<'_>) {
+      this.will_emit(Token::new_text("</"));
+      this.emit_tmp_buffer();
+      this.reconsume_in(State::RCDATA);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      invalid(self);
+      return None;
+    }
+
+    let b = self.read_current();
+
+    match b {
+      b'/' => {
+        if !self.is_appropriate_end_tag() {
+          invalid(self);
+        } else {
+          self.switch_to(State::SelfClosingStartTag);
+        }
+      }
+      b'>' => {
+        if !self.is_appropriate_end_tag() {
+          invalid(self);
+        } else {
+          self.switch_to(State::Data);
+          return Some(self.emit_current_token());
+        }
+      }
+      _ if b.is_ascii_whitespace() => {
+        if !self.is_appropriate_end_tag() {
+          invalid(self);
+        } else {
+          self.switch_to(State::BeforeAttributeName);
+        }
+      }
+      _ => {
+        invalid(self);
+      }
+    }
+
+    None
+  }
+
+  fn process_script_data_state(&mut self) -> Option<Token> {
+    let bytes = self.read_to_oneof(b"<\0");
+
+    trace!("-- ScriptData: {}", bytes_to_string(&bytes));
+
+    if !bytes.is_empty() || self.text_run_start.is_some() {
+      return self.emit_text_run(bytes.len());
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_eof());
+    }
+
+    let b = self.read_current();
+
+    match b {
+      b'<' => {
+        self.switch_to(State::ScriptDataLessThanSign);
+      }
+      b'\0' => {
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.skip(1);
+        return Some(self.emit_char(REPLACEMENT_CHARACTER));
+      }
+      _ => {
+        // noop
+      }
+    }
+
+    None
+  }
+
+  fn process_script_data_less_than_sign_state(&mut self) -> Option<Token> {
+    if self.stream.is_eof() {
+      self.will_emit(Token::new_text("<"));
+      self.reconsume_in(State::ScriptData);
+      return None;
+    }
+
+    let b = self.read_current();
+
+    trace!("-- ScriptDataLessThanSign: {}", b as char);
+
+    match b {
+      b'/' => {
+        self.clear_tmp_buffer();
+        self.switch_to(State::ScriptDataEndTagOpen);
+      }
+      b'!' => {
+        self.switch_to(State::ScriptDataEscapeStart);
+        return Some(self.emit_text(b"<!"));
+      }
+      _ => {
+        self.will_emit(Token::new_text("<"));
+        self.reconsume_in(State::ScriptData);
+      }
+    }
+
+    None
+  }
+
+  fn process_script_data_end_tag_open_state(&mut self) -> Option<Token> {
+    match self.stream.current_cpy() {
+      Some(b) if b.is_ascii_alphabetic() => {
+        trace!("-- ScriptDataEndTagOpen: {}", b as char);
+        self.new_token(Token::new_end_tag());
+        self.reconsume_in(State::ScriptDataEndTagName);
+      }
+      _ => {
+        self.will_emit(Token::new_text("</"));
+        self.reconsume_in(State::ScriptData);
+      }
+    }
+
+    None
+  }
+
+  fn process_script_data_end_tag_name_state(&mut self) -> Option<Token> {
+    self.process_script_end_tag_name(State::ScriptData)
+  }
+
+  fn process_script_data_escape_start_state(&mut self) -> Option<Token> {
+    match self.stream.current_cpy() {
+      Some(b'-') => {
+        trace!("-- ScriptDataEscapeStart: -");
+        self.switch_to(State::ScriptDataEscapeStartDash);
+        Some(self.emit_char('-'))
+      }
+      _ => {
+        self.reconsume_in(State::ScriptData);
+        None
+      }
+    }
+  }
+
+  fn process_script_data_escape_start_dash_state(&mut self) -> Option<Token> {
+    match self.stream.current_cpy() {
+      Some(b'-') => {
+        trace!("-- ScriptDataEscapeStartDash: -");
+        self.switch_to(State::ScriptDataEscapedDashDash);
+        Some(self.emit_char('-'))
+      }
+      _ => {
+        self.reconsume_in(State::ScriptData);
+        None
+      }
+    }
+  }
+
+  fn process_script_data_escaped_state(&mut self) -> Option<Token> {
+    let bytes = self.read_to_oneof(b"-<\0");
+
+    trace!("-- ScriptDataEscaped: {}", bytes_to_string(&bytes));
+
+    if !bytes.is_empty() || self.text_run_start.is_some() {
+      return self.emit_text_run(bytes.len());
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      self.parse_error(ParseError::EofInScriptHtmlCommentLikeText);
+      return Some(self.emit_eof());
+    }
+
+    let b = self.read_current();
+
+    match b {
+      b'-' => {
+        self.switch_to(State::ScriptDataEscapedDash);
+        return Some(self.emit_char('-'));
+      }
+      b'<' => {
+        self.switch_to(State::ScriptDataEscapedLessThanSign);
+      }
+      b'\0' => {
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.skip(1);
+        return Some(self.emit_char(REPLACEMENT_CHARACTER));
+      }
+      _ => {
+        // noop
       }
     }
 
     None
   }
 
-  fn process_rawtext_end_tag_name_state(&mut self) -> Option<Token> {
-    let bytes = self.read_to_whitespace_or_oneof(&[b'/', b'>', b'\0']);
+  fn process_script_data_escaped_dash_state(&mut self) -> Option<Token> {
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      self.parse_error(ParseError::EofInScriptHtmlCommentLikeText);
+      return Some(self.emit_eof());
+    }
 
-    trace!("-- RAWTEXTEndTagName: {}", bytes_to_string(bytes));
+    let b = self.read_current();
 
-    if !bytes.is_empty() {
-      self.concat_to_tag_name(bytes);
-      self.push_many_to_tmp_buffer(bytes);
-    }
+    trace!("-- ScriptDataEscapedDash: {}", b as char);
 
-    fn invalid(this: &mut Tokenizer<'_>) {
-      this.will_emit(Token::new_text("</"));
-      this.emit_tmp_buffer();
-      this.reconsume_in(State::RAWTEXT);
+    match b {
+      b'-' => {
+        self.switch_to(State::ScriptDataEscapedDashDash);
+        return Some(self.emit_char('-'));
+      }
+      b'<' => {
+        self.switch_to(State::ScriptDataEscapedLessThanSign);
+      }
+      b'\0' => {
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.switch_to(State::ScriptDataEscaped);
+        return Some(self.emit_char(REPLACEMENT_CHARACTER));
+      }
+      _ => {
+        self.reconsume_in(State::ScriptDataEscaped);
+      }
     }
 
+    None
+  }
+
+  fn process_script_data_escaped_dash_dash_state(&mut self) -> Option<Token> {
     // read_currentに進む前にEOFチェック
     if self.stream.is_eof() {
-      invalid(self);
-      return None;
+      self.parse_error(ParseError::EofInScriptHtmlCommentLikeText);
+      return Some(self.emit_eof());
     }
 
     let b = self.read_current();
 
+    trace!("-- ScriptDataEscapedDashDash: {}", b as char);
+
     match b {
-      b'/' => {
-        if !self.is_appropriate_end_tag() {
-          invalid(self);
-        } else {
-          self.switch_to(State::SelfClosingStartTag);
-        }
+      b'-' => {
+        self.skip(1);
+        return Some(self.emit_char('-'));
+      }
+      b'<' => {
+        self.switch_to(State::ScriptDataEscapedLessThanSign);
       }
       b'>' => {
-        if !self.is_appropriate_end_tag() {
-          invalid(self);
-        } else {
-          self.switch_to(State::Data);
-          return Some(self.emit_current_token());
-        }
+        self.switch_to(State::ScriptData);
+        return Some(self.emit_char('>'));
       }
-      _ if b.is_ascii_whitespace() => {
-        if !self.is_appropriate_end_tag() {
-          invalid(self);
-        } else {
-          self.switch_to(State::BeforeAttributeName);
-        }
+      b'\0' => {
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.switch_to(State::ScriptDataEscaped);
+        return Some(self.emit_char(REPLACEMENT_CHARACTER));
       }
       _ => {
-        invalid(self);
+        self.reconsume_in(State::ScriptDataEscaped);
       }
     }
 
     None
   }
 
-  fn process_rcdata_state(&mut self) -> Option<Token> {
-    let bytes = self.read_to_oneof(&[b'&', b'<', b'\0']);
+  fn process_script_data_escaped_less_than_sign_state(
+    &mut self,
+  ) -> Option<Token> {
+    match self.stream.current_cpy() {
+      Some(b'/') => {
+        trace!("-- ScriptDataEscapedLessThanSign: /");
+        self.clear_tmp_buffer();
+        self.switch_to(State::ScriptDataEscapedEndTagOpen);
+      }
+      Some(b) if b.is_ascii_alphabetic() => {
+        trace!("-- ScriptDataEscapedLessThanSign: {}", b as char);
+        self.clear_tmp_buffer();
+        self.will_emit(Token::new_text("<"));
+        self.reconsume_in(State::ScriptDataDoubleEscapeStart);
+      }
+      _ => {
+        self.will_emit(Token::new_text("<"));
+        self.reconsume_in(State::ScriptDataEscaped);
+      }
+    }
 
-    trace!("-- RCDATA: {}", bytes_to_string(bytes));
+    None
+  }
 
-    if !bytes.is_empty() {
-      return Some(self.emit_text(bytes));
+  fn process_script_data_escaped_end_tag_open_state(
+    &mut self,
+  ) -> Option<Token> {
+    match self.stream.current_cpy() {
+      Some(b) if b.is_ascii_alphabetic() => {
+        trace!("-- ScriptDataEscapedEndTagOpen: {}", b as char);
+        self.new_token(Token::new_end_tag());
+        self.reconsume_in(State::ScriptDataEscapedEndTagName);
+      }
+      _ => {
+        self.will_emit(Token::new_text("</"));
+        self.reconsume_in(State::ScriptDataEscaped);
+      }
+    }
+
+    None
+  }
+
+  fn process_script_data_escaped_end_tag_name_state(
+    &mut self,
+  ) -> Option<Token> {
+    self.process_script_end_tag_name(State::ScriptDataEscaped)
+  }
+
+  fn process_script_data_double_escape_start_state(&mut self) -> Option<Token> {
+    self.process_script_double_escape_boundary(
+      State::ScriptDataDoubleEscaped,
+      State::ScriptDataEscaped,
+    )
+  }
+
+  fn process_script_data_double_escaped_state(&mut self) -> Option<Token> {
+    let bytes = self.read_to_oneof(b"-<\0");
+
+    trace!("-- ScriptDataDoubleEscaped: {}", bytes_to_string(&bytes));
+
+    if !bytes.is_empty() || self.text_run_start.is_some() {
+      return self.emit_text_run(bytes.len());
     }
 
     // read_currentに進む前にEOFチェック
     if self.stream.is_eof() {
+      self.parse_error(ParseError::EofInScriptHtmlCommentLikeText);
       return Some(self.emit_eof());
     }
 
     let b = self.read_current();
 
     match b {
-      b'&' => {
-        self.return_state = Some(State::RCDATA);
-        self.switch_to(State::CharacterReference);
+      b'-' => {
+        self.switch_to(State::ScriptDataDoubleEscapedDash);
+        return Some(self.emit_char('-'));
       }
       b'<' => {
-        self.switch_to(State::RCDATALessThanSign);
+        self.switch_to(State::ScriptDataDoubleEscapedLessThanSign);
+        return Some(self.emit_char('<'));
       }
       b'\0' => {
-        warn!("unexpected-null-character");
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.skip(1);
         return Some(self.emit_char(REPLACEMENT_CHARACTER));
       }
       _ => {
@@ -919,110 +2175,215 @@ impl<'a> Tokenizer<'a> {
     None
   }
 
-  fn process_rcdata_less_than_sign_state(&mut self) -> Option<Token> {
+  fn process_script_data_double_escaped_dash_state(&mut self) -> Option<Token> {
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      self.parse_error(ParseError::EofInScriptHtmlCommentLikeText);
+      return Some(self.emit_eof());
+    }
+
     let b = self.read_current();
 
-    trace!("-- RCDATALessThanSign: {}", b as char);
+    trace!("-- ScriptDataDoubleEscapedDash: {}", b as char);
 
     match b {
-      b'/' => {
-        self.clear_tmp_buffer();
-        self.switch_to(State::RCDATAEndTagOpen);
+      b'-' => {
+        self.switch_to(State::ScriptDataDoubleEscapedDashDash);
+        return Some(self.emit_char('-'));
+      }
+      b'<' => {
+        self.switch_to(State::ScriptDataDoubleEscapedLessThanSign);
+        return Some(self.emit_char('<'));
+      }
+      b'\0' => {
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.switch_to(State::ScriptDataDoubleEscaped);
+        return Some(self.emit_char(REPLACEMENT_CHARACTER));
       }
       _ => {
-        self.will_emit(Token::new_text("<"));
-        self.reconsume_in(State::RCDATA);
+        self.reconsume_in(State::ScriptDataDoubleEscaped);
       }
     }
 
     None
   }
 
-  fn process_rcdata_end_tag_open_state(&mut self) -> Option<Token> {
+  fn process_script_data_double_escaped_dash_dash_state(
+    &mut self,
+  ) -> Option<Token> {
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      self.parse_error(ParseError::EofInScriptHtmlCommentLikeText);
+      return Some(self.emit_eof());
+    }
+
     let b = self.read_current();
 
-    trace!("-- RCDATAEndTagOpen: {}", b as char);
+    trace!("-- ScriptDataDoubleEscapedDashDash: {}", b as char);
 
     match b {
-      _ if b.is_ascii_alphabetic() => {
-        self.new_token(Token::new_end_tag());
-        self.reconsume_in(State::RCDATAEndTagName);
+      b'-' => {
+        self.skip(1);
+        return Some(self.emit_char('-'));
+      }
+      b'<' => {
+        self.switch_to(State::ScriptDataDoubleEscapedLessThanSign);
+        return Some(self.emit_char('<'));
+      }
+      b'>' => {
+        self.switch_to(State::ScriptData);
+        return Some(self.emit_char('>'));
+      }
+      b'\0' => {
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        self.switch_to(State::ScriptDataDoubleEscaped);
+        return Some(self.emit_char(REPLACEMENT_CHARACTER));
       }
       _ => {
-        self.will_emit(Token::new_text("</"));
-        self.reconsume_in(State::RCDATA);
+        self.reconsume_in(State::ScriptDataDoubleEscaped);
       }
     }
 
     None
   }
 
-  fn process_rcdata_end_tag_name_state(&mut self) -> Option<Token> {
-    let bytes = self.read_to_whitespace_or_oneof(&[b'/', b'>', b'\0']);
+  fn process_script_data_double_escaped_less_than_sign_state(
+    &mut self,
+  ) -> Option<Token> {
+    match self.stream.current_cpy() {
+      Some(b'/') => {
+        trace!("-- ScriptDataDoubleEscapedLessThanSign: /");
+        self.clear_tmp_buffer();
+        self.switch_to(State::ScriptDataDoubleEscapeEnd);
+        Some(self.emit_char('/'))
+      }
+      _ => {
+        self.reconsume_in(State::ScriptDataDoubleEscaped);
+        None
+      }
+    }
+  }
+
+  fn process_script_data_double_escape_end_state(&mut self) -> Option<Token> {
+    self.process_script_double_escape_boundary(
+      State::ScriptDataEscaped,
+      State::ScriptDataDoubleEscaped,
+    )
+  }
 
-    trace!("-- RCDATAEndTagName: {}", bytes_to_string(bytes));
+  // ScriptDataEndTagNameとScriptDataEscapedEndTagNameの共通処理
+  // 適切な終了タグでなければ、return_toの状態で読み直す
+  fn process_script_end_tag_name(&mut self, return_to: State) -> Option<Token> {
+    let bytes = self.read_while(|b| b.is_ascii_alphabetic());
+
+    trace!("-- ScriptDataEndTagName: {}", bytes_to_string(&bytes));
 
     if !bytes.is_empty() {
-      self.concat_to_tag_name(bytes);
-      self.push_many_to_tmp_buffer(bytes);
+      self.concat_to_tag_name(&bytes.to_ascii_lowercase());
+      self.push_many_to_tmp_buffer(&bytes);
     }
 
-    fn invalid(this: &mut Tokenizer<'_>) {
+    fn invalid(this: &mut Tokenizer<'_>, return_to: State) {
       this.will_emit(Token::new_text("</"));
       this.emit_tmp_buffer();
-      this.reconsume_in(State::RCDATA);
+      this.reconsume_in(return_to);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
     }
 
     // read_currentに進む前にEOFチェック
     if self.stream.is_eof() {
-      invalid(self);
+      invalid(self, return_to);
       return None;
     }
 
     let b = self.read_current();
 
     match b {
-      b'/' => {
-        if !self.is_appropriate_end_tag() {
-          invalid(self);
-        } else {
-          self.switch_to(State::SelfClosingStartTag);
-        }
+      b'/' if self.is_appropriate_end_tag() => {
+        self.switch_to(State::SelfClosingStartTag);
       }
-      b'>' => {
-        if !self.is_appropriate_end_tag() {
-          invalid(self);
-        } else {
-          self.switch_to(State::Data);
-          return Some(self.emit_current_token());
-        }
+      b'>' if self.is_appropriate_end_tag() => {
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
       }
-      _ if b.is_ascii_whitespace() => {
-        if !self.is_appropriate_end_tag() {
-          invalid(self);
-        } else {
-          self.switch_to(State::BeforeAttributeName);
-        }
+      _ if b.is_ascii_whitespace() && self.is_appropriate_end_tag() => {
+        self.switch_to(State::BeforeAttributeName);
       }
       _ => {
-        invalid(self);
+        invalid(self, return_to);
       }
     }
 
     None
   }
 
-  fn process_comment_start_state(&mut self) -> Option<Token> {
-    let b = self.read_current();
+  // ScriptDataDoubleEscapeStartとScriptDataDoubleEscapeEndの共通処理
+  // tmp_bufferが"script"であればmatchedへ、そうでなければunmatchedへ遷移する
+  fn process_script_double_escape_boundary(
+    &mut self,
+    matched: State,
+    unmatched: State,
+  ) -> Option<Token> {
+    let bytes = self.read_while(|b| b.is_ascii_alphabetic());
 
-    trace!("-- CommentStart: {}", b as char);
+    trace!(
+      "-- ScriptDataDoubleEscapeBoundary: {}",
+      bytes_to_string(&bytes)
+    );
 
-    match b {
-      b'-' => {
+    if !bytes.is_empty() {
+      self.push_many_to_tmp_buffer(&bytes.to_ascii_lowercase());
+      return Some(self.emit_text(&bytes));
+    }
+
+    match self.stream.current_cpy() {
+      Some(b) if b.is_ascii_whitespace() || b == b'/' || b == b'>' => {
+        if self.tmp_buffer.as_slice() == b"script" {
+          self.switch_to(matched);
+        } else {
+          self.switch_to(unmatched);
+        }
+        Some(self.emit_char(b as char))
+      }
+      _ => {
+        self.reconsume_in(unmatched);
+        None
+      }
+    }
+  }
+
+  fn process_plaintext_state(&mut self) -> Option<Token> {
+    let bytes = self.read_to(b'\0');
+
+    trace!("-- PLAINTEXT: {}", bytes_to_string(&bytes));
+
+    if !bytes.is_empty() || self.text_run_start.is_some() {
+      return self.emit_text_run(bytes.len());
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      return Some(self.emit_eof());
+    }
+
+    // ここに来るのはNULL文字のときのみ
+    self.parse_error(ParseError::UnexpectedNullCharacter);
+    self.switch_to(State::PLAINTEXT);
+    Some(self.emit_char(REPLACEMENT_CHARACTER))
+  }
+
+  fn process_comment_start_state(&mut self) -> Option<Token> {
+    match self.stream.current_cpy() {
+      Some(b'-') => {
+        trace!("-- CommentStart: -");
         self.switch_to(State::CommentStartDash);
       }
-      b'>' => {
-        warn!("abrupt-closing-of-empty-comment");
+      Some(b'>') => {
+        trace!("-- CommentStart: >");
+        self.parse_error(ParseError::AbruptClosingOfEmptyComment);
         self.switch_to(State::Data);
         return Some(self.emit_current_token());
       }
@@ -1035,21 +2396,47 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn process_comment_start_dash_state(&mut self) -> Option<Token> {
-    todo!("process_comment_start_dash_state");
+    match self.stream.current_cpy() {
+      Some(b'-') => {
+        trace!("-- CommentStartDash: -");
+        self.switch_to(State::CommentEnd);
+      }
+      Some(b'>') => {
+        trace!("-- CommentStartDash: >");
+        self.parse_error(ParseError::AbruptClosingOfEmptyComment);
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
+      }
+      None => {
+        self.parse_error(ParseError::EofInComment);
+        self.will_emit(self.current_token.clone().unwrap());
+        return Some(self.emit_eof());
+      }
+      _ => {
+        self.append_char_to_comment('-');
+        self.reconsume_in(State::Comment);
+      }
+    }
+
+    None
   }
 
   fn process_comment_state(&mut self) -> Option<Token> {
     let bytes = self.read_to_oneof(&[b'<', b'-', b'\0']);
 
-    trace!("-- Comment: {}", bytes_to_string(bytes));
+    trace!("-- Comment: {}", bytes_to_string(&bytes));
 
     if !bytes.is_empty() {
-      self.concat_to_comment(bytes);
+      self.concat_to_comment(&bytes);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
     }
 
     // read_currentに進む前にEOFチェック
     if self.stream.is_eof() {
-      warn!("eof-in-comment");
+      self.parse_error(ParseError::EofInComment);
       self.will_emit(self.current_token.clone().unwrap());
       return Some(self.emit_eof());
     }
@@ -1065,8 +2452,9 @@ impl<'a> Tokenizer<'a> {
         self.switch_to(State::CommentLessThanSign);
       }
       b'\0' => {
-        warn!("unexpected-null-character");
+        self.parse_error(ParseError::UnexpectedNullCharacter);
         self.append_char_to_comment(REPLACEMENT_CHARACTER);
+        self.skip(1);
       }
       _ => {
         // noop
@@ -1077,17 +2465,16 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn process_comment_less_than_sign_state(&mut self) -> Option<Token> {
-    let b = self.read_current();
-
-    trace!("-- CommentLessThanSign: {}", b as char);
-
-    match b {
-      b'!' => {
-        self.append_char_to_comment(b as char);
+    match self.stream.current_cpy() {
+      Some(b'!') => {
+        trace!("-- CommentLessThanSign: !");
+        self.append_char_to_comment('!');
         self.switch_to(State::CommentLessThanSignBang);
       }
-      b'<' => {
-        self.append_char_to_comment(b as char);
+      Some(b'<') => {
+        trace!("-- CommentLessThanSign: <");
+        self.append_char_to_comment('<');
+        self.stream.advance();
       }
       _ => {
         self.reconsume_in(State::Comment);
@@ -1098,32 +2485,60 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn process_comment_less_than_sign_bang_state(&mut self) -> Option<Token> {
-    todo!("process_comment_less_than_sign_bang_state");
+    match self.stream.current_cpy() {
+      Some(b'-') => {
+        trace!("-- CommentLessThanSignBang: -");
+        self.switch_to(State::CommentLessThanSignBangDash);
+      }
+      _ => {
+        self.reconsume_in(State::Comment);
+      }
+    }
+
+    None
   }
 
   fn process_comment_less_than_sign_bang_dash_state(
     &mut self,
   ) -> Option<Token> {
-    todo!("process_comment_less_than_sign_bang_dash_state");
+    match self.stream.current_cpy() {
+      Some(b'-') => {
+        trace!("-- CommentLessThanSignBangDash: -");
+        self.switch_to(State::CommentLessThanSignBangDashDash);
+      }
+      _ => {
+        self.reconsume_in(State::CommentEndDash);
+      }
+    }
+
+    None
   }
 
   fn process_comment_less_than_sign_bang_dash_dash_state(
     &mut self,
   ) -> Option<Token> {
-    todo!("process_comment_less_than_sign_bang_dash_dash_state");
+    match self.stream.current_cpy() {
+      Some(b'>') | None => {
+        self.reconsume_in(State::CommentEnd);
+      }
+      _ => {
+        // `<!--`がコメント内でネストしている
+        self.parse_error(ParseError::NestedComment);
+        self.reconsume_in(State::CommentEnd);
+      }
+    }
+
+    None
   }
 
   fn process_comment_end_dash_state(&mut self) -> Option<Token> {
-    let b = self.read_current();
-
-    trace!("-- CommentEndDash: {}", b as char);
-
-    match b {
-      b'-' => {
+    match self.stream.current_cpy() {
+      Some(b'-') => {
+        trace!("-- CommentEndDash: -");
         self.switch_to(State::CommentEnd);
       }
-      _ if self.stream.is_eof() => {
-        warn!("eof-in-comment");
+      None => {
+        self.parse_error(ParseError::EofInComment);
         self.will_emit(self.current_token.clone().unwrap());
         return Some(self.emit_eof());
       }
@@ -1137,23 +2552,23 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn process_comment_end_state(&mut self) -> Option<Token> {
-    let b = self.read_current();
-
-    trace!("-- CommentEnd: {}", b as char);
-
-    match b {
-      b'>' => {
+    match self.stream.current_cpy() {
+      Some(b'>') => {
+        trace!("-- CommentEnd: >");
         self.switch_to(State::Data);
         return Some(self.emit_current_token());
       }
-      b'!' => {
+      Some(b'!') => {
+        trace!("-- CommentEnd: !");
         self.switch_to(State::CommentEndBang);
       }
-      b'-' => {
+      Some(b'-') => {
+        trace!("-- CommentEnd: -");
         self.append_char_to_comment('-');
+        self.stream.advance();
       }
-      _ if self.stream.is_eof() => {
-        warn!("eof-in-comment");
+      None => {
+        self.parse_error(ParseError::EofInComment);
         self.will_emit(self.current_token.clone().unwrap());
         return Some(self.emit_eof());
       }
@@ -1167,16 +2582,43 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn process_comment_end_bang_state(&mut self) -> Option<Token> {
-    todo!("process_comment_end_bang_state");
+    match self.stream.current_cpy() {
+      Some(b'-') => {
+        trace!("-- CommentEndBang: -");
+        self.concat_to_comment(b"--!");
+        self.switch_to(State::CommentEndDash);
+      }
+      Some(b'>') => {
+        trace!("-- CommentEndBang: >");
+        self.parse_error(ParseError::IncorrectlyClosedComment);
+        self.switch_to(State::Data);
+        return Some(self.emit_current_token());
+      }
+      None => {
+        self.parse_error(ParseError::EofInComment);
+        self.will_emit(self.current_token.clone().unwrap());
+        return Some(self.emit_eof());
+      }
+      _ => {
+        self.concat_to_comment(b"--!");
+        self.reconsume_in(State::Comment);
+      }
+    }
+
+    None
   }
 
   fn process_bogus_comment_state(&mut self) -> Option<Token> {
     let bytes = self.read_to_oneof(&[b'>', b'\0']);
 
-    trace!("-- BogusComment: {}", bytes_to_string(bytes));
+    trace!("-- BogusComment: {}", bytes_to_string(&bytes));
 
     if !bytes.is_empty() {
-      self.concat_to_comment(bytes);
+      self.concat_to_comment(&bytes);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
     }
 
     // read_currentに進む前にEOFチェック
@@ -1193,8 +2635,9 @@ impl<'a> Tokenizer<'a> {
         return Some(self.emit_current_token());
       }
       b'\0' => {
-        warn!("unexpected-null-character");
+        self.parse_error(ParseError::UnexpectedNullCharacter);
         self.append_char_to_comment(REPLACEMENT_CHARACTER);
+        self.skip(1);
       }
       _ => {
         // noop
@@ -1204,19 +2647,71 @@ impl<'a> Tokenizer<'a> {
     None
   }
 
+  fn process_cdata_section_state(&mut self) -> Option<Token> {
+    let bytes = self.read_to(b']');
+
+    trace!("-- CDATASection: {}", bytes_to_string(&bytes));
+
+    if !bytes.is_empty() || self.text_run_start.is_some() {
+      return self.emit_text_run(bytes.len());
+    }
+
+    // read_currentに進む前にEOFチェック
+    if self.stream.is_eof() {
+      self.parse_error(ParseError::EofInCdata);
+      return Some(self.emit_eof());
+    }
+
+    // ここに来るのは`]`のときのみ
+    self.switch_to(State::CDATASectionBracket);
+
+    None
+  }
+
+  fn process_cdata_section_bracket_state(&mut self) -> Option<Token> {
+    match self.stream.current_cpy() {
+      Some(b']') => {
+        trace!("-- CDATASectionBracket: ]");
+        self.switch_to(State::CDATASectionEnd);
+        None
+      }
+      _ => {
+        self.reconsume_in(State::CDATASection);
+        Some(self.emit_char(']'))
+      }
+    }
+  }
+
+  fn process_cdata_section_end_state(&mut self) -> Option<Token> {
+    match self.stream.current_cpy() {
+      Some(b']') => {
+        trace!("-- CDATASectionEnd: ]");
+        self.stream.advance();
+        Some(self.emit_char(']'))
+      }
+      Some(b'>') => {
+        trace!("-- CDATASectionEnd: >");
+        self.switch_to(State::Data);
+        None
+      }
+      _ => {
+        self.reconsume_in(State::CDATASection);
+        Some(self.emit_text(b"]]"))
+      }
+    }
+  }
+
   fn process_character_reference_state(&mut self) -> Option<Token> {
     self.clear_tmp_buffer();
     self.push_to_tmp_buffer(b'&');
 
-    let b = self.read_current();
-
-    trace!("-- CharacterReference: {}", b as char);
-
-    match b {
-      _ if b.is_ascii_alphanumeric() => {
+    match self.stream.current_cpy() {
+      Some(b) if b.is_ascii_alphanumeric() => {
+        trace!("-- CharacterReference: {}", b as char);
         self.reconsume_in(State::NamedCharacterReference);
       }
-      b'#' => {
+      Some(b'#') => {
+        trace!("-- CharacterReference: #");
         self.push_to_tmp_buffer(b'#');
         self.switch_to(State::NumericCharacterReference);
       }
@@ -1230,86 +2725,228 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn process_named_character_reference_state(&mut self) -> Option<Token> {
-    let bytes = self.peek_while_with_last(|b| b.is_ascii_alphanumeric(), b';');
+    let bytes = self.peek_while_with_last(
+      |b| b.is_ascii_alphanumeric(),
+      b';',
+      *LONGEST_ENTITY_NAME,
+    );
 
-    trace!("-- NamedCharacterReference: {}", bytes_to_string(bytes));
+    trace!("-- NamedCharacterReference: {}", bytes_to_string(&bytes));
 
-    let name = from_utf8(bytes).unwrap_or("");
+    // 最も長く一致する名前を探す（例: &notit; は &not として扱われる）
+    let matched = (1..=bytes.len()).rev().find_map(|len| {
+      let name = from_utf8(&bytes[..len]).unwrap_or("");
+      ENTITIES.get(name).map(|&codepoints| (len, codepoints))
+    });
 
-    if let Some(&codepoints) = ENTITIES.get(name) {
-      self.skip(bytes.len() - 1);
+    if let Some((len, codepoints)) = matched {
+      let bytes = &bytes[..len];
+
+      // 参照名を読み終えてから発行することで、トークンの範囲に参照名全体を含める
+      self.skip(bytes.len());
       self.push_many_to_tmp_buffer(bytes);
 
       let last = bytes.last().unwrap();
 
       if self.is_character_part_of_attribute() && *last != b';' {
-        if let Some(next) = self.peek_next() {
-          if *next == b'=' || next.is_ascii_alphanumeric() {
+        if let Some(next) = self.stream.current_cpy() {
+          if next == b'=' || next.is_ascii_alphanumeric() {
             self.flush_code_points_consumed_as_a_character_reference();
-            self.switch_to_return_state();
+            self.reconsume_in_return_state();
             return None;
           }
         }
       }
 
       if *last != b';' {
-        warn!("missing-semicolon-after-character-reference");
+        self.parse_error(ParseError::MissingSemicolonAfterCharacterReference);
       }
 
       self.clear_tmp_buffer();
-      self.push_to_tmp_buffer(from_u32(codepoints.0).unwrap() as u8);
+      self.push_char_to_tmp_buffer(from_u32(codepoints.0).unwrap());
       if codepoints.1 != 0 {
-        self.push_to_tmp_buffer(from_u32(codepoints.1).unwrap() as u8);
+        self.push_char_to_tmp_buffer(from_u32(codepoints.1).unwrap());
       }
 
       self.flush_code_points_consumed_as_a_character_reference();
-      self.switch_to_return_state();
+      self.reconsume_in_return_state();
 
       return None;
     }
 
     self.flush_code_points_consumed_as_a_character_reference();
-    self.switch_to(State::AmbiguousAmpersand);
+    self.reconsume_in(State::AmbiguousAmpersand);
 
     None
   }
 
   fn process_ambiguous_ampersand_state(&mut self) -> Option<Token> {
-    todo!("process_ambiguous_ampersand_state");
+    let bytes = self.read_while(|b| b.is_ascii_alphanumeric());
+
+    trace!("-- AmbiguousAmpersand: {}", bytes_to_string(&bytes));
+
+    if !bytes.is_empty() {
+      if self.is_character_part_of_attribute() {
+        self.concat_to_attribute_value(&bytes);
+      } else {
+        return Some(self.emit_text(&bytes));
+      }
+    }
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    if let Some(b';') = self.stream.current_cpy() {
+      self.parse_error(ParseError::UnknownNamedCharacterReference);
+    }
+
+    self.reconsume_in_return_state();
+
+    None
   }
 
   fn process_numeric_character_reference_state(&mut self) -> Option<Token> {
-    todo!("process_numeric_character_reference_state");
+    self.character_reference_code = 0;
+
+    match self.stream.current_cpy() {
+      Some(b) if b == b'x' || b == b'X' => {
+        trace!("-- NumericCharacterReference: {}", b as char);
+        self.push_to_tmp_buffer(b);
+        self.switch_to(State::HexadecimalCharacterReferenceStart);
+      }
+      _ => {
+        self.reconsume_in(State::DecimalCharacterReferenceStart);
+      }
+    }
+
+    None
   }
 
   fn process_hexadecimal_character_reference_start_state(
     &mut self,
   ) -> Option<Token> {
-    todo!("process_hexadecimal_character_reference_start_state");
+    match self.stream.current_cpy() {
+      Some(b) if b.is_ascii_hexdigit() => {
+        trace!("-- HexadecimalCharacterReferenceStart: {}", b as char);
+        self.reconsume_in(State::HexadecimalCharacterReference);
+      }
+      _ => {
+        self
+          .parse_error(ParseError::AbsenceOfDigitsInNumericCharacterReference);
+        self.flush_code_points_consumed_as_a_character_reference();
+        self.reconsume_in_return_state();
+      }
+    }
+
+    None
   }
 
   fn process_decimal_character_reference_start_state(
     &mut self,
   ) -> Option<Token> {
-    todo!("process_decimal_character_reference_start_state");
+    match self.stream.current_cpy() {
+      Some(b) if b.is_ascii_digit() => {
+        trace!("-- DecimalCharacterReferenceStart: {}", b as char);
+        self.reconsume_in(State::DecimalCharacterReference);
+      }
+      _ => {
+        self
+          .parse_error(ParseError::AbsenceOfDigitsInNumericCharacterReference);
+        self.flush_code_points_consumed_as_a_character_reference();
+        self.reconsume_in_return_state();
+      }
+    }
+
+    None
   }
 
   fn process_hexadecimal_character_reference_state(&mut self) -> Option<Token> {
-    todo!("process_hexadecimal_character_reference_state");
+    let bytes = self.read_while(|b| b.is_ascii_hexdigit());
+
+    trace!(
+      "-- HexadecimalCharacterReference: {}",
+      bytes_to_string(&bytes)
+    );
+
+    for b in bytes.iter() {
+      let digit = (*b as char).to_digit(16).unwrap();
+      self.append_digit_to_character_reference_code(16, digit);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    self.finish_numeric_character_reference_digits();
+
+    None
   }
 
   fn process_decimal_character_reference_state(&mut self) -> Option<Token> {
-    todo!("process_decimal_character_reference_state");
+    let bytes = self.read_while(|b| b.is_ascii_digit());
+
+    trace!("-- DecimalCharacterReference: {}", bytes_to_string(&bytes));
+
+    for b in bytes.iter() {
+      let digit = (*b as char).to_digit(10).unwrap();
+      self.append_digit_to_character_reference_code(10, digit);
+    }
+
+    if self.stream.is_waiting() {
+      return None;
+    }
+
+    self.finish_numeric_character_reference_digits();
+
+    None
   }
 
   fn process_numeric_character_reference_end_state(&mut self) -> Option<Token> {
-    todo!("process_numeric_character_reference_end_state");
+    let code = self.character_reference_code;
+
+    trace!("-- NumericCharacterReferenceEnd: {:#X}", code);
+
+    let code = match code {
+      0x00 => {
+        self.parse_error(ParseError::NullCharacterReference);
+        REPLACEMENT_CHARACTER as u32
+      }
+      _ if code > 0x10FFFF => {
+        self.parse_error(ParseError::CharacterReferenceOutsideUnicodeRange);
+        REPLACEMENT_CHARACTER as u32
+      }
+      0xD800..=0xDFFF => {
+        self.parse_error(ParseError::SurrogateCharacterReference);
+        REPLACEMENT_CHARACTER as u32
+      }
+      _ if is_noncharacter(code) => {
+        self.parse_error(ParseError::NoncharacterCharacterReference);
+        code
+      }
+      _ if code == 0x0D || is_control_except_whitespace(code) => {
+        self.parse_error(ParseError::ControlCharacterReference);
+        replace_c1_control(code).unwrap_or(code)
+      }
+      _ => code,
+    };
+
+    self.clear_tmp_buffer();
+    self.push_char_to_tmp_buffer(from_u32(code).unwrap());
+    self.flush_code_points_consumed_as_a_character_reference();
+    self.reconsume_in_return_state();
+
+    None
   }
 
   /* -------------------------------------------- */
 
   fn new_token(&mut self, token: Token) {
-    self.current_token = Some(token);
+    let replaced = self.current_token.replace(token);
+
+    if !self.stream.is_complete() && self.replaced_token.is_none() {
+      self.replaced_token = Some(replaced);
+    }
   }
 
   /* -------------------------------------------- */
@@ -1367,6 +3004,29 @@ impl<'a> Tokenizer<'a> {
     }
   }
 
+  fn concat_to_doctype_identifier(
+    &mut self,
+    identifier: DoctypeIdentifier,
+    suffix: &[u8],
+  ) {
+    let suffix = bytes_to_string(suffix);
+    let current_tag = self.current_token.as_mut().unwrap();
+    if let Token::DOCTYPE {
+      public_identifier,
+      system_identifier,
+      ..
+    } = current_tag
+    {
+      let target = match identifier {
+        DoctypeIdentifier::Public => public_identifier,
+        DoctypeIdentifier::System => system_identifier,
+      };
+      if let Some(ref mut value) = target {
+        value.push_str(&suffix);
+      }
+    }
+  }
+
   fn concat_to_tag_name(&mut self, suffix: &[u8]) {
     let suffix = bytes_to_string(suffix);
     let current_tag = self.current_token.as_mut().unwrap();
@@ -1378,6 +3038,20 @@ impl<'a> Tokenizer<'a> {
     }
   }
 
+  fn concat_to_attribute_name(&mut self, suffix: &[u8]) {
+    let suffix = bytes_to_string(suffix);
+    let current_tag = self.current_token.as_mut().unwrap();
+    match current_tag {
+      Token::Tag { attributes, .. } => {
+        if let Some(mut last) = attributes.pop() {
+          last.name.push_str(&suffix);
+          attributes.push(last);
+        }
+      }
+      _ => unreachable!("No tag found"),
+    }
+  }
+
   fn concat_to_attribute_value(&mut self, suffix: &[u8]) {
     let suffix = bytes_to_string(suffix);
     let current_tag = self.current_token.as_mut().unwrap();
@@ -1404,7 +3078,7 @@ impl<'a> Tokenizer<'a> {
   }
 
   fn concat_to_doctype_name(&mut self, name: &[u8]) {
-    let name = bytes_to_string(name);
+    let name = bytes_to_string(&name.to_ascii_lowercase());
     let current_tag = self.current_token.as_mut().unwrap();
     if let Token::DOCTYPE {
       name: ref mut old_name,
@@ -1417,37 +3091,37 @@ impl<'a> Tokenizer<'a> {
     }
   }
 
-  fn set_tag_name(&mut self, name: &[u8]) {
-    let name = bytes_to_string(name);
+  // 識別子を空文字列で初期化する（欠落している状態と区別するため）
+  fn set_doctype_identifier(&mut self, identifier: DoctypeIdentifier) {
     let current_tag = self.current_token.as_mut().unwrap();
-    match current_tag {
-      Token::Tag { tag_name, .. } => {
-        *tag_name = name;
+    if let Token::DOCTYPE {
+      public_identifier,
+      system_identifier,
+      ..
+    } = current_tag
+    {
+      match identifier {
+        DoctypeIdentifier::Public => {
+          *public_identifier = Some(EcoString::new())
+        }
+        DoctypeIdentifier::System => {
+          *system_identifier = Some(EcoString::new())
+        }
       }
-      _ => unreachable!("No tag found"),
     }
   }
 
-  fn set_attribute_name(&mut self, name: &[u8]) {
-    let name = from_utf8(name).unwrap();
-    let current_tag = self.current_token.as_mut().unwrap();
-    match current_tag {
-      Token::Tag { attributes, .. } => {
-        attributes.push(Attribute::new_name_of(name));
-      }
-      _ => unreachable!("No tag found"),
+  fn set_force_quirks(&mut self) {
+    if let Some(token) = self.current_token.as_mut() {
+      token.set_force_quirks(true);
     }
   }
 
-  fn set_attribute_value(&mut self, value: &[u8]) {
-    let value = bytes_to_string(value);
+  fn start_new_attribute(&mut self) {
     let current_tag = self.current_token.as_mut().unwrap();
     match current_tag {
       Token::Tag { attributes, .. } => {
-        if let Some(mut last) = attributes.pop() {
-          last.value = value;
-          attributes.push(last);
-        }
+        attributes.push(Attribute::new());
       }
       _ => unreachable!("No tag found"),
     }
@@ -1479,7 +3153,7 @@ impl<'a> Tokenizer<'a> {
   /* -------------------------------------------- */
 
   fn get_duplicate_attribute_index(
-    &self,
+    &mut self,
     attributes: &EcoVec<Attribute>,
   ) -> EcoVec<usize> {
     let mut seen = HashSet::new();
@@ -1487,7 +3161,7 @@ impl<'a> Tokenizer<'a> {
 
     for (index, attribute) in attributes.iter().enumerate() {
       if seen.contains(&attribute.name) {
-        warn!("duplicate-attribute");
+        self.parse_error(ParseError::DuplicateAttribute);
         remove_indexes.push(index);
       } else {
         seen.insert(attribute.name.clone());
@@ -1501,25 +3175,50 @@ impl<'a> Tokenizer<'a> {
     let mut token = token;
     if let Token::Tag {
       is_end_tag,
+      self_closing,
       ref mut attributes,
       ..
     } = token
     {
       if !attributes.is_empty() {
-        for index in self.get_duplicate_attribute_index(attributes) {
-          attributes.remove(index);
+        // 後ろから削除しないと、インデックスがずれる
+        for index in self.get_duplicate_attribute_index(attributes).iter().rev()
+        {
+          attributes.remove(*index);
         }
       }
 
+      if let Some(max_attributes) = self.max_attributes {
+        attributes.truncate(max_attributes);
+      }
+
+      if is_end_tag && !attributes.is_empty() {
+        self.parse_error(ParseError::EndTagWithAttributes);
+      }
+
+      if is_end_tag && self_closing {
+        self.parse_error(ParseError::EndTagWithTrailingSolidus);
+      }
+
       if !is_end_tag {
         self.last_emitted_start_tag = Some(token.clone());
       }
     }
-    self.output.push_back(token);
+    self.push_output(token);
+  }
+
+  // 前回発行した位置から現在位置までを、トークンの範囲とする
+  fn push_output(&mut self, token: Token) {
+    let end = self.stream.idx.min(self.stream.len());
+    let span = Span::new(self.last_emitted_end.min(end), end);
+    self.last_emitted_end = end;
+    self.output.push_back((token, span));
   }
 
   fn pop_token(&mut self) -> Token {
-    self.output.pop_front().unwrap()
+    let (token, span) = self.output.pop_front().unwrap();
+    self.current_span = span;
+    token
   }
 
   fn emit_current_token(&mut self) -> Token {
@@ -1533,6 +3232,22 @@ impl<'a> Tokenizer<'a> {
     self.emit_current_token()
   }
 
+  // 直前に読んだlenバイトのテキストを、保留しているテキストに続けて発行する
+  // 入力の末尾で途切れた場合は、続きの入力が届くまで発行を保留する
+  // こうすることで、チャンクの区切り方によってテキストトークンの分かれ方が変わらない
+  fn emit_text_run(&mut self, len: usize) -> Option<Token> {
+    let end = self.stream.idx;
+    let start = self.text_run_start.take().unwrap_or(end - len);
+
+    if self.stream.is_waiting() {
+      self.text_run_start = Some(start);
+      return None;
+    }
+
+    let bytes = self.stream.slice(start, end);
+    Some(self.emit_text(&bytes))
+  }
+
   fn emit_char(&mut self, c: char) -> Token {
     self.new_token(Token::Text(EcoString::from(c)));
     self.emit_current_token()
@@ -1543,13 +3258,89 @@ impl<'a> Tokenizer<'a> {
     self.emit_current_token()
   }
 
+  // eof-in-doctype: force-quirksを設定したDOCTYPEトークンとEOFを発行する
+  fn emit_doctype_in_eof(&mut self) -> Token {
+    self.parse_error(ParseError::EofInDoctype);
+    self.set_force_quirks();
+    self.will_emit(self.current_token.clone().unwrap());
+    self.emit_eof()
+  }
+
   fn emit_tmp_buffer(&mut self) {
-    self.output.push_back(Token::Text(bytes_to_string(&self.tmp_buffer)));
+    self.push_output(Token::Text(bytes_to_string(&self.tmp_buffer)));
+  }
+
+  /* error ------------------------------------- */
+
+  // https://html.spec.whatwg.org/multipage/parsing.html#preprocessing-the-input-stream
+  // まだ調べていない入力から、制御文字と非文字を探す
+  // 入力は文字の途中で区切られないので、追加された分だけを調べればよい
+  fn check_input_stream(&mut self) {
+    let end = self.stream.len();
+    if !self.collect_errors || self.checked_location.offset >= end {
+      return;
+    }
+
+    let bytes = self.stream.range(self.checked_location.offset, end);
+    let mut location = self.checked_location;
+    let mut checked = 0;
+    let mut next = 0;
+
+    // ASCIIの印字可能文字は飛ばし、制御文字と非文字になりうる文字の先頭バイトだけを調べる
+    // U+0000は、各状態でunexpected-null-characterとして扱う
+    while let Some(position) =
+      bytes[next..].iter().position(|b| !(0x20..0x7F).contains(b))
+    {
+      let index = next + position;
+      next = index + 1;
+
+      if !matches!(
+        bytes[index],
+        0x01..=0x08 | 0x0B | 0x0E..=0x1F | 0x7F | 0xC2 | 0xEF | 0xF0..=0xF4
+      ) {
+        continue;
+      }
+
+      let Some(c) = first_char(&bytes[index..bytes.len().min(index + 4)])
+      else {
+        continue;
+      };
+      let code = c as u32;
+      let error = if is_noncharacter(code) {
+        ParseError::NoncharacterInInputStream
+      } else if is_control_except_whitespace(code) {
+        ParseError::ControlCharacterInInputStream
+      } else {
+        continue;
+      };
+
+      location = location.advance(&bytes[checked..index]);
+      checked = index;
+
+      let error = error(location);
+      warn!("{}", error);
+      self.errors.push(error);
+    }
+
+    self.checked_location = location.advance(&bytes[checked..]);
+  }
+
+  fn parse_error(&mut self, error: fn(SourceLocation) -> ParseError) {
+    if !self.collect_errors {
+      return;
+    }
+
+    let error = error(self.current_location());
+    warn!("{}", error);
+    self.errors.push(error);
   }
 
   /* tmp buffer --------------------------------- */
 
   fn clear_tmp_buffer(&mut self) {
+    if !self.stream.is_complete() && self.cleared_tmp_buffer.is_none() {
+      self.cleared_tmp_buffer = Some(std::mem::take(&mut self.tmp_buffer));
+    }
     self.tmp_buffer.clear();
     trace!("-- tmp_buffer_clear");
   }
@@ -1562,6 +3353,10 @@ impl<'a> Tokenizer<'a> {
     );
   }
 
+  fn push_char_to_tmp_buffer(&mut self, c: char) {
+    self.push_many_to_tmp_buffer(c.encode_utf8(&mut [0; 4]).as_bytes());
+  }
+
   fn push_many_to_tmp_buffer(&mut self, bytes: &[u8]) {
     self.tmp_buffer.extend_from_slice(bytes);
     trace!(
@@ -1572,6 +3367,32 @@ impl<'a> Tokenizer<'a> {
 
   /* character reference ------------------------ */
 
+  // 桁あふれしないよう、Unicodeの範囲を超えたら以降は無視する
+  fn append_digit_to_character_reference_code(
+    &mut self,
+    radix: u32,
+    digit: u32,
+  ) {
+    if self.character_reference_code > 0x10FFFF {
+      return;
+    }
+    self.character_reference_code =
+      self.character_reference_code * radix + digit;
+  }
+
+  // 数字列を読み終えたあと、セミコロンの有無を確認してEnd状態へ進む
+  fn finish_numeric_character_reference_digits(&mut self) {
+    match self.stream.current_cpy() {
+      Some(b';') => {
+        self.switch_to(State::NumericCharacterReferenceEnd);
+      }
+      _ => {
+        self.parse_error(ParseError::MissingSemicolonAfterCharacterReference);
+        self.reconsume_in(State::NumericCharacterReferenceEnd);
+      }
+    }
+  }
+
   fn flush_code_points_consumed_as_a_character_reference(&mut self) {
     if self.is_character_part_of_attribute() {
       self.concat_to_attribute_value(self.tmp_buffer.clone().as_slice());
@@ -1595,21 +3416,13 @@ impl<'a> Tokenizer<'a> {
 
   /* -------------------------------------------- */
 
+  // 入力の末尾では\0を返すので、呼び出し側でis_eofを確認する
   fn read_current(&mut self) -> u8 {
-    self.stream.current_cpy().unwrap()
+    self.stream.current_cpy().unwrap_or(b'\0')
   }
 
-  fn read_current_skipped_whitespace(&mut self) -> u8 {
-    let start = self.stream.idx;
-    let rest = &self.stream.data()[start..];
-
-    let end = rest
-      .iter()
-      .position(|&b| !b.is_ascii_whitespace())
-      .unwrap_or(self.stream.len() - start);
-
-    self.stream.advance_by(end);
-    self.stream.current_cpy().unwrap()
+  fn skip_whitespace(&mut self) {
+    self.read_while(|b| b.is_ascii_whitespace());
   }
 
   // patternに合致するなら、進める
@@ -1631,7 +3444,7 @@ impl<'a> Tokenizer<'a> {
         self.stream.advance_by(pattern_len - 1);
         return true;
       }
-    } else if peeked == pattern {
+    } else if *peeked == *pattern {
       // for _ in 0..pattern.len() { self.stream.advance(); } のイメージ
       self.stream.advance_by(pattern_len - 1);
       return true;
@@ -1640,9 +3453,9 @@ impl<'a> Tokenizer<'a> {
     false
   }
 
-  fn read_to(&mut self, c: u8) -> &'a [u8] {
+  fn read_to(&mut self, c: u8) -> StreamSlice<'a, u8> {
     let start = self.stream.idx;
-    let bytes = &self.stream.data()[start..];
+    let bytes = self.stream.rest();
 
     let end =
       bytes.iter().position(|&b| b == c).unwrap_or(self.stream.len() - start);
@@ -1653,9 +3466,9 @@ impl<'a> Tokenizer<'a> {
 
   // cに遭遇するまで読み進める
   // cを含む位置を返すので注意
-  fn read_to_oneof(&mut self, c: &[u8]) -> &'a [u8] {
+  fn read_to_oneof(&mut self, c: &[u8]) -> StreamSlice<'a, u8> {
     let start = self.stream.idx;
-    let bytes = &self.stream.data()[start..];
+    let bytes = self.stream.rest();
 
     let end = bytes
       .iter()
@@ -1666,9 +3479,9 @@ impl<'a> Tokenizer<'a> {
     self.stream.slice(start, start + end)
   }
 
-  fn read_to_whitespace_or_oneof(&mut self, c: &[u8]) -> &'a [u8] {
+  fn read_to_whitespace_or_oneof(&mut self, c: &[u8]) -> StreamSlice<'a, u8> {
     let start = self.stream.idx;
-    let bytes = &self.stream.data()[start..];
+    let bytes = self.stream.rest();
 
     let end = bytes
       .iter()
@@ -1679,24 +3492,35 @@ impl<'a> Tokenizer<'a> {
     self.stream.slice(start, start + end)
   }
 
-  fn peek_next(&self) -> Option<&u8> {
-    self.stream.peek(1)
+  fn read_while(&mut self, f: impl Fn(u8) -> bool) -> StreamSlice<'a, u8> {
+    let start = self.stream.idx;
+    let bytes = self.stream.rest();
+
+    let end =
+      bytes.iter().position(|&b| !f(b)).unwrap_or(self.stream.len() - start);
+
+    self.stream.advance_by(end);
+    self.stream.slice(start, start + end)
   }
 
+  // max_lenより先は読まない
   fn peek_while_with_last(
     &mut self,
     f: impl Fn(u8) -> bool,
     except_last: u8,
-  ) -> &'a [u8] {
+    max_len: usize,
+  ) -> StreamSlice<'a, u8> {
     let start = self.stream.idx;
-    let bytes = &self.stream.data()[start..];
+    let bytes = self.stream.rest();
+    let bytes = &bytes[..bytes.len().min(max_len)];
 
-    let mut end =
-      bytes.iter().position(|&b| !f(b)).unwrap_or(self.stream.len() - start);
+    let mut end = bytes.iter().position(|&b| !f(b)).unwrap_or(bytes.len());
 
-    if let Some(next) = self.stream.peek(end) {
-      if *next == except_last {
-        end += 1;
+    if end < max_len {
+      if let Some(next) = self.stream.peek(end) {
+        if *next == except_last {
+          end += 1;
+        }
       }
     }
 
@@ -1707,3 +3531,12 @@ impl<'a> Tokenizer<'a> {
     self.stream.advance_by(offset);
   }
 }
+
+// 先頭のUTF-8の文字を返す
+fn first_char(bytes: &[u8]) -> Option<char> {
+  let valid = match from_utf8(bytes) {
+    Ok(str) => str,
+    Err(error) => from_utf8(&bytes[..error.valid_up_to()]).unwrap(),
+  };
+  valid.chars().next()
+}
@@ -12,6 +12,27 @@ pub enum State {
   RAWTEXTEndTagOpen,
   RAWTEXTEndTagName,
 
+  ScriptData,
+  ScriptDataLessThanSign,
+  ScriptDataEndTagOpen,
+  ScriptDataEndTagName,
+  ScriptDataEscapeStart,
+  ScriptDataEscapeStartDash,
+  ScriptDataEscaped,
+  ScriptDataEscapedDash,
+  ScriptDataEscapedDashDash,
+  ScriptDataEscapedLessThanSign,
+  ScriptDataEscapedEndTagOpen,
+  ScriptDataEscapedEndTagName,
+  ScriptDataDoubleEscapeStart,
+  ScriptDataDoubleEscaped,
+  ScriptDataDoubleEscapedDash,
+  ScriptDataDoubleEscapedDashDash,
+  ScriptDataDoubleEscapedLessThanSign,
+  ScriptDataDoubleEscapeEnd,
+
+  PLAINTEXT,
+
   TagName,
   TagOpen,
   EndTagOpen,
@@ -41,10 +62,25 @@ pub enum State {
   CommentEndBang,
   BogusComment,
 
+  CDATASection,
+  CDATASectionBracket,
+  CDATASectionEnd,
+
   DOCTYPE,
   BeforeDOCTYPEName,
   DOCTYPEName,
   AfterDOCTYPEName,
+  AfterDOCTYPEPublicKeyword,
+  BeforeDOCTYPEPublicIdentifier,
+  DOCTYPEPublicIdentifierDoubleQuoted,
+  DOCTYPEPublicIdentifierSingleQuoted,
+  AfterDOCTYPEPublicIdentifier,
+  BetweenDOCTYPEPublicAndSystemIdentifiers,
+  AfterDOCTYPESystemKeyword,
+  BeforeDOCTYPESystemIdentifier,
+  DOCTYPESystemIdentifierDoubleQuoted,
+  DOCTYPESystemIdentifierSingleQuoted,
+  AfterDOCTYPESystemIdentifier,
   BogusDOCTYPE,
 
   CharacterReference,
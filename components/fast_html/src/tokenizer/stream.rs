@@ -1,18 +1,57 @@
+use std::cell::Cell;
 use std::cmp::min;
+use std::ops::Deref;
+use std::rc::Rc;
 
+// idxなどの位置は、捨てた入力も含めた先頭から数える
 #[derive(Debug)]
 pub struct Stream<'a, T> {
   pub idx: usize,
-  data: &'a [T],
+  input: Input<'a, T>,
+  // 入力がすべて揃っているかどうか
+  complete: bool,
+  // 揃っていない入力の末尾を読もうとしたかどうか
+  reached_end: Cell<bool>,
 }
 
-impl<'a, T: Copy> Stream<'a, T> {
+#[derive(Debug)]
+enum Input<'a, T> {
+  // まとめて与えられた入力は、コピーせずに借りる
+  Borrowed(&'a [T]),
+  // 少しずつ追加される入力
+  // offsetより前の、読み終えた部分は捨てている
+  Buffered { data: Rc<Vec<T>>, offset: usize },
+}
+
+// Streamの一部分
+// 追加される入力は共有しているので、Streamを読み進めている間も保持できる
+pub enum StreamSlice<'a, T> {
+  Borrowed(&'a [T]),
+  Shared {
+    data: Rc<Vec<T>>,
+    from: usize,
+    to: usize,
+  },
+}
+
+impl<T> Deref for StreamSlice<'_, T> {
+  type Target = [T];
+
+  fn deref(&self) -> &Self::Target {
+    match self {
+      StreamSlice::Borrowed(data) => data,
+      StreamSlice::Shared { data, from, to } => &data[*from..*to],
+    }
+  }
+}
+
+impl<T: Copy> Stream<'_, T> {
   pub fn current_cpy(&self) -> Option<T> {
-    self.data.get(self.idx).copied()
+    self.current().copied()
   }
 }
 
-impl<'a, T: Eq + Copy> Stream<'a, T> {
+impl<T: Eq + Copy> Stream<'_, T> {
   pub fn expect_and_skip(&mut self, expect: T) -> Option<T> {
     let c = self.current_cpy()?;
     if c == expect {
@@ -40,16 +79,61 @@ impl<'a, T: Eq + Copy> Stream<'a, T> {
 }
 
 impl<'a, T> Stream<'a, T> {
-  pub fn new(data: &'a [T]) -> Stream<T> {
-    Self { data, idx: 0 }
+  pub fn new(data: &'a [T]) -> Stream<'a, T> {
+    Self {
+      idx: 0,
+      input: Input::Borrowed(data),
+      complete: true,
+      reached_end: Cell::new(false),
+    }
+  }
+
+  // 入力を少しずつ追加していくStreamを作る
+  pub fn incomplete() -> Stream<'a, T> {
+    Self {
+      idx: 0,
+      input: Input::Buffered {
+        data: Rc::new(Vec::new()),
+        offset: 0,
+      },
+      complete: false,
+      reached_end: Cell::new(false),
+    }
+  }
+
+  // 捨てた入力の長さ
+  fn offset(&self) -> usize {
+    match &self.input {
+      Input::Borrowed(_) => 0,
+      Input::Buffered { offset, .. } => *offset,
+    }
+  }
+
+  // 残している入力
+  fn items(&self) -> &[T] {
+    match &self.input {
+      Input::Borrowed(data) => data,
+      Input::Buffered { data, .. } => data,
+    }
   }
 
   pub fn len(&self) -> usize {
-    self.data.len()
+    self.offset() + self.items().len()
+  }
+
+  // fromからtoまでの入力
+  // 捨てた入力や、まだ届いていない入力は含まない
+  pub fn range(&self, from: usize, to: usize) -> &[T] {
+    let offset = self.offset();
+    let items = self.items();
+    let to = to.clamp(offset, self.len()) - offset;
+    let from = from.clamp(offset, offset + to) - offset;
+    &items[from..to]
   }
 
-  pub fn data(&self) -> &[T] {
-    self.data
+  // 現在位置から末尾までの入力
+  pub fn rest(&self) -> &[T] {
+    self.range(self.idx, self.len())
   }
 
   pub fn advance(&mut self) {
@@ -61,26 +145,104 @@ impl<'a, T> Stream<'a, T> {
   }
 
   pub fn current(&self) -> Option<&T> {
-    self.data.get(self.idx)
+    self.peek(0)
   }
 
   pub fn peek(&self, offset: usize) -> Option<&T> {
-    self.data.get(self.idx + offset)
+    let item = (self.idx + offset)
+      .checked_sub(self.offset())
+      .and_then(|idx| self.items().get(idx));
+    if item.is_none() {
+      self.touch_end();
+    }
+    item
   }
 
   pub fn is_eof(&self) -> bool {
-    self.idx >= self.data.len()
+    let is_eof = self.idx >= self.len();
+    if is_eof {
+      self.touch_end();
+    }
+    is_eof
   }
 
-  pub fn slice(&self, from: usize, to: usize) -> &'a [T] {
-    &self.data[from..to]
+  pub fn slice(&self, from: usize, to: usize) -> StreamSlice<'a, T> {
+    match &self.input {
+      Input::Borrowed(data) => StreamSlice::Borrowed(&data[from..to]),
+      Input::Buffered { data, offset } => StreamSlice::Shared {
+        data: Rc::clone(data),
+        from: from - offset,
+        to: to - offset,
+      },
+    }
   }
 
-  pub fn slice_checked(&self, from: usize, to: usize) -> &'a [T] {
-    &self.data[from..min(self.data.len(), to)]
+  pub fn slice_checked(&self, from: usize, to: usize) -> StreamSlice<'a, T> {
+    if to > self.len() {
+      self.touch_end();
+    }
+    self.slice(from, min(self.len(), to))
   }
 
-  pub fn slice_len(&self, len: usize) -> &'a [T] {
+  pub fn slice_len(&self, len: usize) -> StreamSlice<'a, T> {
     self.slice_checked(self.idx, self.idx + len)
   }
+
+  /* incomplete input --------------------------- */
+
+  pub fn is_complete(&self) -> bool {
+    self.complete
+  }
+
+  pub fn complete(&mut self) {
+    self.complete = true;
+  }
+
+  // 揃っている入力を読み終えて、続きを待っている
+  // is_eofと違い、末尾を読もうとしたことにはならない
+  pub fn is_waiting(&self) -> bool {
+    !self.complete && self.idx >= self.len()
+  }
+
+  // 入力が揃っていないのに末尾まで読もうとした
+  // 続きの入力次第で結果が変わるので、読んだ結果を信用できない
+  pub fn touch_end(&self) {
+    if !self.complete {
+      self.reached_end.set(true);
+    }
+  }
+
+  pub fn reached_end(&self) -> bool {
+    self.reached_end.get()
+  }
+
+  pub fn clear_reached_end(&self) {
+    self.reached_end.set(false);
+  }
+}
+
+impl<T: Clone> Stream<'_, T> {
+  pub fn push(&mut self, chunk: &[T]) {
+    let Input::Buffered { data, .. } = &mut self.input else {
+      unreachable!("Cannot push to a complete stream");
+    };
+
+    // StreamSliceが残っていなければ、コピーせずに追記される
+    Rc::make_mut(data).extend_from_slice(chunk);
+  }
+
+  // untilより前の入力を捨てる
+  pub fn discard(&mut self, until: usize) {
+    let Input::Buffered { data, offset } = &mut self.input else {
+      return;
+    };
+
+    if until <= *offset {
+      return;
+    }
+
+    let count = min(until - *offset, data.len());
+    Rc::make_mut(data).drain(..count);
+    *offset += count;
+  }
 }
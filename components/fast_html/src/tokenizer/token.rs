@@ -14,6 +14,8 @@ pub struct Attribute {
 pub enum Token {
   DOCTYPE {
     name: Option<EcoString>,
+    public_identifier: Option<EcoString>,
+    system_identifier: Option<EcoString>,
     force_quirks: bool,
   },
   Tag {
@@ -28,6 +30,27 @@ pub enum Token {
   EOF,
 }
 
+// 組み立て中のトークンの各部分の長さ
+// トークンには追記していくだけなので、この長さまで切り詰めれば元に戻せる
+#[derive(Debug, Clone, Copy)]
+pub enum TokenMark {
+  DOCTYPE {
+    name: Option<usize>,
+    public_identifier: Option<usize>,
+    system_identifier: Option<usize>,
+    force_quirks: bool,
+  },
+  Tag {
+    tag_name: usize,
+    attributes: usize,
+    last_name: usize,
+    last_value: usize,
+    self_closing: bool,
+  },
+  Comment(usize),
+  Other,
+}
+
 impl Token {
   /* constructor -------------------------------- */
 
@@ -61,6 +84,16 @@ impl Token {
     }
   }
 
+  pub fn new_end_tag_of(name: &str) -> Self {
+    Token::Tag {
+      tag_name: EcoString::from(name),
+      is_end_tag: true,
+      self_closing: false,
+      self_closing_acknowledged: false,
+      attributes: EcoVec::new(),
+    }
+  }
+
   pub fn new_text(text: &str) -> Self {
     Token::Text(EcoString::from(text))
   }
@@ -71,7 +104,19 @@ impl Token {
 
   pub fn new_doctype_char_of(ch: char) -> Self {
     Token::DOCTYPE {
-      name: Some(EcoString::from(ch)),
+      name: Some(EcoString::from(ch.to_ascii_lowercase())),
+      public_identifier: None,
+      system_identifier: None,
+      force_quirks: false,
+    }
+  }
+
+  // 名前は、このあと1文字ずつではなくまとめて追加する
+  pub fn new_doctype() -> Self {
+    Token::DOCTYPE {
+      name: Some(EcoString::new()),
+      public_identifier: None,
+      system_identifier: None,
       force_quirks: false,
     }
   }
@@ -79,6 +124,8 @@ impl Token {
   pub fn new_doctype_with_force_quirks() -> Self {
     Token::DOCTYPE {
       name: None,
+      public_identifier: None,
+      system_identifier: None,
       force_quirks: true,
     }
   }
@@ -101,8 +148,22 @@ impl Token {
     }
   }
 
+  pub fn attributes_mut(&mut self) -> &mut EcoVec<Attribute> {
+    if let Token::Tag { attributes, .. } = self {
+      attributes
+    } else {
+      panic!("Token is not a Tag");
+    }
+  }
+
   /* setter ------------------------------------- */
 
+  pub fn set_tag_name(&mut self, name: &str) {
+    if let Token::Tag { tag_name, .. } = self {
+      *tag_name = EcoString::from(name);
+    }
+  }
+
   pub fn acknowledge_self_closing_if_set(&mut self) {
     if let Token::Tag {
       ref mut self_closing_acknowledged,
@@ -126,6 +187,103 @@ impl Token {
     }
   }
 
+  /* mark --------------------------------------- */
+
+  pub fn mark(&self) -> TokenMark {
+    match self {
+      Token::DOCTYPE {
+        name,
+        public_identifier,
+        system_identifier,
+        force_quirks,
+      } => TokenMark::DOCTYPE {
+        name: name.as_ref().map(EcoString::len),
+        public_identifier: public_identifier.as_ref().map(EcoString::len),
+        system_identifier: system_identifier.as_ref().map(EcoString::len),
+        force_quirks: *force_quirks,
+      },
+      Token::Tag {
+        tag_name,
+        attributes,
+        self_closing,
+        ..
+      } => TokenMark::Tag {
+        tag_name: tag_name.len(),
+        attributes: attributes.len(),
+        last_name: attributes.last().map_or(0, |last| last.name.len()),
+        last_value: attributes.last().map_or(0, |last| last.value.len()),
+        self_closing: *self_closing,
+      },
+      Token::Comment(text) => TokenMark::Comment(text.len()),
+      _ => TokenMark::Other,
+    }
+  }
+
+  // markを取った時点まで戻す
+  pub fn rewind(&mut self, mark: TokenMark) {
+    // 巻き戻すのは直前に追記した分だけなので、末尾から1文字ずつ取り除く
+    fn truncate(value: &mut EcoString, len: usize) {
+      while value.len() > len {
+        value.pop();
+      }
+    }
+
+    fn rewind_option(value: &mut Option<EcoString>, len: Option<usize>) {
+      match (value.as_mut(), len) {
+        (Some(value), Some(len)) => truncate(value, len),
+        (_, None) => *value = None,
+        (None, Some(_)) => {}
+      }
+    }
+
+    match (self, mark) {
+      (
+        Token::DOCTYPE {
+          name,
+          public_identifier,
+          system_identifier,
+          force_quirks,
+        },
+        TokenMark::DOCTYPE {
+          name: name_len,
+          public_identifier: public_identifier_len,
+          system_identifier: system_identifier_len,
+          force_quirks: old_force_quirks,
+        },
+      ) => {
+        rewind_option(name, name_len);
+        rewind_option(public_identifier, public_identifier_len);
+        rewind_option(system_identifier, system_identifier_len);
+        *force_quirks = old_force_quirks;
+      }
+      (
+        Token::Tag {
+          tag_name,
+          attributes,
+          self_closing,
+          ..
+        },
+        TokenMark::Tag {
+          tag_name: tag_name_len,
+          attributes: attributes_len,
+          last_name,
+          last_value,
+          self_closing: old_self_closing,
+        },
+      ) => {
+        truncate(tag_name, tag_name_len);
+        attributes.truncate(attributes_len);
+        if let Some(last) = attributes.make_mut().last_mut() {
+          truncate(&mut last.name, last_name);
+          truncate(&mut last.value, last_value);
+        }
+        *self_closing = old_self_closing;
+      }
+      (Token::Comment(text), TokenMark::Comment(len)) => truncate(text, len),
+      _ => {}
+    }
+  }
+
   /* checker ------------------------------------ */
 
   pub fn is_start_tag(&self) -> bool {
@@ -142,6 +300,13 @@ impl Token {
     }
   }
 
+  pub fn is_self_closing(&self) -> bool {
+    match self {
+      Token::Tag { self_closing, .. } => *self_closing,
+      _ => false,
+    }
+  }
+
   pub fn is_eof(&self) -> bool {
     match self {
       Token::EOF => true,
@@ -1,4 +1,8 @@
-const SPECIAL_ELEMENTS: [&str; 82] = [
+use fast_dom::namespace::Namespace;
+
+use crate::sink::NodeHandle;
+
+const SPECIAL_ELEMENTS: [&str; 83] = [
   "address",
   "applet",
   "area",
@@ -62,6 +66,7 @@ const SPECIAL_ELEMENTS: [&str; 82] = [
   "plaintext",
   "pre",
   "script",
+  "search",
   "section",
   "select",
   "source",
@@ -83,6 +88,18 @@ const SPECIAL_ELEMENTS: [&str; 82] = [
   "xmp",
 ];
 
-pub fn is_special_element(name: &str) -> bool {
-  SPECIAL_ELEMENTS.contains(&name)
+const SPECIAL_MATHML_ELEMENTS: [&str; 6] =
+  ["mi", "mo", "mn", "ms", "mtext", "annotation-xml"];
+
+const SPECIAL_SVG_ELEMENTS: [&str; 3] = ["foreignObject", "desc", "title"];
+
+pub fn is_special_element<H: NodeHandle>(element: &H) -> bool {
+  let namespace = element.namespace();
+  let names: &[&str] = match namespace {
+    Namespace::Html => &SPECIAL_ELEMENTS,
+    Namespace::MathMl => &SPECIAL_MATHML_ELEMENTS,
+    Namespace::Svg => &SPECIAL_SVG_ELEMENTS,
+    _ => return false,
+  };
+  element.match_name_in(namespace, names)
 }
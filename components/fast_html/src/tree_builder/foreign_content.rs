@@ -0,0 +1,249 @@
+use fast_dom::namespace::Namespace;
+
+use crate::sink::{NodeHandle, TreeSink};
+
+use crate::tokenizer::token::Token;
+
+// https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inforeign
+// SVG要素のタグ名は、トークナイザで小文字化されたものを元の大文字小文字に戻す
+const SVG_TAG_NAME_ADJUSTMENTS: [(&str, &str); 37] = [
+  ("altglyph", "altGlyph"),
+  ("altglyphdef", "altGlyphDef"),
+  ("altglyphitem", "altGlyphItem"),
+  ("animatecolor", "animateColor"),
+  ("animatemotion", "animateMotion"),
+  ("animatetransform", "animateTransform"),
+  ("clippath", "clipPath"),
+  ("feblend", "feBlend"),
+  ("fecolormatrix", "feColorMatrix"),
+  ("fecomponenttransfer", "feComponentTransfer"),
+  ("fecomposite", "feComposite"),
+  ("feconvolvematrix", "feConvolveMatrix"),
+  ("fediffuselighting", "feDiffuseLighting"),
+  ("fedisplacementmap", "feDisplacementMap"),
+  ("fedistantlight", "feDistantLight"),
+  ("fedropshadow", "feDropShadow"),
+  ("feflood", "feFlood"),
+  ("fefunca", "feFuncA"),
+  ("fefuncb", "feFuncB"),
+  ("fefuncg", "feFuncG"),
+  ("fefuncr", "feFuncR"),
+  ("fegaussianblur", "feGaussianBlur"),
+  ("feimage", "feImage"),
+  ("femerge", "feMerge"),
+  ("femergenode", "feMergeNode"),
+  ("femorphology", "feMorphology"),
+  ("feoffset", "feOffset"),
+  ("fepointlight", "fePointLight"),
+  ("fespecularlighting", "feSpecularLighting"),
+  ("fespotlight", "feSpotLight"),
+  ("fetile", "feTile"),
+  ("feturbulence", "feTurbulence"),
+  ("foreignobject", "foreignObject"),
+  ("glyphref", "glyphRef"),
+  ("lineargradient", "linearGradient"),
+  ("radialgradient", "radialGradient"),
+  ("textpath", "textPath"),
+];
+
+// https://html.spec.whatwg.org/multipage/parsing.html#adjust-svg-attributes
+const SVG_ATTRIBUTE_NAME_ADJUSTMENTS: [(&str, &str); 58] = [
+  ("attributename", "attributeName"),
+  ("attributetype", "attributeType"),
+  ("basefrequency", "baseFrequency"),
+  ("baseprofile", "baseProfile"),
+  ("calcmode", "calcMode"),
+  ("clippathunits", "clipPathUnits"),
+  ("diffuseconstant", "diffuseConstant"),
+  ("edgemode", "edgeMode"),
+  ("filterunits", "filterUnits"),
+  ("glyphref", "glyphRef"),
+  ("gradienttransform", "gradientTransform"),
+  ("gradientunits", "gradientUnits"),
+  ("kernelmatrix", "kernelMatrix"),
+  ("kernelunitlength", "kernelUnitLength"),
+  ("keypoints", "keyPoints"),
+  ("keysplines", "keySplines"),
+  ("keytimes", "keyTimes"),
+  ("lengthadjust", "lengthAdjust"),
+  ("limitingconeangle", "limitingConeAngle"),
+  ("markerheight", "markerHeight"),
+  ("markerunits", "markerUnits"),
+  ("markerwidth", "markerWidth"),
+  ("maskcontentunits", "maskContentUnits"),
+  ("maskunits", "maskUnits"),
+  ("numoctaves", "numOctaves"),
+  ("pathlength", "pathLength"),
+  ("patterncontentunits", "patternContentUnits"),
+  ("patterntransform", "patternTransform"),
+  ("patternunits", "patternUnits"),
+  ("pointsatx", "pointsAtX"),
+  ("pointsaty", "pointsAtY"),
+  ("pointsatz", "pointsAtZ"),
+  ("preservealpha", "preserveAlpha"),
+  ("preserveaspectratio", "preserveAspectRatio"),
+  ("primitiveunits", "primitiveUnits"),
+  ("refx", "refX"),
+  ("refy", "refY"),
+  ("repeatcount", "repeatCount"),
+  ("repeatdur", "repeatDur"),
+  ("requiredextensions", "requiredExtensions"),
+  ("requiredfeatures", "requiredFeatures"),
+  ("specularconstant", "specularConstant"),
+  ("specularexponent", "specularExponent"),
+  ("spreadmethod", "spreadMethod"),
+  ("startoffset", "startOffset"),
+  ("stddeviation", "stdDeviation"),
+  ("stitchtiles", "stitchTiles"),
+  ("surfacescale", "surfaceScale"),
+  ("systemlanguage", "systemLanguage"),
+  ("tablevalues", "tableValues"),
+  ("targetx", "targetX"),
+  ("targety", "targetY"),
+  ("textlength", "textLength"),
+  ("viewbox", "viewBox"),
+  ("viewtarget", "viewTarget"),
+  ("xchannelselector", "xChannelSelector"),
+  ("ychannelselector", "yChannelSelector"),
+  ("zoomandpan", "zoomAndPan"),
+];
+
+// https://html.spec.whatwg.org/multipage/parsing.html#adjust-foreign-attributes
+// (属性名, 接頭辞, ローカル名, 名前空間)
+const FOREIGN_ATTRIBUTE_ADJUSTMENTS: [(&str, Option<&str>, &str, Namespace);
+  11] = [
+  ("xlink:actuate", Some("xlink"), "actuate", Namespace::XLink),
+  ("xlink:arcrole", Some("xlink"), "arcrole", Namespace::XLink),
+  ("xlink:href", Some("xlink"), "href", Namespace::XLink),
+  ("xlink:role", Some("xlink"), "role", Namespace::XLink),
+  ("xlink:show", Some("xlink"), "show", Namespace::XLink),
+  ("xlink:title", Some("xlink"), "title", Namespace::XLink),
+  ("xlink:type", Some("xlink"), "type", Namespace::XLink),
+  ("xml:lang", Some("xml"), "lang", Namespace::Xml),
+  ("xml:space", Some("xml"), "space", Namespace::Xml),
+  ("xmlns", None, "xmlns", Namespace::Xmlns),
+  ("xmlns:xlink", Some("xmlns"), "xlink", Namespace::Xmlns),
+];
+
+// 外部コンテンツの中にあっても、HTMLの要素として扱われる開始タグ
+const BREAKOUT_TAG_NAMES: [&str; 45] = [
+  "b",
+  "big",
+  "blockquote",
+  "body",
+  "br",
+  "center",
+  "code",
+  "dd",
+  "div",
+  "dl",
+  "dt",
+  "em",
+  "embed",
+  "h1",
+  "h2",
+  "h3",
+  "h4",
+  "h5",
+  "h6",
+  "head",
+  "hr",
+  "i",
+  "img",
+  "li",
+  "listing",
+  "menu",
+  "meta",
+  "nobr",
+  "ol",
+  "p",
+  "pre",
+  "ruby",
+  "s",
+  "small",
+  "span",
+  "strong",
+  "strike",
+  "sub",
+  "sup",
+  "table",
+  "tt",
+  "u",
+  "ul",
+  "var",
+  "font",
+];
+
+pub fn adjust_svg_tag_name(token: &mut Token) {
+  let adjusted = SVG_TAG_NAME_ADJUSTMENTS
+    .iter()
+    .find(|(from, _)| token.tag_name() == *from)
+    .map(|(_, to)| *to);
+
+  if let Some(name) = adjusted {
+    token.set_tag_name(name);
+  }
+}
+
+pub fn adjust_svg_attributes(token: &mut Token) {
+  for attr in token.attributes_mut().make_mut() {
+    if let Some((_, to)) =
+      SVG_ATTRIBUTE_NAME_ADJUSTMENTS.iter().find(|(from, _)| attr.name == *from)
+    {
+      attr.name = (*to).into();
+    }
+  }
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#adjust-mathml-attributes
+pub fn adjust_mathml_attributes(token: &mut Token) {
+  for attr in token.attributes_mut().make_mut() {
+    if attr.name == "definitionurl" {
+      attr.name = "definitionURL".into();
+    }
+  }
+}
+
+pub fn adjust_foreign_attribute(
+  name: &str,
+) -> Option<(Option<&'static str>, &'static str, Namespace)> {
+  FOREIGN_ATTRIBUTE_ADJUSTMENTS.iter().find(|(from, ..)| *from == name).map(
+    |(_, prefix, local_name, namespace)| (*prefix, *local_name, *namespace),
+  )
+}
+
+// fontは、color・face・size属性のいずれかを持つ場合のみ
+pub fn is_breakout_start_tag(token: &Token) -> bool {
+  if !token.is_start_tag() || !token.match_tag_name_in(&BREAKOUT_TAG_NAMES) {
+    return false;
+  }
+
+  if token.tag_name() == "font" {
+    return token
+      .attributes()
+      .iter()
+      .any(|attr| ["color", "face", "size"].contains(&attr.name.as_str()));
+  }
+
+  true
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#mathml-text-integration-point
+pub fn is_mathml_text_integration_point<H: NodeHandle>(element: &H) -> bool {
+  element.match_name_in(Namespace::MathMl, &["mi", "mo", "mn", "ms", "mtext"])
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#html-integration-point
+pub fn is_html_integration_point<S: TreeSink>(
+  sink: &S,
+  element: &S::Handle,
+) -> bool {
+  if element.match_name_in(Namespace::MathMl, &["annotation-xml"]) {
+    return sink.get_attribute(element, "encoding").is_some_and(|encoding| {
+      encoding.eq_ignore_ascii_case("text/html")
+        || encoding.eq_ignore_ascii_case("application/xhtml+xml")
+    });
+  }
+
+  element.match_name_in(Namespace::Svg, &["foreignObject", "desc", "title"])
+}
@@ -6,6 +6,7 @@ pub enum InsertMode {
 
   BeforeHead,
   InHead,
+  InHeadNoscript,
   AfterHead,
 
   InBody,
@@ -24,4 +25,10 @@ pub enum InsertMode {
 
   InSelect,
   InSelectInTable,
+
+  InTemplate,
+
+  InFrameset,
+  AfterFrameset,
+  AfterAfterFrameset,
 }
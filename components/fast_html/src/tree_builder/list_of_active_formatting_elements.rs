@@ -1,86 +1,74 @@
-use std::{
-  ops::{Deref, DerefMut},
-  rc::Rc,
-};
+use std::ops::{Deref, DerefMut};
 
 use ecow::EcoVec;
 
-use fast_dom::node::NodePtr;
+use crate::sink::NodeHandle;
 
-#[derive(Debug)]
-pub struct ListOfActiveFormattingElements {
-  entries: EcoVec<Entry>,
+pub struct ListOfActiveFormattingElements<H> {
+  entries: EcoVec<Entry<H>>,
 }
 
-#[derive(Debug, Clone)]
-pub enum Entry {
+#[derive(Clone)]
+pub enum Entry<H> {
   Marker,
-  Element(NodePtr),
+  Element(H),
 }
 
-impl Deref for ListOfActiveFormattingElements {
-  type Target = EcoVec<Entry>;
+impl<H> Deref for ListOfActiveFormattingElements<H> {
+  type Target = EcoVec<Entry<H>>;
 
   fn deref(&self) -> &Self::Target {
     &self.entries
   }
 }
 
-impl DerefMut for ListOfActiveFormattingElements {
+impl<H> DerefMut for ListOfActiveFormattingElements<H> {
   fn deref_mut(&mut self) -> &mut Self::Target {
     &mut self.entries
   }
 }
 
-impl ListOfActiveFormattingElements {
+impl<H: NodeHandle> ListOfActiveFormattingElements<H> {
   pub fn new() -> Self {
     Self {
       entries: EcoVec::new(),
     }
   }
 
-  pub fn get_element_after_last_marker(
-    &self,
-    tag_name: &str,
-  ) -> Option<NodePtr> {
-    self.iter().rev().find_map(|entry| match entry {
-      Entry::Marker => None,
-      Entry::Element(node) => {
-        if node.as_element().tag_name() == tag_name {
-          Some(node.clone())
-        } else {
-          None
+  pub fn get_element_after_last_marker(&self, tag_name: &str) -> Option<H> {
+    for entry in self.iter().rev() {
+      match entry {
+        Entry::Marker => return None,
+        Entry::Element(node) => {
+          if node.local_name() == tag_name {
+            return Some(node.clone());
+          }
         }
       }
-    })
+    }
+    None
   }
 
-  pub fn get_index_of_node(&self, node: &NodePtr) -> Option<usize> {
+  pub fn get_index_of_node(&self, node: &H) -> Option<usize> {
     self.iter().rposition(|entry| match entry {
       Entry::Marker => false,
-      Entry::Element(e) => Rc::ptr_eq(e, node),
+      Entry::Element(e) => e.same_node(node),
     })
   }
 
-  pub fn remove_element(&mut self, element: &NodePtr) {
-    let index = self
-      .iter()
-      .rposition(|entry| match entry {
-        Entry::Marker => false,
-        Entry::Element(e) => Rc::ptr_eq(e, element),
-      })
-      .unwrap_or_else(|| {
-        panic!("Unable to find active element: {:?}", element)
-      });
-    self.entries.remove(index);
+  // すでに取り除かれている場合は何もしない
+  pub fn remove_element(&mut self, element: &H) {
+    if let Some(index) = self.get_index_of_node(element) {
+      self.entries.remove(index);
+    }
   }
 
-  pub fn contains_node(&self, node: &NodePtr) -> bool {
+  pub fn contains_node(&self, node: &H) -> bool {
     self
       .iter()
       .rfind(|entry| match entry {
         Entry::Marker => false,
-        Entry::Element(e) => Rc::ptr_eq(e, node),
+        Entry::Element(e) => e.same_node(node),
       })
       .is_some()
   }
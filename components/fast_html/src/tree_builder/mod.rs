@@ -1,42 +1,50 @@
 mod element_types;
+mod foreign_content;
 mod insert_mode;
 mod list_of_active_formatting_elements;
+mod quirks_mode;
 mod stack_of_open_elements;
 
-use std::rc::Rc;
-
-use fast_dom::node::DOMNode;
+use fast_dom::document::QuirksMode;
+use fast_dom::namespace::Namespace;
 use fast_dom::node::NodePtr;
-use fast_dom::text::Text;
-use fast_dom::tree::TreeNode;
-use fast_dom::tree::WeakTreeNode;
-use fast_dom::{document::DocumentType, node::DOMNodeData};
+use fast_dom::span::Span;
 
 use ecow::{EcoString, EcoVec};
 
 use log::{debug, warn};
 
+use crate::error::{ParseError, SourceLocation};
+use crate::options::ParserOptions;
+use crate::sink::{FastDomSink, NodeHandle, TreeSink};
 use crate::tokenizer;
 
-use super::tokenizer::token::Attribute;
 use super::tokenizer::token::Token;
 use super::tokenizer::Tokenizer;
 
+pub use self::foreign_content::adjust_foreign_attribute;
+
 use self::element_types::is_special_element;
+use self::foreign_content::{
+  adjust_mathml_attributes, adjust_svg_attributes, adjust_svg_tag_name,
+  is_breakout_start_tag, is_html_integration_point,
+  is_mathml_text_integration_point,
+};
 use self::list_of_active_formatting_elements::Entry;
+use self::quirks_mode::quirks_mode_of;
 use self::{
   insert_mode::InsertMode,
   list_of_active_formatting_elements::ListOfActiveFormattingElements,
   stack_of_open_elements::StackOfOpenElements,
 };
 
-pub enum AdjustedInsertionLocation {
-  LastChild(NodePtr),
-  BeforeSibling(NodePtr, NodePtr),
+pub enum AdjustedInsertionLocation<H> {
+  LastChild(H),
+  BeforeSibling(H, H),
 }
 
-impl AdjustedInsertionLocation {
-  pub fn parent(&self) -> &NodePtr {
+impl<H> AdjustedInsertionLocation<H> {
+  pub fn parent(&self) -> &H {
     match self {
       AdjustedInsertionLocation::LastChild(parent) => parent,
       AdjustedInsertionLocation::BeforeSibling(parent, _) => parent,
@@ -54,62 +62,289 @@ enum AdoptionAgencyAlgorithmOutcome {
   RunAnyOtherEndTagsSteps,
 }
 
-pub struct TreeBuilder<'a> {
+// 木の組み立て方はsinkに任せるので、同じ木構築の実装で別のDOMを作れる
+pub struct TreeBuilder<'a, S: TreeSink = FastDomSink> {
   tokenizer: Tokenizer<'a>,
+  sink: S,
   insert_mode: InsertMode,
   original_insert_mode: Option<InsertMode>,
-  open_elements: StackOfOpenElements,
-  active_formatting_elements: ListOfActiveFormattingElements,
-  document: NodePtr,
-  head_pointer: Option<NodePtr>,
-  text_insertion_node: Option<NodePtr>,
+  template_insert_modes: Vec<InsertMode>,
+  open_elements: StackOfOpenElements<S::Handle>,
+  active_formatting_elements: ListOfActiveFormattingElements<S::Handle>,
+  document: S::Handle,
+  quirks_mode: QuirksMode,
+  head_pointer: Option<S::Handle>,
+  // https://html.spec.whatwg.org/multipage/parsing.html#form-element-pointer
+  form_pointer: Option<S::Handle>,
+  text_insertion_node: Option<S::Handle>,
   text_insertion_string_data: EcoString,
   should_stop: bool,
+  ignore_next_line_feed: bool,
   frameset_ok: bool,
-  scripting: bool,
   foster_parenting: bool,
   pending_table_character_tokens: EcoVec<Token>,
+  // 保留中のテキストで、空白以外の文字を最初に含むトークンの位置
+  // ストリーミングでは、トークナイザがその位置の入力を捨ててしまうので、先に行と列を求めておく
+  pending_table_character_location: Option<SourceLocation>,
+  // 処理中の開始タグの自己終了フラグが、認められたかどうか
+  self_closing_acknowledged: bool,
+  errors: Vec<ParseError>,
+  current_token_span: Span,
+  // フラグメント解析の場合のみ、コンテキスト要素を持つ
+  context_element: Option<S::Handle>,
+  options: ParserOptions,
 }
 
-impl<'a> TreeBuilder<'a> {
+impl<'a> TreeBuilder<'a, FastDomSink> {
   pub fn new(tokenizer: Tokenizer<'a>, document: NodePtr) -> Self {
+    Self::with_options(tokenizer, document, ParserOptions::default())
+  }
+
+  // トークナイザにも同じ指定を渡す
+  pub fn with_options(
+    tokenizer: Tokenizer<'a>,
+    document: NodePtr,
+    options: ParserOptions,
+  ) -> Self {
+    Self::with_sink(tokenizer, FastDomSink::new(document), options)
+  }
+
+  // https://html.spec.whatwg.org/multipage/parsing.html#parsing-html-fragments
+  // documentには空のDocumentを渡す
+  // 解析結果は、documentの唯一の子であるhtml要素の子として構築される
+  pub fn new_fragment(
+    tokenizer: Tokenizer<'a>,
+    document: NodePtr,
+    context_element: NodePtr,
+  ) -> Self {
+    Self::new_fragment_with_options(
+      tokenizer,
+      document,
+      context_element,
+      ParserOptions::default(),
+    )
+  }
+
+  pub fn new_fragment_with_options(
+    mut tokenizer: Tokenizer<'a>,
+    document: NodePtr,
+    context_element: NodePtr,
+    options: ParserOptions,
+  ) -> Self {
+    if context_element.is_html() {
+      let state = match context_element.local_name() {
+        "title" | "textarea" => tokenizer::state::State::RCDATA,
+        "style" | "xmp" | "iframe" | "noembed" | "noframes" => {
+          tokenizer::state::State::RAWTEXT
+        }
+        "noscript" if options.scripting => tokenizer::state::State::RAWTEXT,
+        "script" => tokenizer::state::State::ScriptData,
+        "plaintext" => tokenizer::state::State::PLAINTEXT,
+        _ => tokenizer::state::State::Data,
+      };
+      // まだ何も読んでいないので、入力を進めずに状態だけ切り替える
+      tokenizer.reconsume_in(state);
+    }
+
+    let mut tree_builder = Self::with_options(tokenizer, document, options);
+
+    // コンテキスト要素の文書が後方互換モードなら、それに合わせる
+    if let Some(mode) = context_element
+      .owner_document()
+      .and_then(|owner| owner.as_maybe_document().map(|doc| doc.mode()))
+    {
+      tree_builder.set_document_mode(mode);
+    }
+
+    let root = tree_builder.create_element_for_tag_name("html");
+    tree_builder.sink.append(&tree_builder.document, root.clone());
+    tree_builder.open_elements.push(root);
+
+    if context_element.match_tag_name_in(&["template"]) {
+      tree_builder.template_insert_modes.push(InsertMode::InTemplate);
+    }
+
+    tree_builder.context_element = Some(context_element.clone());
+    tree_builder.reset_insertion_mode_appropriately();
+
+    // コンテキスト要素自身を含めて、最も近い祖先のform要素を指す
+    tree_builder.form_pointer = if context_element.match_tag_name_in(&["form"])
+    {
+      Some(context_element.clone())
+    } else {
+      context_element
+        .find_first_anecestor(|node| {
+          node
+            .as_maybe_element()
+            .is_some_and(|element| element.match_tag_name_in(&["form"]))
+        })
+        .map(NodePtr)
+    };
+
+    tree_builder
+  }
+
+  // 構築中のDocument
+  // feedの途中でも、それまでに届いた入力から作られた部分を辿れる
+  pub fn document(&self) -> &NodePtr {
+    &self.document
+  }
+}
+
+impl<'a, S: TreeSink> TreeBuilder<'a, S> {
+  // sinkが組み立てる先のDOMに、木を構築する
+  pub fn with_sink(
+    mut tokenizer: Tokenizer<'a>,
+    sink: S,
+    options: ParserOptions,
+  ) -> Self {
+    tokenizer.set_options(&options);
+
     Self {
       tokenizer,
+      document: sink.document(),
+      sink,
       insert_mode: InsertMode::Initial,
       original_insert_mode: None,
+      template_insert_modes: Vec::new(),
       open_elements: StackOfOpenElements::new(),
       active_formatting_elements: ListOfActiveFormattingElements::new(),
-      document,
+      quirks_mode: QuirksMode::NoQuirks,
       head_pointer: None,
+      form_pointer: None,
       text_insertion_node: None,
       text_insertion_string_data: EcoString::new(),
       should_stop: false,
+      ignore_next_line_feed: false,
       frameset_ok: true,
-      scripting: false,
       foster_parenting: false,
       pending_table_character_tokens: EcoVec::new(),
+      pending_table_character_location: None,
+      self_closing_acknowledged: false,
+      errors: Vec::new(),
+      current_token_span: Span::default(),
+      context_element: None,
+      options,
     }
   }
 
-  pub fn run(mut self) -> NodePtr {
+  pub fn run(self) -> (S::Output, Vec<ParseError>) {
+    self.finish()
+  }
+
+  // 入力を追加し、確定したトークンの分だけDOMを構築する
+  // トークナイザはTokenizer::streaming()で作ったものを渡す
+  pub fn feed(&mut self, chunk: &[u8]) {
+    self.tokenizer.feed(chunk);
+    self.pump();
+    self.flush_text_insertion();
+  }
+
+  // 入力の終わりを伝え、残りのトークンを処理する
+  pub fn finish(mut self) -> (S::Output, Vec<ParseError>) {
+    self.tokenizer.finish();
+    self.pump();
+    self.flush_text_insertion();
+
+    // トークナイザのエラーとツリー構築のエラーを、入力中の位置順に並べる
+    let mut errors = self.tokenizer.take_errors();
+    errors.append(&mut self.errors);
+    errors.sort_by_key(|error| error.location().offset);
+
+    (self.sink.finish(), errors)
+  }
+
+  fn pump(&mut self) {
     while !self.should_stop {
-      let token = self.tokenizer.next_token();
+      self
+        .tokenizer
+        .set_allow_cdata(self.is_adjusted_current_node_in_foreign_content());
+
+      let Some((mut token, span)) = self.tokenizer.try_next_token_with_span()
+      else {
+        break;
+      };
       debug!("{:?}", token);
 
+      self.current_token_span = span;
+
+      // pre, listing, textareaの開始タグ直後の改行は無視する
+      if std::mem::take(&mut self.ignore_next_line_feed) {
+        if let Token::Text(ref mut str) = token {
+          if let Some(rest) = str.strip_prefix('\n') {
+            if rest.is_empty() {
+              continue;
+            }
+            *str = EcoString::from(rest);
+          }
+        }
+      }
+
+      let is_self_closing = token.is_start_tag() && token.is_self_closing();
+      self.self_closing_acknowledged = false;
+
       self.process(token);
+
+      // 空要素や外部コンテンツの要素以外で、自己終了フラグが使われた
+      if is_self_closing && !self.self_closing_acknowledged {
+        self.parse_error(
+          ParseError::NonVoidHtmlElementStartTagWithTrailingSolidus,
+        );
+      }
     }
+  }
 
-    self.flush_text_insertion();
+  // https://html.spec.whatwg.org/multipage/parsing.html#tree-construction-dispatcher
+  fn process(&mut self, token: Token) {
+    if self.should_process_in_foreign_content(&token) {
+      self.handle_in_foreign_content(token);
+    } else {
+      self.process_by_insert_mode(token);
+    }
+  }
+
+  fn should_process_in_foreign_content(&self, token: &Token) -> bool {
+    let Some(node) = self.adjusted_current_node() else {
+      return false;
+    };
+    if node.is_html() || token.is_eof() {
+      return false;
+    }
+
+    let is_text = matches!(token, Token::Text(_));
+
+    if is_mathml_text_integration_point(&node)
+      && (is_text
+        || token.is_start_tag()
+          && !token.match_tag_name_in(&["mglyph", "malignmark"]))
+    {
+      return false;
+    }
+
+    if node.namespace() == Namespace::MathMl
+      && node.local_name() == "annotation-xml"
+      && token.is_start_tag()
+      && token.tag_name() == "svg"
+    {
+      return false;
+    }
 
-    self.document
+    if is_html_integration_point(&self.sink, &node)
+      && (is_text || token.is_start_tag())
+    {
+      return false;
+    }
+
+    true
   }
 
-  fn process(&mut self, token: Token) {
+  fn process_by_insert_mode(&mut self, token: Token) {
     match self.insert_mode {
       InsertMode::Initial => self.handle_initial_mode(token),
       InsertMode::BeforeHtml => self.handle_before_html_mode(token),
       InsertMode::BeforeHead => self.handle_before_head_mode(token),
       InsertMode::InHead => self.handle_in_head_mode(token),
+      InsertMode::InHeadNoscript => self.handle_in_head_noscript_mode(token),
       InsertMode::AfterHead => self.handle_after_head_mode(token),
       InsertMode::InBody => self.handle_in_body_mode(token),
       InsertMode::AfterBody => self.handle_after_body_mode(token),
@@ -123,7 +358,13 @@ impl<'a> TreeBuilder<'a> {
       InsertMode::InCaption => self.handle_in_caption_mode(token),
       InsertMode::InSelect => self.handle_in_select_mode(token),
       InsertMode::InSelectInTable => self.handle_in_select_in_table_mode(token),
+      InsertMode::InTemplate => self.handle_in_template_mode(token),
       InsertMode::Text => self.handle_text_mode(token),
+      InsertMode::InFrameset => self.handle_in_frameset_mode(token),
+      InsertMode::AfterFrameset => self.handle_after_frameset_mode(token),
+      InsertMode::AfterAfterFrameset => {
+        self.handle_after_after_frameset_mode(token)
+      }
     }
   }
 
@@ -132,36 +373,55 @@ impl<'a> TreeBuilder<'a> {
     self.insert_mode = mode;
   }
 
+  // https://html.spec.whatwg.org/multipage/parsing.html#acknowledge-self-closing-flag
+  fn acknowledge_self_closing(&mut self, token: &mut Token) {
+    if token.is_self_closing() {
+      token.acknowledge_self_closing_if_set();
+      self.self_closing_acknowledged = true;
+    }
+  }
+
   fn stop_parsing(&mut self) {
     self.should_stop = true;
   }
 
-  fn unexpected(&self, token: &Token) {
-    match token {
+  fn unexpected(&mut self, token: &Token) {
+    self.parse_error(match token {
       Token::Tag {
-        tag_name,
-        is_end_tag,
-        ..
-      } => {
-        if *is_end_tag {
-          warn!("Unexpected end tag: {}", tag_name);
-        } else {
-          warn!("Unexpected start tag: {}", tag_name);
-        }
-      }
-      Token::DOCTYPE { .. } => {
-        todo!("unexpected: Token::DOCTYPE");
-      }
-      Token::Comment(data) => {
-        warn!("Unexpected comment: {}", data);
-      }
-      Token::Text(text) => {
-        warn!("Unexpected text: {}", text);
-      }
-      Token::EOF => {
-        warn!("Unexpected EOF");
-      }
+        is_end_tag: true, ..
+      } => ParseError::UnexpectedEndTag,
+      Token::Tag { .. } => ParseError::UnexpectedStartTag,
+      Token::DOCTYPE { .. } => ParseError::UnexpectedDoctype,
+      Token::Comment(_) => ParseError::UnexpectedComment,
+      Token::Text(_) => ParseError::UnexpectedText,
+      Token::EOF => ParseError::UnexpectedEof,
+    });
+  }
+
+  fn parse_error(&mut self, error: fn(SourceLocation) -> ParseError) {
+    self.parse_error_at(error, self.current_token_span.start);
+  }
+
+  fn parse_error_at(
+    &mut self,
+    error: fn(SourceLocation) -> ParseError,
+    offset: usize,
+  ) {
+    if !self.options.collect_errors {
+      return;
+    }
+
+    let location = self.tokenizer.location_at(offset);
+    self.report_error(error(location));
+  }
+
+  fn report_error(&mut self, error: ParseError) {
+    if !self.options.collect_errors {
+      return;
     }
+
+    warn!("{}", error);
+    self.errors.push(error);
   }
 
   /* -------------------------------------------- */
@@ -172,11 +432,11 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if let Some(node) = &self.text_insertion_node {
-      let text_node = node.as_text();
-      text_node.set_data(self.text_insertion_string_data.clone());
-
-      self.text_insertion_string_data.clear();
+      self.sink.set_text_data(node, self.text_insertion_string_data.clone());
     }
+
+    self.text_insertion_node = None;
+    self.text_insertion_string_data.clear();
   }
 
   fn parse_text_only_element(
@@ -201,36 +461,60 @@ impl<'a> TreeBuilder<'a> {
     self.switch_to(InsertMode::Text);
   }
 
+  /* document --------------------------------- */
+
+  fn set_document_mode(&mut self, mode: QuirksMode) {
+    self.quirks_mode = mode;
+    self.sink.set_quirks_mode(mode);
+  }
+
+  fn is_quirks_mode(&self) -> bool {
+    self.quirks_mode == QuirksMode::Quirks
+  }
+
   /* attribute ---------------------------------- */
 
   // tokenが持つ属性をelementが持っていなければ、elementに追加する
-  fn add_missing_attributes_to_node(&self, node: &NodePtr, token: &Token) {
+  fn add_missing_attributes_to_node(&self, node: &S::Handle, token: &Token) {
     if let Token::Tag { attributes, .. } = token {
-      let element = node.as_element();
       for attr in attributes {
-        if element.has_attribute(&attr.name) {
+        if self.sink.get_attribute(node, &attr.name).is_some() {
           continue;
         }
-        element.set_attribute(&attr.name, &attr.value);
+        self.sink.set_attribute(node, &attr.name, &attr.value);
       }
     }
   }
 
   /* open_elements ------------------------------ */
 
-  fn current_node(&self) -> NodePtr {
+  fn current_node(&self) -> S::Handle {
     self.open_elements.current_node().unwrap()
   }
 
+  // https://html.spec.whatwg.org/multipage/parsing.html#adjusted-current-node
+  fn adjusted_current_node(&self) -> Option<S::Handle> {
+    // フラグメントの場合、スタックにhtml要素しかなければコンテキスト要素とみなす
+    if self.open_elements.len() == 1 {
+      if let Some(context) = &self.context_element {
+        return Some(context.clone());
+      }
+    }
+    self.open_elements.current_node()
+  }
+
+  // 調整済み現在ノードがHTML名前空間以外の要素かどうか
+  fn is_adjusted_current_node_in_foreign_content(&self) -> bool {
+    self.adjusted_current_node().is_some_and(|node| !node.is_html())
+  }
+
   fn generate_implied_end_tags(&mut self, exclude: &str) {
     while let Some(node) = self.open_elements.current_node() {
-      let element = node.as_element();
-
-      if element.tag_name() == exclude {
+      if node.local_name() == exclude {
         break;
       }
 
-      if element.match_tag_name_in(&[
+      if node.match_tag_name_in(&[
         "dd", "dt", "li", "optgroup", "option", "p", "rb", "rt", "rtc", "rp",
       ]) {
         self.open_elements.pop();
@@ -240,20 +524,50 @@ impl<'a> TreeBuilder<'a> {
     }
   }
 
+  fn generate_all_implied_end_tags_thoroughly(&mut self) {
+    while let Some(node) = self.open_elements.current_node() {
+      if node.match_tag_name_in(&[
+        "caption", "colgroup", "dd", "dt", "li", "optgroup", "option", "p",
+        "rb", "rp", "rt", "rtc", "tbody", "td", "tfoot", "th", "thead", "tr",
+      ]) {
+        self.open_elements.pop();
+      } else {
+        break;
+      }
+    }
+  }
+
   fn close_p_element(&mut self) {
     self.generate_implied_end_tags("p");
 
     let current_node = self.open_elements.current_node().unwrap();
-    let current_element = current_node.as_element();
-
-    if current_element.tag_name() != "p" {
-      warn!("Expected p element");
+    if current_node.local_name() != "p" {
+      self.parse_error(ParseError::EndTagTooEarly);
     }
 
     self.open_elements.pop_until("p");
   }
 
-  fn is_marker_or_open_element(&self, entry: &Entry) -> bool {
+  fn close_table_row(&mut self) {
+    self.open_elements.clear_back_to_table_row_context();
+    self.open_elements.pop();
+    self.switch_to(InsertMode::InTableBody);
+  }
+
+  // https://html.spec.whatwg.org/multipage/parsing.html#close-the-cell
+  fn close_cell(&mut self) {
+    self.generate_implied_end_tags("");
+
+    if !self.current_node().match_tag_name_in(&["td", "th"]) {
+      self.parse_error(ParseError::EndTagTooEarly);
+    }
+
+    self.open_elements.pop_until_some_in(&["td", "th"]);
+    self.active_formatting_elements.clear_up_to_last_marker();
+    self.switch_to(InsertMode::InRow);
+  }
+
+  fn is_marker_or_open_element(&self, entry: &Entry<S::Handle>) -> bool {
     match entry {
       Entry::Marker => true,
       Entry::Element(node) if self.open_elements.contains_node(node) => true,
@@ -307,12 +621,13 @@ impl<'a> TreeBuilder<'a> {
       };
       let new_element =
         self.insert_html_element(self.create_tag_token_cloned_from(&element));
+      self.copy_span(&element, &new_element);
 
       // 新しい要素に置き換え
-      self.active_formatting_elements.remove(last_index);
+      self.active_formatting_elements.remove(index);
       self
         .active_formatting_elements
-        .insert(last_index, Entry::Element(new_element));
+        .insert(index, Entry::Element(new_element));
 
       // 最後のエントリまで来たら終了
       if index == last_index {
@@ -329,26 +644,37 @@ impl<'a> TreeBuilder<'a> {
       // nodeがオープン要素のスタックの最初のノードである場合、lastをtrueに設定
       let last = index == 0;
 
-      // TODO: フラグメント解析アルゴリズムをサポートするか決める
-      // パーサーがHTMLフラグメント解析アルゴリズムの一部として作成された場合（フラグメントの場合）、nodeをそのアルゴリズムに渡されたコンテキスト要素に設定する
-
-      let element = node.as_element();
-
-      if element.tag_name() == "select" {
-        for ancestor in self.open_elements.iter().rev() {
-          let ancestor_tag_name = ancestor.as_element().tag_name();
+      // フラグメントの場合、スタックの最初のノードの代わりにコンテキスト要素を調べる
+      let node = match &self.context_element {
+        Some(context) if last => context.clone(),
+        _ => node.clone(),
+      };
 
-          match ancestor_tag_name.as_str() {
-            "template" => {
-              self.switch_to(InsertMode::InSelect);
-              return;
-            }
-            "table" => {
-              self.switch_to(InsertMode::InSelectInTable);
-              return;
-            }
-            _ => {
-              // noop
+      // 以下で調べるのはHTMLの要素のみ
+      if !node.is_html() {
+        if last {
+          self.switch_to(InsertMode::InBody);
+          return;
+        }
+        continue;
+      }
+
+      if node.local_name() == "select" {
+        // select要素より下にある祖先を、近い順に調べる
+        if !last {
+          for ancestor in self.open_elements.iter().take(index).rev() {
+            match ancestor.local_name() {
+              "template" => {
+                self.switch_to(InsertMode::InSelect);
+                return;
+              }
+              "table" => {
+                self.switch_to(InsertMode::InSelectInTable);
+                return;
+              }
+              _ => {
+                // noop
+              }
             }
           }
         }
@@ -357,55 +683,58 @@ impl<'a> TreeBuilder<'a> {
         return;
       }
 
-      if element.match_tag_name_in(&["td", "th"]) && !last {
+      if node.match_tag_name_in(&["td", "th"]) && !last {
         self.switch_to(InsertMode::InCell);
         return;
       }
 
-      if element.tag_name() == "tr" {
+      if node.local_name() == "tr" {
         self.switch_to(InsertMode::InRow);
         return;
       }
 
-      if element.match_tag_name_in(&["tbody", "thead", "tfoot"]) {
+      if node.match_tag_name_in(&["tbody", "thead", "tfoot"]) {
         self.switch_to(InsertMode::InTableBody);
         return;
       }
 
-      if element.tag_name() == "caption" {
+      if node.local_name() == "caption" {
         self.switch_to(InsertMode::InCaption);
         return;
       }
 
-      if element.tag_name() == "colgroup" {
+      if node.local_name() == "colgroup" {
         self.switch_to(InsertMode::InColumnGroup);
         return;
       }
 
-      if element.tag_name() == "table" {
+      if node.local_name() == "table" {
         self.switch_to(InsertMode::InTable);
         return;
       }
 
-      if element.tag_name() == "template" {
-        todo!("reset_insertion_mode_appropriately: template");
+      if node.local_name() == "template" {
+        let mode = self.template_insert_modes.last().cloned().unwrap();
+        self.switch_to(mode);
+        return;
       }
 
-      if element.tag_name() == "head" && !last {
+      if node.local_name() == "head" && !last {
         self.switch_to(InsertMode::InHead);
         return;
       }
 
-      if element.tag_name() == "body" {
+      if node.local_name() == "body" {
         self.switch_to(InsertMode::InBody);
         return;
       }
 
-      if element.tag_name() == "frameset" {
-        todo!("reset_insertion_mode_appropriately: frameset");
+      if node.local_name() == "frameset" {
+        self.switch_to(InsertMode::InFrameset);
+        return;
       }
 
-      if element.tag_name() == "html" {
+      if node.local_name() == "html" {
         match self.head_pointer {
           Some(_) => {
             self.switch_to(InsertMode::AfterHead);
@@ -429,44 +758,36 @@ impl<'a> TreeBuilder<'a> {
   // formatting_elementよりもスタックの下位にあり、特別なカテゴリの要素である、オープン要素のスタックの最上位ノード
   fn find_furthest_block_and_index(
     &self,
-    formatting_element: &NodePtr,
-  ) -> (Option<NodePtr>, Option<usize>) {
-    let mut furthest_block = None;
-    let mut furthest_block_index = None;
-
-    for (index, element) in self.open_elements.iter().rev().enumerate() {
-      if Rc::ptr_eq(element, formatting_element) {
-        break;
-      }
-
-      if is_special_element(element.as_element().tag_name()) {
-        furthest_block = Some(element.clone());
-        furthest_block_index = Some(index);
-      }
-    }
+    formatting_element: &S::Handle,
+  ) -> Option<(S::Handle, usize)> {
+    let formatting_element_index = self
+      .open_elements
+      .iter()
+      .position(|el| el.same_node(formatting_element))?;
 
-    (furthest_block, furthest_block_index)
+    self
+      .open_elements
+      .iter()
+      .enumerate()
+      .skip(formatting_element_index + 1)
+      .find(|(_, el)| is_special_element(*el))
+      .map(|(index, el)| (el.clone(), index))
   }
 
   // 開いている要素のスタックでformattingElementのすぐ上にある要素
   fn find_common_ancestor(
     &self,
-    formatting_element: &NodePtr,
-  ) -> Option<NodePtr> {
-    let mut found_element = None;
-
-    for (index, el) in self.open_elements.iter().rev().enumerate() {
-      if Rc::ptr_eq(el, formatting_element) {
-        if index < self.open_elements.len() - 1 {
-          found_element = Some(self.open_elements.get(index - 1));
-        }
-        break;
-      }
-    }
+    formatting_element: &S::Handle,
+  ) -> Option<S::Handle> {
+    let index = self
+      .open_elements
+      .iter()
+      .position(|el| el.same_node(formatting_element))?;
 
-    found_element
+    index.checked_sub(1).map(|index| self.open_elements.get(index))
   }
 
+  // https://html.spec.whatwg.org/multipage/parsing.html#adoption-agency-algorithm
   fn adoption_agency_algorithm(
     &mut self,
     token: &Token,
@@ -474,29 +795,20 @@ impl<'a> TreeBuilder<'a> {
     let subject = token.tag_name();
     let current_node = self.current_node();
 
-    if current_node.as_element().tag_name() == subject
+    if current_node.is_html()
+      && current_node.local_name() == subject
       && !self.active_formatting_elements.contains_node(&current_node)
     {
       self.open_elements.pop();
       return AdoptionAgencyAlgorithmOutcome::DoNothing;
     }
 
-    let mut external_loop_counter = 0;
-
-    loop {
-      if external_loop_counter >= 8 {
-        break;
-      }
-      external_loop_counter += 1;
-
-      let formatting_element =
-        self.active_formatting_elements.get_element_after_last_marker(subject);
-
-      if formatting_element.is_none() {
+    for _ in 0..8 {
+      let Some(formatting_element) =
+        self.active_formatting_elements.get_element_after_last_marker(subject)
+      else {
         return AdoptionAgencyAlgorithmOutcome::RunAnyOtherEndTagsSteps;
-      }
-
-      let formatting_element = formatting_element.unwrap();
+      };
 
       if !self.open_elements.contains_node(&formatting_element) {
         self.unexpected(token);
@@ -509,40 +821,31 @@ impl<'a> TreeBuilder<'a> {
         return AdoptionAgencyAlgorithmOutcome::DoNothing;
       }
 
-      if !Rc::ptr_eq(&formatting_element, &current_node) {
+      if !formatting_element.same_node(&self.current_node()) {
         self.unexpected(token);
       }
 
-      let (mut furthest_block, mut furthest_block_index) =
-        self.find_furthest_block_and_index(&formatting_element);
-
-      if furthest_block.is_none() {
-        while !Rc::ptr_eq(&current_node, &formatting_element) {
-          self.open_elements.pop();
+      let Some((furthest_block, furthest_block_index)) =
+        self.find_furthest_block_and_index(&formatting_element)
+      else {
+        while let Some(node) = self.open_elements.pop() {
+          if node.same_node(&formatting_element) {
+            break;
+          }
         }
-        self.open_elements.pop();
 
         self.active_formatting_elements.remove_element(&formatting_element);
         return AdoptionAgencyAlgorithmOutcome::DoNothing;
-      }
+      };
 
+      // furthest_blockが見つかった場合、formatting_elementはhtml要素ではないので、必ず存在する
       let common_ancestor = self
         .find_common_ancestor(&formatting_element)
-        .take()
         .expect("Common ancestor doesn't exists in agency adoption algorithm");
 
-      let furthest_block = furthest_block.take().unwrap();
-      let furthest_block_index = furthest_block_index.take().unwrap();
-
-      // アクティブな書式設定要素のリスト内のformattingElementの位置を、リスト内のその両側の要素を基準にして記録
-      let mut bookmark = self
-        .active_formatting_elements
-        .iter()
-        .rposition(|el| match el {
-          Entry::Element(node) => Rc::ptr_eq(node, &formatting_element),
-          Entry::Marker => false,
-        })
-        .unwrap();
+      // アクティブな書式設定要素のリスト内で、新しい要素を入れる位置
+      // Noneの場合はformatting_elementを置き換え、Someの場合はその要素の直後に入れる
+      let mut bookmark: Option<S::Handle> = None;
 
       let mut node;
       let mut node_index = furthest_block_index;
@@ -553,10 +856,11 @@ impl<'a> TreeBuilder<'a> {
       loop {
         inner_loop_counter += 1;
 
+        // nodeがスタックから取り除かれていても、直前の要素は同じ位置にある
         node_index -= 1;
         node = self.open_elements.get(node_index);
 
-        if Rc::ptr_eq(&node, &formatting_element) {
+        if node.same_node(&formatting_element) {
           break;
         }
 
@@ -564,22 +868,17 @@ impl<'a> TreeBuilder<'a> {
           && self.active_formatting_elements.contains_node(&node)
         {
           self.active_formatting_elements.remove_element(&node);
-          // continue; // TODO: これが仕様通りか調べる
         }
 
-        if !self.active_formatting_elements.contains_node(&node) {
-          self
-            .open_elements
-            .remove_first_matching_node(|n| Rc::ptr_eq(n, &node));
+        let Some(node_index_in_formatting) =
+          self.active_formatting_elements.get_index_of_node(&node)
+        else {
+          self.open_elements.remove(node_index);
           continue;
-        }
+        };
 
         let new_element = self.create_element_cloned_from(&node);
 
-        // nodeが含まれていない場合はそもそもここに到達していないので、unwrapしても問題ない
-        let node_index_in_formatting =
-          self.active_formatting_elements.get_index_of_node(&node).unwrap();
-
         self.open_elements.remove(node_index);
         self.open_elements.insert(node_index, new_element.clone());
 
@@ -591,33 +890,66 @@ impl<'a> TreeBuilder<'a> {
 
         node = new_element;
 
-        if Rc::ptr_eq(&last_node, &furthest_block) {
-          bookmark = node_index_in_formatting + 1;
+        if last_node.same_node(&furthest_block) {
+          bookmark = Some(node.clone());
         }
 
-        node.append_child(last_node.0);
+        self.sink.append(&node, last_node);
         last_node = node;
       }
 
       let insert_place =
-        self.get_appropriate_insert_position(Some(common_ancestor));
+        self.get_appropriate_insert_position_for_element(Some(common_ancestor));
       self.insert_at(insert_place, last_node);
 
-      let new_element = self.create_element_cloned_from(&node);
-
-      furthest_block.transfer_children_to(new_element.0.clone());
-      furthest_block.append_child(new_element.0.clone());
+      let new_element = self.create_element_cloned_from(&formatting_element);
 
-      self.active_formatting_elements.remove_element(&formatting_element);
+      // 深さの上限にある要素の子を包むと深くなるので、包まずに兄弟として入れる
+      if self
+        .options
+        .max_tree_depth
+        .is_some_and(|max_depth| self.tree_depth(&furthest_block) >= max_depth)
+      {
+        let insert_place = self.get_appropriate_insert_position_for_element(
+          Some(furthest_block.clone()),
+        );
+        self.insert_at(insert_place, new_element.clone());
+      } else {
+        self.sink.reparent_children(&furthest_block, &new_element);
+        self.sink.append(&furthest_block, new_element.clone());
+      }
 
-      self.active_formatting_elements.remove(bookmark);
-      self
-        .active_formatting_elements
-        .insert(bookmark, Entry::Element(new_element.clone()));
+      match bookmark {
+        Some(bookmark) => {
+          self.active_formatting_elements.remove_element(&formatting_element);
+          let index = self
+            .active_formatting_elements
+            .get_index_of_node(&bookmark)
+            .map_or(self.active_formatting_elements.len(), |index| index + 1);
+          self
+            .active_formatting_elements
+            .insert(index, Entry::Element(new_element.clone()));
+        }
+        None => {
+          let index = self
+            .active_formatting_elements
+            .get_index_of_node(&formatting_element)
+            .unwrap();
+          self.active_formatting_elements.remove(index);
+          self
+            .active_formatting_elements
+            .insert(index, Entry::Element(new_element.clone()));
+        }
+      }
 
       self
         .open_elements
-        .remove_first_matching_node(|n| Rc::ptr_eq(n, &formatting_element));
+        .remove_first_matching_node(|n| n.same_node(&formatting_element));
+      let furthest_block_index = self
+        .open_elements
+        .iter()
+        .position(|el| el.same_node(&furthest_block))
+        .unwrap();
       self.open_elements.insert(furthest_block_index + 1, new_element);
     }
 
@@ -626,7 +958,15 @@ impl<'a> TreeBuilder<'a> {
 
   /* create_element ----------------------------- */
 
-  fn create_element(&self, tag_token: Token) -> NodePtr {
+  fn create_element(&self, tag_token: Token) -> S::Handle {
+    self.create_element_ns(tag_token, Namespace::Html)
+  }
+
+  fn create_element_ns(
+    &self,
+    tag_token: Token,
+    namespace: Namespace,
+  ) -> S::Handle {
     let (tag_name, attributes) = if let Token::Tag {
       tag_name,
       attributes,
@@ -638,17 +978,37 @@ impl<'a> TreeBuilder<'a> {
       (EcoString::new(), EcoVec::new())
     };
 
-    let document = WeakTreeNode::from(&self.document.0);
-    let element = fast_dom::create_element(document, &tag_name);
+    let element = self.sink.create_element(&tag_name, namespace);
 
     for attr in attributes {
-      element.as_element().set_attribute(&attr.name, &attr.value);
+      // SVG・MathMLの要素では、xlink:hrefなどの属性に名前空間を付ける
+      let foreign_attribute = adjust_foreign_attribute(&attr.name)
+        .filter(|_| namespace != Namespace::Html);
+
+      match foreign_attribute {
+        Some((prefix, local_name, attr_namespace)) => {
+          self.sink.set_attribute_ns(
+            &element,
+            attr_namespace,
+            prefix,
+            local_name,
+            &attr.value,
+          )
+        }
+        None => self.sink.set_attribute(&element, &attr.name, &attr.value),
+      }
     }
 
     element
   }
 
-  fn create_element_for_tag_name(&self, tag_name: &str) -> NodePtr {
+  fn create_comment(&self, data: EcoString) -> S::Handle {
+    let comment = self.sink.create_comment(data);
+    self.sink.set_span(&comment, self.current_token_span);
+    comment
+  }
+
+  fn create_element_for_tag_name(&self, tag_name: &str) -> S::Handle {
     self.create_element(Token::Tag {
       tag_name: EcoString::from(tag_name),
       attributes: EcoVec::new(),
@@ -658,132 +1018,292 @@ impl<'a> TreeBuilder<'a> {
     })
   }
 
-  fn create_tag_token_cloned_from(&self, element: &NodePtr) -> Token {
-    let element = element.as_element();
-    let tag_name = element.tag_name().clone();
-    let attributes = element
-      .attributes()
-      .iter()
-      .map(|(name, value)| Attribute {
-        name: name.clone(),
-        value: value.clone(),
-      })
-      .collect();
-
+  fn create_tag_token_cloned_from(&self, element: &S::Handle) -> Token {
     Token::Tag {
-      tag_name,
-      attributes,
+      tag_name: EcoString::from(element.local_name()),
+      attributes: self.sink.attributes(element),
       is_end_tag: false,
       self_closing: false,
       self_closing_acknowledged: false,
     }
   }
 
-  fn create_element_cloned_from(&self, element: &NodePtr) -> NodePtr {
+  fn create_element_cloned_from(&self, element: &S::Handle) -> S::Handle {
     let tag_token = self.create_tag_token_cloned_from(element);
-    self.create_element(tag_token)
+    let new_element = self.create_element(tag_token);
+    self.copy_span(element, &new_element);
+    new_element
+  }
+
+  // 複製した要素にも、元の要素の範囲を引き継ぐ
+  fn copy_span(&self, from: &S::Handle, to: &S::Handle) {
+    if let Some(span) = self.sink.span(from) {
+      self.sink.set_span(to, span);
+    }
   }
 
   /* insert ------------------------------------- */
 
   fn get_appropriate_insert_position(
     &self,
-    target: Option<NodePtr>,
-  ) -> AdjustedInsertionLocation {
+    target: Option<S::Handle>,
+  ) -> AdjustedInsertionLocation<S::Handle> {
     let target = target.unwrap_or(self.current_node());
 
-    if self.foster_parenting
-      && target
-        .as_element()
-        .match_tag_name_in(&["table", "tbody", "tfoot", "thead", "tr"])
+    let location = if self.foster_parenting
+      && target.match_tag_name_in(&["table", "tbody", "tfoot", "thead", "tr"])
     {
-      todo!("get_appropriate_insert_position: foster parenting");
+      self.get_foster_parenting_insert_position()
     } else {
       AdjustedInsertionLocation::LastChild(target)
-    }
-  }
+    };
 
-  fn get_node_for_text_insertion(
-    &mut self,
-    insert_position: AdjustedInsertionLocation,
-  ) -> NodePtr {
-    match &insert_position {
-      AdjustedInsertionLocation::LastChild(parent) => {
-        if let Some(last_child) = parent.last_child() {
-          if last_child.as_maybe_text().is_some() {
-            return NodePtr(last_child);
-          }
-        }
-      }
-      AdjustedInsertionLocation::BeforeSibling(_, sibling) => {
-        if let Some(prev_sibling) = sibling.prev_sibling() {
-          if prev_sibling.as_maybe_text().is_some() {
-            return NodePtr(prev_sibling);
-          }
-        }
+    // template要素の中に挿入する場合は、その中身の末尾に入れる
+    if let AdjustedInsertionLocation::LastChild(parent) = &location {
+      if let Some(contents) = self.sink.template_contents(parent) {
+        return AdjustedInsertionLocation::LastChild(contents);
       }
     }
 
-    let text_node =
-      DOMNode::new(DOMNodeData::Text(Text::new(EcoString::new())));
-    let text = NodePtr(TreeNode::new(text_node));
+    location
+  }
 
-    text.set_document(WeakTreeNode::from(&self.document.0));
-    self.insert_at(insert_position, text.clone());
+  // 要素を挿入する位置を求める
+  // 深さの上限を超える場合は、それ以上深くせずに、上限の深さの要素の兄弟として入れる
+  // テキストとコメントは木を深くしないので、上限の深さの要素の中にも入れる
+  fn get_appropriate_insert_position_for_element(
+    &self,
+    target: Option<S::Handle>,
+  ) -> AdjustedInsertionLocation<S::Handle> {
+    let location = self.get_appropriate_insert_position(target);
 
-    text
-  }
+    let Some(max_depth) = self.options.max_tree_depth else {
+      return location;
+    };
 
-  fn insert_at(&mut self, location: AdjustedInsertionLocation, child: NodePtr) {
-    match location {
-      AdjustedInsertionLocation::LastChild(parent) => {
-        parent.append_child(child.0);
-      }
-      AdjustedInsertionLocation::BeforeSibling(parent, sibling) => {
-        parent.insert_before(child.0, Some(sibling.0));
+    // 文書の直下には入れないように、html要素より上には上がらない
+    let max_parent_depth = max_depth.saturating_sub(1).max(1);
+
+    let mut parent = location.parent().clone();
+    let mut depth = self.tree_depth(&parent);
+    if depth <= max_parent_depth {
+      return location;
+    }
+
+    while depth > max_parent_depth {
+      let Some(next) = self.parent_or_template_host(&parent) else {
+        break;
+      };
+      if parent.is_element() {
+        depth -= 1;
       }
+      parent = next;
     }
-  }
 
-  fn insert_html_element(&mut self, token: Token) -> NodePtr {
-    let insert_position = self.get_appropriate_insert_position(None);
-    let element = self.create_element(token);
-    let return_ref = element.clone();
+    match self.sink.template_contents(&parent) {
+      Some(contents) => AdjustedInsertionLocation::LastChild(contents),
+      None => AdjustedInsertionLocation::LastChild(parent),
+    }
+  }
 
-    self.open_elements.push(element.clone());
-    self.insert_at(insert_position, element);
+  // nodeとその祖先の要素の数(html要素は1)
+  // template要素の中身は、template要素の子として数える
+  fn tree_depth(&self, node: &S::Handle) -> usize {
+    let mut depth = 0;
+    let mut node = Some(node.clone());
 
-    return_ref
-  }
+    while let Some(current) = node {
+      if current.is_element() {
+        depth += 1;
+      }
+      node = self.parent_or_template_host(&current);
+    }
 
-  fn insert_comment(&mut self, data: EcoString) {
-    let insert_position = self.get_appropriate_insert_position(None);
-    let comment = TreeNode::new(DOMNode::new(DOMNodeData::Comment(data)));
-    comment.set_document(WeakTreeNode::from(&self.document.0));
-    self.insert_at(insert_position, NodePtr(comment));
+    depth
   }
 
-  fn insert_str(&mut self, str: &EcoString) {
-    let insert_position = self.get_appropriate_insert_position(None);
-
-    if insert_position.parent().as_maybe_document().is_some() {
+  // template要素の中身のDocumentFragmentには親がないので、開いているtemplate要素から探す
+  fn parent_or_template_host(&self, node: &S::Handle) -> Option<S::Handle> {
+    self.sink.parent(node).or_else(|| {
+      self
+        .open_elements
+        .iter()
+        .rev()
+        .find(|element| {
+          self
+            .sink
+            .template_contents(element)
+            .is_some_and(|contents| contents.same_node(node))
+        })
+        .cloned()
+    })
+  }
+
+  // https://html.spec.whatwg.org/multipage/parsing.html#foster-parent
+  // table要素の直前か、table要素がまだ木にない場合はその1つ下に開いている要素の末尾
+  fn get_foster_parenting_insert_position(
+    &self,
+  ) -> AdjustedInsertionLocation<S::Handle> {
+    let last_index_of = |tag_name: &str| {
+      self
+        .open_elements
+        .iter()
+        .rposition(|node| node.match_tag_name_in(&[tag_name]))
+    };
+
+    let last_template = last_index_of("template");
+    let last_table = last_index_of("table");
+
+    // 直近のtemplate要素がtable要素より上にある場合は、template要素の中に入れる
+    // (中身のDocumentFragmentへの振り替えは呼び出し元で行う)
+    if let Some(template_index) = last_template {
+      if last_table.is_none_or(|table_index| template_index > table_index) {
+        return AdjustedInsertionLocation::LastChild(
+          self.open_elements.get(template_index),
+        );
+      }
+    }
+
+    // フラグメントの場合は、スタックの最初の要素(html要素)の中に入れる
+    let Some(table_index) = last_table else {
+      return AdjustedInsertionLocation::LastChild(self.open_elements.get(0));
+    };
+
+    let table = self.open_elements.get(table_index);
+
+    if let Some(parent) = self.sink.parent(&table) {
+      return AdjustedInsertionLocation::BeforeSibling(parent, table);
+    }
+
+    AdjustedInsertionLocation::LastChild(
+      self.open_elements.get(table_index - 1),
+    )
+  }
+
+  fn get_node_for_text_insertion(
+    &mut self,
+    insert_position: AdjustedInsertionLocation<S::Handle>,
+  ) -> S::Handle {
+    let adjacent_node = match &insert_position {
+      AdjustedInsertionLocation::LastChild(parent) => {
+        self.sink.last_child(parent)
+      }
+      AdjustedInsertionLocation::BeforeSibling(_, sibling) => {
+        self.sink.prev_sibling(sibling)
+      }
+    };
+
+    if let Some(text) = adjacent_node.filter(|node| node.is_text()) {
+      return text;
+    }
+
+    let text = self.sink.create_text(EcoString::new());
+    self.insert_at(insert_position, text.clone());
+
+    text
+  }
+
+  fn insert_at(
+    &mut self,
+    location: AdjustedInsertionLocation<S::Handle>,
+    child: S::Handle,
+  ) {
+    match location {
+      AdjustedInsertionLocation::LastChild(parent) => {
+        self.sink.append(&parent, child);
+      }
+      AdjustedInsertionLocation::BeforeSibling(parent, sibling) => {
+        self.sink.insert_before(&parent, &sibling, child);
+      }
+    }
+  }
+
+  fn insert_html_element(&mut self, token: Token) -> S::Handle {
+    self.insert_foreign_element(token, Namespace::Html)
+  }
+
+  fn insert_foreign_element(
+    &mut self,
+    token: Token,
+    namespace: Namespace,
+  ) -> S::Handle {
+    let insert_position =
+      self.get_appropriate_insert_position_for_element(None);
+    let element = self.create_element_ns(token, namespace);
+    self.sink.set_span(&element, self.current_token_span);
+    self.associate_with_form(&element, insert_position.parent());
+    let return_ref = element.clone();
+
+    self.open_elements.push(element.clone());
+    self.insert_at(insert_position, element);
+
+    return_ref
+  }
+
+  // https://html.spec.whatwg.org/multipage/parsing.html#create-an-element-for-the-token
+  // form要素ポインタが指すform要素を、フォームオーナーとして関連付ける
+  fn associate_with_form(
+    &self,
+    element: &S::Handle,
+    intended_parent: &S::Handle,
+  ) {
+    let Some(form) = &self.form_pointer else {
+      return;
+    };
+
+    if self.open_elements.contains("template") {
+      return;
+    }
+
+    self.sink.associate_with_form(element, form, intended_parent);
+  }
+
+  // 開始タグが省略された要素を挿入する
+  // 入力中に対応する範囲はないので、省略の原因になったトークンの位置に空の範囲を持たせる
+  fn insert_implied_html_element(&mut self, tag_name: &str) -> S::Handle {
+    let element = self.insert_html_element(Token::new_start_tag_of(tag_name));
+    self.sink.set_span(&element, Span::at(self.current_token_span.start));
+    element
+  }
+
+  fn insert_comment(&mut self, data: EcoString) {
+    let insert_position = self.get_appropriate_insert_position(None);
+    let comment = self.create_comment(data);
+    self.insert_at(insert_position, comment);
+  }
+
+  fn insert_str(&mut self, str: &EcoString) {
+    let insert_position = self.get_appropriate_insert_position(None);
+
+    if insert_position.parent().is_document() {
       return;
     }
 
     let text_insertion_node = self.get_node_for_text_insertion(insert_position);
 
+    // 連結されるテキストの範囲をすべて含むように広げる
+    let span = match self.sink.span(&text_insertion_node) {
+      Some(span) => span.union(self.current_token_span),
+      None => self.current_token_span,
+    };
+    self.sink.set_span(&text_insertion_node, span);
+
     match &self.text_insertion_node {
-      Some(node) if Rc::ptr_eq(node, &text_insertion_node) => {
-        self.text_insertion_string_data.push_str(str);
-      }
-      None => {
-        self.text_insertion_node = Some(text_insertion_node);
+      Some(node) if node.same_node(&text_insertion_node) => {
         self.text_insertion_string_data.push_str(str);
       }
       _ => {
         self.flush_text_insertion();
-        self.text_insertion_node = Some(text_insertion_node);
+        // 既存のテキストノードに連結する場合は、そのデータから書き始める
+        // feedをまたいで長いテキストが続く場合も複製せずに書き足せるよう、
+        // flush_text_insertionまではノードから取り出しておく
+        self.text_insertion_string_data =
+          self.sink.text_data(&text_insertion_node);
+        if !self.text_insertion_string_data.is_empty() {
+          self.sink.set_text_data(&text_insertion_node, EcoString::new());
+        }
         self.text_insertion_string_data.push_str(str);
+        self.text_insertion_node = Some(text_insertion_node);
       }
     }
   }
@@ -793,26 +1313,52 @@ impl<'a> TreeBuilder<'a> {
   fn handle_initial_mode(&mut self, token: Token) {
     match token {
       Token::Text(str) if str.trim().is_empty() => {}
-      Token::DOCTYPE { ref name, .. } => {
-        let name = name.clone().unwrap();
-
-        if name.as_str() != "html" {
-          self.unexpected(&token);
+      Token::DOCTYPE {
+        ref name,
+        ref public_identifier,
+        ref system_identifier,
+        force_quirks,
+      } => {
+        if name.as_deref() != Some("html")
+          || public_identifier.is_some()
+          || system_identifier
+            .as_deref()
+            .is_some_and(|id| id != "about:legacy-compat")
+        {
+          self.parse_error(ParseError::UnknownDoctype);
         }
 
-        let doctype = DocumentType::new(name);
-
-        if let Some(doc) = self.document.as_maybe_document() {
-          doc.set_doctype(doctype);
-        }
+        // iframe srcdoc文書は、DOCTYPEの内容によらず標準モードにする
+        let mode = if self.options.iframe_srcdoc {
+          QuirksMode::NoQuirks
+        } else {
+          quirks_mode_of(
+            name.as_deref(),
+            public_identifier.as_deref(),
+            system_identifier.as_deref(),
+            force_quirks,
+          )
+        };
+
+        self.sink.set_doctype(
+          name.clone().unwrap_or_default(),
+          public_identifier.clone().unwrap_or_default(),
+          system_identifier.clone().unwrap_or_default(),
+        );
+        self.set_document_mode(mode);
 
         self.switch_to(InsertMode::BeforeHtml);
       }
-      Token::Comment(_) => {
-        todo!("handle_initial_mode: Token::Comment");
+      Token::Comment(text) => {
+        let comment = self.create_comment(text);
+        self.sink.append(&self.document, comment);
       }
       _ => {
-        self.unexpected(&token);
+        // iframe srcdoc文書では、DOCTYPEがなくてもよい
+        if !self.options.iframe_srcdoc {
+          self.parse_error(ParseError::ExpectedDoctype);
+          self.set_document_mode(QuirksMode::Quirks);
+        }
         self.switch_to(InsertMode::BeforeHtml);
         self.process(token);
       }
@@ -820,9 +1366,10 @@ impl<'a> TreeBuilder<'a> {
   }
 
   fn handle_before_html_mode(&mut self, token: Token) {
-    fn anything_else(this: &mut TreeBuilder<'_>, token: Token) {
+    fn anything_else<S: TreeSink>(this: &mut TreeBuilder<'_, S>, token: Token) {
       let element = this.create_element_for_tag_name("html");
-      this.document.append_child(element.0.clone());
+      this.sink.set_span(&element, Span::at(this.current_token_span.start));
+      this.sink.append(&this.document, element.clone());
       this.open_elements.push(element.clone());
       this.switch_to(InsertMode::BeforeHead);
       this.process(token.clone());
@@ -834,9 +1381,8 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if let Token::Comment(text) = token {
-      let data = DOMNodeData::Comment(text);
-      let comment = TreeNode::new(DOMNode::new(data));
-      self.document.append_child(comment);
+      let comment = self.create_comment(text);
+      self.sink.append(&self.document, comment);
       return;
     }
 
@@ -848,7 +1394,8 @@ impl<'a> TreeBuilder<'a> {
 
     if token.is_start_tag() && token.tag_name() == "html" {
       let element = self.create_element(token);
-      self.document.append_child(element.0.clone());
+      self.sink.set_span(&element, self.current_token_span);
+      self.sink.append(&self.document, element.clone());
       self.open_elements.push(element.clone());
       self.switch_to(InsertMode::BeforeHead);
       return;
@@ -870,24 +1417,23 @@ impl<'a> TreeBuilder<'a> {
     anything_else(self, token);
   }
 
-  fn handle_before_head_mode(&mut self, token: Token) {
-    fn anything_else(this: &mut TreeBuilder<'_>, token: Token) {
-      let head_element = this.insert_html_element(Token::Tag {
-        tag_name: EcoString::from("head"),
-        attributes: EcoVec::new(),
-        is_end_tag: false,
-        self_closing: false,
-        self_closing_acknowledged: false,
-      });
+  fn handle_before_head_mode(&mut self, mut token: Token) {
+    fn anything_else<S: TreeSink>(this: &mut TreeBuilder<'_, S>, token: Token) {
+      let head_element = this.insert_implied_html_element("head");
       this.head_pointer = Some(head_element.clone());
       this.switch_to(InsertMode::InHead);
       this.process(token);
     }
 
-    if let Token::Text(ref str) = token {
-      if str.trim().is_empty() {
+    // 先頭の空白は無視し、残りは「それ以外」として扱う
+    if let Token::Text(ref s) = token {
+      let (_, rest) = split_leading_whitespace(s);
+
+      if rest.is_empty() {
         return;
       }
+
+      token = Token::new_text(rest);
     }
 
     if let Token::Comment(text) = token {
@@ -927,11 +1473,19 @@ impl<'a> TreeBuilder<'a> {
   }
 
   fn handle_in_head_mode(&mut self, mut token: Token) {
-    if let Token::Text(ref str) = token {
-      if str.trim().is_empty() {
-        self.insert_str(str);
+    // 先頭の空白だけを挿入し、残りは「それ以外」として扱う
+    if let Token::Text(ref s) = token {
+      let (whitespace, rest) = split_leading_whitespace(s);
+
+      if !whitespace.is_empty() {
+        self.insert_str(&EcoString::from(whitespace));
+      }
+
+      if rest.is_empty() {
         return;
       }
+
+      token = Token::new_text(rest);
     }
 
     if let Token::Comment(text) = token {
@@ -953,14 +1507,14 @@ impl<'a> TreeBuilder<'a> {
     {
       self.insert_html_element(token.clone());
       self.open_elements.pop();
-      token.acknowledge_self_closing_if_set();
+      self.acknowledge_self_closing(&mut token);
       return;
     }
 
     if token.is_start_tag() && token.tag_name() == "meta" {
       self.insert_html_element(token.clone());
       self.open_elements.pop();
-      token.acknowledge_self_closing_if_set();
+      self.acknowledge_self_closing(&mut token);
       return;
     }
 
@@ -972,22 +1526,32 @@ impl<'a> TreeBuilder<'a> {
       return;
     }
 
-    if token.is_start_tag() && token.tag_name() == "noscript" && !self.scripting
+    // スクリプトが有効な場合、noscript要素の中身はテキストとして扱う
+    if token.is_start_tag()
+      && (token.match_tag_name_in(&["noframes", "style"])
+        || token.tag_name() == "noscript" && self.options.scripting)
     {
-      todo!("handle_in_head_mode: noscript");
-    }
-
-    if token.is_start_tag() && token.match_tag_name_in(&["noframes", "style"]) {
-      todo!("handle_in_head_mode: noframes, style");
+      self.parse_text_only_element(
+        token,
+        TextOnlyElementParsingAlgorithm::GenericRawText,
+      );
+      return;
     }
 
-    if token.is_start_tag() && token.tag_name() == "noscript" && self.scripting
-    {
-      todo!("handle_in_head_mode: noscript");
+    if token.is_start_tag() && token.tag_name() == "noscript" {
+      self.insert_html_element(token);
+      self.switch_to(InsertMode::InHeadNoscript);
+      return;
     }
 
     if token.is_start_tag() && token.tag_name() == "script" {
-      todo!("handle_in_head_mode: script");
+      self.insert_html_element(token);
+      self.tokenizer.reconsume_in(tokenizer::state::State::ScriptData);
+
+      // スクリプトの解析が終わったら、元の挿入モードに戻りたいので保存しておく
+      self.original_insert_mode = Some(self.insert_mode.clone());
+      self.switch_to(InsertMode::Text);
+      return;
     }
 
     if token.is_end_tag() && token.tag_name() == "head" {
@@ -997,11 +1561,28 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_start_tag() && token.tag_name() == "template" {
-      todo!("handle_in_head_mode: template start");
+      self.insert_html_element(token);
+      self.active_formatting_elements.add_marker();
+      self.frameset_ok = false;
+      self.switch_to(InsertMode::InTemplate);
+      self.template_insert_modes.push(InsertMode::InTemplate);
+      return;
     }
 
     if token.is_end_tag() && token.tag_name() == "template" {
-      todo!("handle_in_head_mode: template end");
+      if !self.open_elements.contains("template") {
+        self.unexpected(&token);
+        return;
+      }
+
+      self.generate_all_implied_end_tags_thoroughly();
+
+      if self.current_node().local_name() != "template" {
+        self.unexpected(&token);
+      }
+
+      self.close_template();
+      return;
     }
 
     if token.is_start_tag() && token.tag_name() == "head" {
@@ -1009,7 +1590,7 @@ impl<'a> TreeBuilder<'a> {
       return;
     }
 
-    if token.is_end_tag() {
+    if token.is_end_tag() && !token.match_tag_name_in(&["body", "html", "br"]) {
       self.unexpected(&token);
       return;
     }
@@ -1019,24 +1600,90 @@ impl<'a> TreeBuilder<'a> {
     self.process(token);
   }
 
-  fn handle_after_head_mode(&mut self, token: Token) {
-    fn anything_else(this: &mut TreeBuilder<'_>, token: Token) {
-      this.insert_html_element(Token::Tag {
-        tag_name: EcoString::from("body"),
-        attributes: EcoVec::new(),
-        is_end_tag: false,
-        self_closing: false,
-        self_closing_acknowledged: false,
-      });
+  fn handle_in_head_noscript_mode(&mut self, mut token: Token) {
+    fn anything_else<S: TreeSink>(this: &mut TreeBuilder<'_, S>, token: Token) {
+      this.unexpected(&token);
+      this.open_elements.pop();
+      this.switch_to(InsertMode::InHead);
+      this.process(token);
+    }
+
+    // 先頭の空白だけをheadの中と同じように挿入し、残りは「それ以外」として扱う
+    if let Token::Text(ref s) = token {
+      let (whitespace, rest) = split_leading_whitespace(s);
+
+      if !whitespace.is_empty() {
+        self.handle_in_head_mode(Token::new_text(whitespace));
+      }
+
+      if rest.is_empty() {
+        return;
+      }
+
+      token = Token::new_text(rest);
+    }
+
+    if let Token::DOCTYPE { .. } = token {
+      self.unexpected(&token);
+      return;
+    }
+
+    if token.is_start_tag() && token.tag_name() == "html" {
+      return self.handle_in_body_mode(token);
+    }
+
+    if token.is_end_tag() && token.tag_name() == "noscript" {
+      self.open_elements.pop();
+      self.switch_to(InsertMode::InHead);
+      return;
+    }
+
+    if let Token::Comment(_) = token {
+      return self.handle_in_head_mode(token);
+    }
+
+    if token.is_start_tag()
+      && token.match_tag_name_in(&[
+        "basefont", "bgsound", "link", "meta", "noframes", "style",
+      ])
+    {
+      return self.handle_in_head_mode(token);
+    }
+
+    if token.is_end_tag() && token.tag_name() == "br" {
+      return anything_else(self, token);
+    }
+
+    if token.is_start_tag() && token.match_tag_name_in(&["head", "noscript"])
+      || token.is_end_tag()
+    {
+      self.unexpected(&token);
+      return;
+    }
+
+    anything_else(self, token);
+  }
+
+  fn handle_after_head_mode(&mut self, mut token: Token) {
+    fn anything_else<S: TreeSink>(this: &mut TreeBuilder<'_, S>, token: Token) {
+      this.insert_implied_html_element("body");
       this.switch_to(InsertMode::InBody);
       this.process(token);
     }
 
-    if let Token::Text(ref str) = token {
-      if str.trim().is_empty() {
-        self.insert_str(str);
+    // 先頭の空白だけを挿入し、残りは「それ以外」として扱う
+    if let Token::Text(ref s) = token {
+      let (whitespace, rest) = split_leading_whitespace(s);
+
+      if !whitespace.is_empty() {
+        self.insert_str(&EcoString::from(whitespace));
+      }
+
+      if rest.is_empty() {
         return;
       }
+
+      token = Token::new_text(rest);
     }
 
     if let Token::Comment(text) = token {
@@ -1060,7 +1707,11 @@ impl<'a> TreeBuilder<'a> {
       return;
     }
 
-    // TODO: framesetタグの扱いを決める
+    if token.is_start_tag() && token.tag_name() == "frameset" {
+      self.insert_html_element(token);
+      self.switch_to(InsertMode::InFrameset);
+      return;
+    }
 
     if token.is_start_tag()
       && token.match_tag_name_in(&[
@@ -1075,7 +1726,7 @@ impl<'a> TreeBuilder<'a> {
       self.handle_in_head_mode(token);
       self
         .open_elements
-        .remove_first_matching_node(|node| Rc::ptr_eq(node, &head));
+        .remove_first_matching_node(|node| node.same_node(&head));
 
       return;
     }
@@ -1102,22 +1753,19 @@ impl<'a> TreeBuilder<'a> {
   }
 
   fn handle_in_body_mode(&mut self, mut token: Token) {
-    fn any_other_end_tags(this: &mut TreeBuilder<'_>, token: Token) {
+    fn any_other_end_tags<S: TreeSink>(
+      this: &mut TreeBuilder<'_, S>,
+      token: Token,
+    ) {
       let mut match_index = None;
 
       for (index, node) in this.open_elements.iter().enumerate().rev() {
-        let node_tag_name = node.as_element().tag_name();
-
-        if node_tag_name == token.tag_name() {
-          if !Rc::ptr_eq(node, &this.current_node()) {
-            this.unexpected(&token);
-          }
-
+        if node.is_html() && node.local_name() == token.tag_name() {
           match_index = Some(index);
           break;
         }
 
-        if is_special_element(node_tag_name) {
+        if is_special_element(node) {
           this.unexpected(&token);
           return;
         }
@@ -1125,13 +1773,19 @@ impl<'a> TreeBuilder<'a> {
 
       if let Some(index) = match_index {
         this.generate_implied_end_tags(token.tag_name());
+
+        // 暗黙の終了タグを生成した後で、現在ノードかどうかを確かめる
+        if index + 1 != this.open_elements.len() {
+          this.unexpected(&token);
+        }
+
         this.open_elements.pop_before_index(index);
       }
     }
 
     if let Token::Text(ref str) = token {
       if str == "\0" {
-        warn!("Unexpected null character");
+        self.parse_error(ParseError::UnexpectedNullCharacter);
         return;
       }
 
@@ -1153,19 +1807,20 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if let Token::DOCTYPE { .. } = token {
-      warn!("Unexpected DOCTYPE");
+      self.parse_error(ParseError::UnexpectedDoctype);
       return;
     }
 
     if token.is_start_tag() && token.tag_name() == "html" {
-      warn!("Unexpected HTML tag");
+      self.parse_error(ParseError::UnexpectedStartTag);
 
       if self.open_elements.contains("template") {
         return;
       }
 
-      let current_node = self.open_elements.current_node().unwrap();
-      self.add_missing_attributes_to_node(&current_node, &token);
+      // 属性はhtml要素(スタックの一番下)に足す
+      let html_element = self.open_elements.get(0);
+      self.add_missing_attributes_to_node(&html_element, &token);
 
       return;
     }
@@ -1194,10 +1849,8 @@ impl<'a> TreeBuilder<'a> {
       let body = self.open_elements.get(1);
 
       // 開いている要素の2番目の要素がbody要素でない場合
-      if let Some(element) = body.as_maybe_element() {
-        if element.tag_name() != "body" {
-          return;
-        }
+      if body.is_element() && body.local_name() != "body" {
+        return;
       }
 
       // 開いている要素にtemplate要素が存在する場合
@@ -1207,14 +1860,36 @@ impl<'a> TreeBuilder<'a> {
 
       self.frameset_ok = false;
       self.add_missing_attributes_to_node(&body, &token);
+      return;
     }
 
-    // TODO: framesetタグの扱いを決める
+    if token.is_start_tag() && token.tag_name() == "frameset" {
+      self.unexpected(&token);
+
+      // フラグメント解析の場合など、2番目の要素がbody要素でなければ無視する
+      let is_body_second = self.open_elements.len() > 1
+        && self.open_elements.get(1).local_name() == "body";
+
+      if !is_body_second || !self.frameset_ok {
+        return;
+      }
+
+      // body要素を取り除いて、frameset要素に置き換える
+      self.sink.remove_from_parent(&self.open_elements.get(1));
+      self.open_elements.pop_before_index(1);
+
+      self.insert_html_element(token);
+      self.switch_to(InsertMode::InFrameset);
+      return;
+    }
 
     if token.is_eof() {
-      // TODO: template挿入モードを扱うかどうか決める
+      if !self.template_insert_modes.is_empty() {
+        return self.handle_in_template_mode(token);
+      }
 
-      if self.open_elements.contains_in(&[
+      // これら以外の要素が開いたままならパースエラー
+      if self.open_elements.contains_not_in(&[
         "dd", "dt", "li", "optgroup", "option", "p", "rb", "rp", "rt", "rtc",
         "tbody", "td", "tfoot", "th", "thead", "tr", "body", "html",
       ]) {
@@ -1242,7 +1917,7 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_end_tag() && token.tag_name() == "html" {
-      if self.open_elements.has_element_name_in_scope("body") {
+      if !self.open_elements.has_element_name_in_scope("body") {
         self.unexpected(&token);
         return;
       }
@@ -1281,6 +1956,7 @@ impl<'a> TreeBuilder<'a> {
         "nav",
         "ol",
         "p",
+        "search",
         "section",
         "summary",
         "ul",
@@ -1302,11 +1978,7 @@ impl<'a> TreeBuilder<'a> {
       }
 
       let current_node = self.open_elements.current_node().unwrap();
-      let current_element = current_node.as_element();
-
-      if current_element
-        .match_tag_name_in(&["h1", "h2", "h3", "h4", "h5", "h6"])
-      {
+      if current_node.match_tag_name_in(&["h1", "h2", "h3", "h4", "h5", "h6"]) {
         self.unexpected(&token);
         self.open_elements.pop();
       }
@@ -1323,42 +1995,49 @@ impl<'a> TreeBuilder<'a> {
       self.insert_html_element(token);
       self.frameset_ok = false;
 
-      let next_token = self.tokenizer.next_token();
-
-      if let Token::Text(ref str) = next_token {
-        if str == "\n" {
-          return;
-        }
-        self.process(next_token);
-      }
-
+      self.ignore_next_line_feed = true;
       return;
     }
 
     if token.is_start_tag() && token.tag_name() == "form" {
-      todo!("process_in_body: form start tag");
+      let has_template = self.open_elements.contains("template");
+
+      if self.form_pointer.is_some() && !has_template {
+        self.unexpected(&token);
+        return;
+      }
+
+      if self.open_elements.has_element_name_in_button_scope("p") {
+        self.close_p_element();
+      }
+
+      let element = self.insert_html_element(token);
+
+      if !has_template {
+        self.form_pointer = Some(element);
+      }
+      return;
     }
 
     if token.is_start_tag() && token.tag_name() == "li" {
       self.frameset_ok = false;
 
       for node in self.open_elements.iter().rev() {
-        let element = node.as_element();
-        let tag_name = element.tag_name();
+        let tag_name = node.local_name();
 
         if tag_name == "li" {
           self.generate_implied_end_tags("li");
 
-          if self.current_node().as_element().tag_name() != "li" {
-            warn!("Expected 'li' tag");
+          if self.current_node().local_name() != "li" {
+            self.parse_error(ParseError::EndTagTooEarly);
           }
 
           self.open_elements.pop_until("li");
           break;
         }
 
-        if is_special_element(tag_name)
-          && !element.match_tag_name_in(&["address", "div", "p"])
+        if is_special_element(node)
+          && !node.match_tag_name_in(&["address", "div", "p"])
         {
           break;
         }
@@ -1377,14 +2056,13 @@ impl<'a> TreeBuilder<'a> {
       self.frameset_ok = false;
 
       for node in self.open_elements.iter().rev() {
-        let element = node.as_element();
-        let tag_name = element.tag_name();
+        let tag_name = node.local_name();
 
         if tag_name == "dd" {
           self.generate_implied_end_tags("dd");
 
-          if self.current_node().as_element().tag_name() != "dd" {
-            warn!("Expected 'dd' tag");
+          if self.current_node().local_name() != "dd" {
+            self.parse_error(ParseError::EndTagTooEarly);
           }
 
           self.open_elements.pop_until("dd");
@@ -1395,8 +2073,8 @@ impl<'a> TreeBuilder<'a> {
         if tag_name == "dt" {
           self.generate_implied_end_tags("dt");
 
-          if self.current_node().as_element().tag_name() != "dt" {
-            warn!("Expected 'dt' tag");
+          if self.current_node().local_name() != "dt" {
+            self.parse_error(ParseError::EndTagTooEarly);
           }
 
           self.open_elements.pop_until("dt");
@@ -1404,8 +2082,8 @@ impl<'a> TreeBuilder<'a> {
           break;
         }
 
-        if is_special_element(tag_name)
-          && !element.match_tag_name_in(&["address", "div", "p"])
+        if is_special_element(node)
+          && !node.match_tag_name_in(&["address", "div", "p"])
         {
           break;
         }
@@ -1421,7 +2099,15 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_start_tag() && token.tag_name() == "plaintext" {
-      todo!("process_in_body: plaintext start tag");
+      if self.open_elements.has_element_name_in_button_scope("p") {
+        self.close_p_element();
+      }
+
+      self.insert_html_element(token);
+      // 一度PLAINTEXT状態に入ると、終了タグも含めて以降はすべてテキストとなる
+      self.tokenizer.reconsume_in(tokenizer::state::State::PLAINTEXT);
+
+      return;
     }
 
     if token.is_start_tag() && token.tag_name() == "button" {
@@ -1463,6 +2149,7 @@ impl<'a> TreeBuilder<'a> {
         "nav",
         "ol",
         "pre",
+        "search",
         "section",
         "summary",
         "ul",
@@ -1476,11 +2163,8 @@ impl<'a> TreeBuilder<'a> {
       self.generate_implied_end_tags("");
 
       let current_node = self.current_node();
-      let current_element = current_node.as_element();
-
-      if current_element.tag_name() != token.tag_name() {
+      if current_node.local_name() != token.tag_name() {
         self.unexpected(&token);
-        return;
       }
 
       self.open_elements.pop_until(token.tag_name());
@@ -1488,13 +2172,47 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_end_tag() && token.tag_name() == "form" {
-      todo!("process_in_body: form end tag");
+      if !self.open_elements.contains("template") {
+        let node = self.form_pointer.take();
+
+        let Some(node) =
+          node.filter(|node| self.open_elements.has_element_in_scope(node))
+        else {
+          self.unexpected(&token);
+          return;
+        };
+
+        self.generate_implied_end_tags("");
+
+        if !self.current_node().same_node(&node) {
+          self.unexpected(&token);
+        }
+
+        // form要素より後に開かれた要素は、スタックに残したままにする
+        self.open_elements.remove_first_matching_node(|el| el.same_node(&node));
+        return;
+      }
+
+      // template要素の中では、form要素ポインタを使わない
+      if !self.open_elements.has_element_name_in_scope("form") {
+        self.unexpected(&token);
+        return;
+      }
+
+      self.generate_implied_end_tags("");
+
+      if self.current_node().local_name() != "form" {
+        self.unexpected(&token);
+      }
+
+      self.open_elements.pop_until("form");
+      return;
     }
 
     if token.is_end_tag() && token.tag_name() == "p" {
       if !self.open_elements.has_element_name_in_button_scope("p") {
         self.unexpected(&token);
-        self.insert_html_element(Token::new_start_tag_of("p"));
+        self.insert_implied_html_element("p");
       }
       self.close_p_element();
       return;
@@ -1508,7 +2226,7 @@ impl<'a> TreeBuilder<'a> {
 
       self.generate_implied_end_tags("li");
 
-      if self.current_node().as_element().tag_name() != "li" {
+      if self.current_node().local_name() != "li" {
         self.unexpected(&token);
       }
 
@@ -1527,7 +2245,7 @@ impl<'a> TreeBuilder<'a> {
 
       self.generate_implied_end_tags(tag_name);
 
-      if self.current_node().as_element().tag_name() != tag_name {
+      if self.current_node().local_name() != tag_name {
         self.unexpected(&token);
       }
 
@@ -1549,9 +2267,7 @@ impl<'a> TreeBuilder<'a> {
       self.generate_implied_end_tags("");
 
       let current_node = self.open_elements.current_node().unwrap();
-      let current_element = current_node.as_element();
-
-      if current_element.tag_name() != token.tag_name() {
+      if current_node.local_name() != token.tag_name() {
         self.unexpected(&token);
       }
 
@@ -1576,7 +2292,7 @@ impl<'a> TreeBuilder<'a> {
         self.active_formatting_elements.remove_element(&element);
         self
           .open_elements
-          .remove_first_matching_node(|node| Rc::ptr_eq(node, &element));
+          .remove_first_matching_node(|node| node.same_node(&element));
       }
 
       self.reconstruct_active_formatting_elements();
@@ -1598,7 +2314,18 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_start_tag() && token.tag_name() == "nobr" {
-      todo!("process_in_body: nobr start tag");
+      self.reconstruct_active_formatting_elements();
+
+      // nobr要素は入れ子にできないので、開いているnobr要素を閉じる
+      if self.open_elements.has_element_name_in_scope("nobr") {
+        self.unexpected(&token);
+        self.adoption_agency_algorithm(&token);
+        self.reconstruct_active_formatting_elements();
+      }
+
+      let element = self.insert_html_element(token);
+      self.active_formatting_elements.push(Entry::Element(element));
+      return;
     }
 
     if token.is_end_tag()
@@ -1637,7 +2364,7 @@ impl<'a> TreeBuilder<'a> {
 
       self.generate_implied_end_tags("");
 
-      if self.current_node().as_element().tag_name() != token.tag_name() {
+      if self.current_node().local_name() != token.tag_name() {
         self.unexpected(&token);
       }
 
@@ -1648,8 +2375,10 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_start_tag() && token.tag_name() == "table" {
-      // TODO: quicksモードの場合、この処理は行わない
-      if self.open_elements.has_element_name_in_button_scope("p") {
+      // quirksモードの場合、p要素は閉じない
+      if !self.is_quirks_mode()
+        && self.open_elements.has_element_name_in_button_scope("p")
+      {
         self.close_p_element();
       }
 
@@ -1660,8 +2389,10 @@ impl<'a> TreeBuilder<'a> {
       return;
     }
 
+    // 属性を捨てて、<br>と同じように扱う
     if token.is_end_tag() && token.tag_name() == "br" {
-      todo!("process_in_body: br end tag");
+      self.unexpected(&token);
+      return self.handle_in_body_mode(Token::new_start_tag_of("br"));
     }
 
     if token.is_start_tag()
@@ -1669,7 +2400,7 @@ impl<'a> TreeBuilder<'a> {
         .match_tag_name_in(&["area", "br", "embed", "img", "keygen", "wbr"])
     {
       self.reconstruct_active_formatting_elements();
-      token.acknowledge_self_closing_if_set();
+      self.acknowledge_self_closing(&mut token);
       self.insert_html_element(token);
       self.open_elements.pop();
       self.frameset_ok = false;
@@ -1677,13 +2408,21 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_start_tag() && token.tag_name() == "input" {
-      todo!("process_in_body: input start tag");
+      self.reconstruct_active_formatting_elements();
+      self.acknowledge_self_closing(&mut token);
+      let hidden = is_hidden_input(&token);
+      self.insert_html_element(token);
+      self.open_elements.pop();
+      if !hidden {
+        self.frameset_ok = false;
+      }
+      return;
     }
 
     if token.is_start_tag()
       && token.match_tag_name_in(&["param", "source", "track"])
     {
-      token.acknowledge_self_closing_if_set();
+      self.acknowledge_self_closing(&mut token);
       self.insert_html_element(token);
       self.open_elements.pop();
       return;
@@ -1693,7 +2432,7 @@ impl<'a> TreeBuilder<'a> {
       if self.open_elements.has_element_name_in_button_scope("p") {
         self.close_p_element();
       }
-      token.acknowledge_self_closing_if_set();
+      self.acknowledge_self_closing(&mut token);
       self.insert_html_element(token);
       self.open_elements.pop();
       self.frameset_ok = false;
@@ -1701,18 +2440,27 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_start_tag() && token.tag_name() == "image" {
-      todo!("process_in_body: image start tag");
+      self.unexpected(&token);
+      token.set_tag_name("img");
+      return self.process(token);
     }
 
     if token.is_start_tag() && token.tag_name() == "textarea" {
-      todo!("process_in_body: textarea start tag");
+      self.parse_text_only_element(
+        token,
+        TextOnlyElementParsingAlgorithm::GenericRCDataElement,
+      );
+      self.ignore_next_line_feed = true;
+      self.frameset_ok = false;
+      return;
     }
 
     if token.is_start_tag() && token.tag_name() == "xmp" {
-      todo!("process_in_body: xmp start tag");
-    }
+      if self.open_elements.has_element_name_in_button_scope("p") {
+        self.close_p_element();
+      }
 
-    if token.is_start_tag() && token.tag_name() == "iframe" {
+      self.reconstruct_active_formatting_elements();
       self.frameset_ok = false;
       self.parse_text_only_element(
         token,
@@ -1721,26 +2469,69 @@ impl<'a> TreeBuilder<'a> {
       return;
     }
 
-    if token.is_start_tag() && token.tag_name() == "noembed" {
-      todo!("process_in_body: noembed start tag");
+    if token.is_start_tag() && token.tag_name() == "iframe" {
+      self.frameset_ok = false;
+      self.parse_text_only_element(
+        token,
+        TextOnlyElementParsingAlgorithm::GenericRawText,
+      );
+      return;
     }
 
-    if token.is_start_tag() && token.tag_name() == "noscript" && self.scripting
+    // スクリプトが無効な場合、noscript要素はほかの要素と同じように扱う
+    if token.is_start_tag()
+      && (token.tag_name() == "noembed"
+        || token.tag_name() == "noscript" && self.options.scripting)
     {
-      todo!("process_in_body: noscript start tag");
+      self.parse_text_only_element(
+        token,
+        TextOnlyElementParsingAlgorithm::GenericRawText,
+      );
+      return;
     }
 
     if token.is_start_tag() && token.tag_name() == "select" {
-      todo!("process_in_body: select start tag");
+      self.reconstruct_active_formatting_elements();
+      self.insert_html_element(token);
+      self.frameset_ok = false;
+
+      match self.insert_mode {
+        InsertMode::InTable
+        | InsertMode::InCaption
+        | InsertMode::InTableBody
+        | InsertMode::InRow
+        | InsertMode::InCell => {
+          self.switch_to(InsertMode::InSelectInTable);
+        }
+        _ => {
+          self.switch_to(InsertMode::InSelect);
+        }
+      }
+      return;
     }
 
     if token.is_start_tag() && token.match_tag_name_in(&["optgroup", "option"])
     {
-      todo!("process_in_body: optgroup/option start tag");
+      if self.current_node().local_name() == "option" {
+        self.open_elements.pop();
+      }
+
+      self.reconstruct_active_formatting_elements();
+      self.insert_html_element(token);
+      return;
     }
 
     if token.is_start_tag() && token.match_tag_name_in(&["rb", "rtc"]) {
-      todo!("process_in_body: rb/rtc start tag");
+      if self.open_elements.has_element_name_in_scope("ruby") {
+        self.generate_implied_end_tags("");
+      }
+
+      if !self.current_node().match_tag_name_in(&["ruby"]) {
+        self.unexpected(&token);
+      }
+
+      self.insert_html_element(token);
+      return;
     }
 
     if token.is_start_tag() && token.match_tag_name_in(&["rp", "rt"]) {
@@ -1748,7 +2539,7 @@ impl<'a> TreeBuilder<'a> {
         self.generate_implied_end_tags("rtc");
       }
 
-      if !self.current_node().as_element().match_tag_name_in(&["rtc", "ruby"]) {
+      if !self.current_node().match_tag_name_in(&["rtc", "ruby"]) {
         self.unexpected(&token);
       }
 
@@ -1756,12 +2547,25 @@ impl<'a> TreeBuilder<'a> {
       return;
     }
 
-    if token.is_start_tag() && token.tag_name() == "math" {
-      todo!("process_in_body: math start tag");
-    }
+    if token.is_start_tag() && token.match_tag_name_in(&["math", "svg"]) {
+      self.reconstruct_active_formatting_elements();
+
+      let namespace = if token.tag_name() == "math" {
+        adjust_mathml_attributes(&mut token);
+        Namespace::MathMl
+      } else {
+        adjust_svg_attributes(&mut token);
+        Namespace::Svg
+      };
 
-    if token.is_start_tag() && token.tag_name() == "svg" {
-      todo!("process_in_body: svg start tag");
+      let is_self_closing = token.is_self_closing();
+      self.acknowledge_self_closing(&mut token);
+      self.insert_foreign_element(token, namespace);
+
+      if is_self_closing {
+        self.open_elements.pop();
+      }
+      return;
     }
 
     if token.is_start_tag()
@@ -1770,7 +2574,8 @@ impl<'a> TreeBuilder<'a> {
         "th", "thead", "tr",
       ])
     {
-      todo!("process_in_body: table related start tag");
+      self.unexpected(&token);
+      return;
     }
 
     if token.is_start_tag() {
@@ -1792,10 +2597,9 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if let Token::Comment(text) = token {
-      let data = DOMNodeData::Comment(text);
-      let comment = TreeNode::new(DOMNode::new(data));
+      let comment = self.create_comment(text);
       let first_open_element = self.open_elements.first().unwrap();
-      first_open_element.append_child(comment);
+      self.sink.append(first_open_element, comment);
       return;
     }
 
@@ -1809,7 +2613,11 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_end_tag() && token.tag_name() == "html" {
-      // TODO: フラグメント解析アルゴリズムをサポートするか決める
+      // フラグメント解析では無視する
+      if self.context_element.is_some() {
+        self.unexpected(&token);
+        return;
+      }
 
       self.switch_to(InsertMode::AfterAfterBody);
       return;
@@ -1832,9 +2640,8 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if let Token::Comment(text) = token {
-      let data = DOMNodeData::Comment(text);
-      let comment = TreeNode::new(DOMNode::new(data));
-      self.document.append_child(comment);
+      let comment = self.create_comment(text);
+      self.sink.append(&self.document, comment);
       return;
     }
 
@@ -1857,14 +2664,172 @@ impl<'a> TreeBuilder<'a> {
     self.process(token);
   }
 
-  fn handle_in_table_mode(&mut self, token: Token) {
+  // 空白文字だけを挿入し、それ以外の文字はパースエラーとして無視する
+  fn insert_whitespace_only(&mut self, token: &Token) {
+    if let Some(whitespace) = self.take_whitespace(token) {
+      self.insert_str(&whitespace);
+    }
+  }
+
+  // テキストから空白文字だけを取り出す
+  // それ以外の文字はパースエラーとして捨て、空白文字がない場合はNoneを返す
+  fn take_whitespace(&mut self, token: &Token) -> Option<EcoString> {
+    let Token::Text(s) = token else {
+      return None;
+    };
+
+    let whitespace =
+      s.chars().filter(|c| c.is_ascii_whitespace()).collect::<EcoString>();
+
+    if whitespace.len() < s.len() {
+      self.unexpected(token);
+    }
+
+    (!whitespace.is_empty()).then_some(whitespace)
+  }
+
+  fn handle_in_frameset_mode(&mut self, mut token: Token) {
     if let Token::Text(_) = token {
-      if self
-        .current_node()
-        .as_element()
-        .match_tag_name_in(&["table", "tbody", "tfoot", "thead", "tr"])
+      return self.insert_whitespace_only(&token);
+    }
+
+    if let Token::Comment(text) = token {
+      self.insert_comment(text);
+      return;
+    }
+
+    if let Token::DOCTYPE { .. } = token {
+      self.unexpected(&token);
+      return;
+    }
+
+    if token.is_start_tag() && token.tag_name() == "html" {
+      return self.handle_in_body_mode(token);
+    }
+
+    if token.is_start_tag() && token.tag_name() == "frameset" {
+      self.insert_html_element(token);
+      return;
+    }
+
+    if token.is_end_tag() && token.tag_name() == "frameset" {
+      // フラグメント解析の場合は、html要素しか残っていないことがある
+      if self.open_elements.len() == 1 {
+        self.unexpected(&token);
+        return;
+      }
+
+      self.open_elements.pop();
+
+      if self.context_element.is_none()
+        && self.current_node().local_name() != "frameset"
       {
+        self.switch_to(InsertMode::AfterFrameset);
+      }
+      return;
+    }
+
+    if token.is_start_tag() && token.tag_name() == "frame" {
+      self.acknowledge_self_closing(&mut token);
+      self.insert_html_element(token);
+      self.open_elements.pop();
+      return;
+    }
+
+    if token.is_start_tag() && token.tag_name() == "noframes" {
+      return self.handle_in_head_mode(token);
+    }
+
+    if token.is_eof() {
+      if self.open_elements.len() != 1 {
+        self.unexpected(&token);
+      }
+
+      self.stop_parsing();
+      return;
+    }
+
+    self.unexpected(&token);
+  }
+
+  fn handle_after_frameset_mode(&mut self, token: Token) {
+    if let Token::Text(_) = token {
+      return self.insert_whitespace_only(&token);
+    }
+
+    if let Token::Comment(text) = token {
+      self.insert_comment(text);
+      return;
+    }
+
+    if let Token::DOCTYPE { .. } = token {
+      self.unexpected(&token);
+      return;
+    }
+
+    if token.is_start_tag() && token.tag_name() == "html" {
+      return self.handle_in_body_mode(token);
+    }
+
+    if token.is_end_tag() && token.tag_name() == "html" {
+      self.switch_to(InsertMode::AfterAfterFrameset);
+      return;
+    }
+
+    if token.is_start_tag() && token.tag_name() == "noframes" {
+      return self.handle_in_head_mode(token);
+    }
+
+    if token.is_eof() {
+      self.stop_parsing();
+      return;
+    }
+
+    self.unexpected(&token);
+  }
+
+  fn handle_after_after_frameset_mode(&mut self, token: Token) {
+    if token.is_eof() {
+      self.stop_parsing();
+      return;
+    }
+
+    if let Token::Comment(text) = token {
+      let comment = self.create_comment(text);
+      self.sink.append(&self.document, comment);
+      return;
+    }
+
+    if let Token::DOCTYPE { .. } = token {
+      return self.handle_in_body_mode(token);
+    }
+
+    // 空白文字はbodyの中と同じように扱い、それ以外の文字は無視する
+    if let Token::Text(_) = token {
+      if let Some(whitespace) = self.take_whitespace(&token) {
+        self.handle_in_body_mode(Token::Text(whitespace));
+      }
+      return;
+    }
+
+    if token.is_start_tag() && token.tag_name() == "html" {
+      return self.handle_in_body_mode(token);
+    }
+
+    if token.is_start_tag() && token.tag_name() == "noframes" {
+      return self.handle_in_head_mode(token);
+    }
+
+    self.unexpected(&token);
+  }
+
+  fn handle_in_table_mode(&mut self, mut token: Token) {
+    if let Token::Text(_) = token {
+      if self.current_node().match_tag_name_in(&[
+        "table", "tbody", "template", "tfoot", "thead", "tr",
+      ]) {
         self.pending_table_character_tokens.clear();
+        self.pending_table_character_location = None;
         self.original_insert_mode = Some(self.insert_mode.clone());
         self.switch_to(InsertMode::InTableText);
         return self.process(token);
@@ -1897,7 +2862,10 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_start_tag() && token.tag_name() == "col" {
-      todo!("process_in_table: col start tag");
+      self.open_elements.clear_back_to_table_context();
+      self.insert_implied_html_element("colgroup");
+      self.switch_to(InsertMode::InColumnGroup);
+      return self.process(token);
     }
 
     if token.is_start_tag()
@@ -1911,13 +2879,21 @@ impl<'a> TreeBuilder<'a> {
 
     if token.is_start_tag() && token.match_tag_name_in(&["td", "th", "tr"]) {
       self.open_elements.clear_back_to_table_context();
-      self.insert_html_element(Token::new_start_tag_of("tbody"));
+      self.insert_implied_html_element("tbody");
       self.switch_to(InsertMode::InTableBody);
       return self.process(token);
     }
 
     if token.is_start_tag() && token.tag_name() == "table" {
-      todo!("process_in_table: table start tag");
+      self.unexpected(&token);
+
+      if !self.open_elements.has_element_name_in_table_scope("table") {
+        return;
+      }
+
+      self.open_elements.pop_until("table");
+      self.reset_insertion_mode_appropriately();
+      return self.process(token);
     }
 
     if token.is_end_tag() && token.tag_name() == "table" {
@@ -1936,26 +2912,45 @@ impl<'a> TreeBuilder<'a> {
         "th", "thead", "tr",
       ])
     {
-      todo!("process_in_table: body/caption/col/colgroup/html/tbody/td/tfoot/th/thead/tr end tag");
+      self.unexpected(&token);
+      return;
     }
 
     if token.is_start_tag()
       && token.match_tag_name_in(&["style", "script", "template"])
     {
-      todo!("process_in_table: style/script/template start tag");
+      return self.handle_in_head_mode(token);
     }
 
     if token.is_end_tag() && token.tag_name() == "template" {
-      todo!("process_in_table: template end tag");
+      return self.handle_in_head_mode(token);
     }
 
-    if token.is_start_tag() && token.tag_name() == "input" {
-      todo!("process_in_table: input start tag");
+    // type="hidden"のinput要素だけは、tableの中にそのまま置く
+    if token.is_start_tag()
+      && token.tag_name() == "input"
+      && is_hidden_input(&token)
+    {
+      self.unexpected(&token);
+      self.acknowledge_self_closing(&mut token);
+      self.insert_html_element(token);
+      self.open_elements.pop();
+      return;
     }
 
     if token.is_start_tag() && token.tag_name() == "form" {
-      todo!("process_in_table: form start tag");
-    }
+      self.unexpected(&token);
+
+      if self.open_elements.contains("template") || self.form_pointer.is_some()
+      {
+        return;
+      }
+
+      let element = self.insert_html_element(token);
+      self.form_pointer = Some(element);
+      self.open_elements.pop();
+      return;
+    }
 
     if let Token::EOF = token {
       return self.handle_in_body_mode(token);
@@ -1973,18 +2968,22 @@ impl<'a> TreeBuilder<'a> {
         self.unexpected(&token);
         return;
       }
+
+      // U+00A0などはASCII空白ではないので、里親に出される
+      if self.pending_table_character_location.is_none()
+        && s.chars().any(|c| !c.is_ascii_whitespace())
+      {
+        self.pending_table_character_location =
+          Some(self.tokenizer.location_at(self.current_token_span.start));
+      }
+
       self.pending_table_character_tokens.push(token);
       return;
     }
 
-    let has_not_whitespace_char =
-      self.pending_table_character_tokens.iter().any(|token| match token {
-        Token::Text(s) => !s.trim().is_empty(),
-        _ => false,
-      });
-
-    if has_not_whitespace_char {
-      warn!("Non-whitespace in table text");
+    // パースエラーは、テキストを保留し終えたトークンではなく、テキストの位置に報告する
+    if let Some(location) = self.pending_table_character_location {
+      self.report_error(ParseError::UnexpectedCharacterInTable(location));
       for pending_token in self.pending_table_character_tokens.clone() {
         self.foster_parenting = true;
         self.handle_in_body_mode(pending_token);
@@ -2011,7 +3010,11 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_start_tag() && token.match_tag_name_in(&["th", "td"]) {
-      todo!("process_in_table_body: th/td start tag");
+      self.unexpected(&token);
+      self.open_elements.clear_back_to_table_body_context();
+      self.insert_implied_html_element("tr");
+      self.switch_to(InsertMode::InRow);
+      return self.process(token);
     }
 
     if token.is_end_tag()
@@ -2033,7 +3036,19 @@ impl<'a> TreeBuilder<'a> {
         "caption", "col", "colgroup", "tbody", "tfoot", "thead",
       ])
     {
-      todo!("process_in_table_body: caption/col/colgroup/tbody/tfoot/thead start tag");
+      if !self
+        .open_elements
+        .has_oneof_element_names_in_table_scope(&["tbody", "tfoot", "thead"])
+      {
+        self.unexpected(&token);
+        return;
+      }
+
+      self.open_elements.clear_back_to_table_body_context();
+      self.open_elements.pop();
+
+      self.switch_to(InsertMode::InTable);
+      return self.process(token);
     }
 
     if token.is_end_tag() && token.tag_name() == "table" {
@@ -2057,7 +3072,8 @@ impl<'a> TreeBuilder<'a> {
         "body", "caption", "col", "colgroup", "html", "td", "th", "tr",
       ])
     {
-      todo!("process_in_table_body: body end tag");
+      self.unexpected(&token);
+      return;
     }
 
     self.handle_in_table_mode(token)
@@ -2078,9 +3094,7 @@ impl<'a> TreeBuilder<'a> {
         return;
       }
 
-      self.open_elements.clear_back_to_table_row_context();
-      self.open_elements.pop();
-      self.switch_to(InsertMode::InTableBody);
+      self.close_table_row();
       return;
     }
 
@@ -2089,17 +3103,39 @@ impl<'a> TreeBuilder<'a> {
         "caption", "col", "colgroup", "tbody", "tfoot", "thead", "tr",
       ])
     {
-      todo!("process_in_row: caption/col/colgroup/tbody/tfoot/thead start tag");
+      if !self.open_elements.has_element_name_in_table_scope("tr") {
+        self.unexpected(&token);
+        return;
+      }
+
+      self.close_table_row();
+      return self.process(token);
     }
 
     if token.is_end_tag() && token.tag_name() == "table" {
-      todo!("process_in_row: table end tag");
+      if !self.open_elements.has_element_name_in_table_scope("tr") {
+        self.unexpected(&token);
+        return;
+      }
+
+      self.close_table_row();
+      return self.process(token);
     }
 
     if token.is_end_tag()
       && token.match_tag_name_in(&["tbody", "tfoot", "thead"])
     {
-      todo!("process_in_row: tbody/tfoot/thead end tag");
+      if !self.open_elements.has_element_name_in_table_scope(token.tag_name()) {
+        self.unexpected(&token);
+        return;
+      }
+
+      if !self.open_elements.has_element_name_in_table_scope("tr") {
+        return;
+      }
+
+      self.close_table_row();
+      return self.process(token);
     }
 
     if token.is_end_tag()
@@ -2107,7 +3143,8 @@ impl<'a> TreeBuilder<'a> {
         "body", "caption", "col", "colgroup", "html", "td", "th",
       ])
     {
-      todo!("process_in_row: body/caption/col/colgroup/html/td/th end tag");
+      self.unexpected(&token);
+      return;
     }
 
     self.handle_in_table_mode(token)
@@ -2122,8 +3159,8 @@ impl<'a> TreeBuilder<'a> {
 
       self.generate_implied_end_tags("");
 
-      if self.current_node().as_element().tag_name() != token.tag_name() {
-        warn!("Expected current node to have same tag name as token");
+      if self.current_node().local_name() != token.tag_name() {
+        self.parse_error(ParseError::EndTagTooEarly);
       }
       self.open_elements.pop_until(token.tag_name());
       self.active_formatting_elements.clear_up_to_last_marker();
@@ -2137,31 +3174,55 @@ impl<'a> TreeBuilder<'a> {
         "tr",
       ])
     {
-      todo!("process_in_cell: caption/col/colgroup/tbody/td/tfoot/th/thead/tr start tag");
+      if !self
+        .open_elements
+        .has_oneof_element_names_in_table_scope(&["td", "th"])
+      {
+        self.unexpected(&token);
+        return;
+      }
+
+      self.close_cell();
+      return self.process(token);
     }
 
     if token.is_end_tag()
       && token
         .match_tag_name_in(&["body", "caption", "col", "colgroup", "html"])
     {
-      todo!("process_in_cell: body/caption/col/colgroup/html end tag");
+      self.unexpected(&token);
+      return;
     }
 
     if token.is_end_tag()
       && token.match_tag_name_in(&["table", "tbody", "tfoot", "thead", "tr"])
     {
-      todo!("process_in_cell: table/tbody/tfoot/thead/tr end tag");
+      if !self.open_elements.has_element_name_in_table_scope(token.tag_name()) {
+        self.unexpected(&token);
+        return;
+      }
+
+      self.close_cell();
+      return self.process(token);
     }
 
     self.handle_in_body_mode(token)
   }
 
   fn handle_in_column_group_mode(&mut self, mut token: Token) {
+    // 先頭の空白だけを挿入し、残りは「それ以外」として扱う
     if let Token::Text(ref s) = token {
-      if s.trim().is_empty() {
-        self.insert_str(s);
+      let (whitespace, rest) = split_leading_whitespace(s);
+
+      if !whitespace.is_empty() {
+        self.insert_str(&EcoString::from(whitespace));
+      }
+
+      if rest.is_empty() {
         return;
       }
+
+      token = Token::new_text(rest);
     }
 
     if let Token::Comment(text) = token {
@@ -2179,14 +3240,14 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_start_tag() && token.tag_name() == "col" {
-      token.acknowledge_self_closing_if_set();
+      self.acknowledge_self_closing(&mut token);
       self.insert_html_element(token);
       self.open_elements.pop();
       return;
     }
 
     if token.is_end_tag() && token.tag_name() == "colgroup" {
-      if self.current_node().as_element().tag_name() != "colgroup" {
+      if self.current_node().local_name() != "colgroup" {
         self.unexpected(&token);
         return;
       }
@@ -2202,7 +3263,7 @@ impl<'a> TreeBuilder<'a> {
       return;
     }
 
-    if token.tag_name() == "template" {
+    if token.match_tag_name_in(&["template"]) {
       return self.handle_in_head_mode(token);
     }
 
@@ -2210,7 +3271,7 @@ impl<'a> TreeBuilder<'a> {
       return self.handle_in_body_mode(token);
     }
 
-    if self.current_node().as_element().tag_name() != "colgroup" {
+    if self.current_node().local_name() != "colgroup" {
       self.unexpected(&token);
       return;
     }
@@ -2229,7 +3290,7 @@ impl<'a> TreeBuilder<'a> {
 
       self.generate_implied_end_tags("");
 
-      if self.current_node().as_element().tag_name() != "caption" {
+      if self.current_node().local_name() != "caption" {
         self.unexpected(&token);
       }
 
@@ -2253,7 +3314,7 @@ impl<'a> TreeBuilder<'a> {
 
       self.generate_implied_end_tags("");
 
-      if self.current_node().as_element().tag_name() != "caption" {
+      if self.current_node().local_name() != "caption" {
         self.unexpected(&token);
       }
 
@@ -2272,7 +3333,7 @@ impl<'a> TreeBuilder<'a> {
 
       self.generate_implied_end_tags("");
 
-      if self.current_node().as_element().tag_name() != "caption" {
+      if self.current_node().local_name() != "caption" {
         self.unexpected(&token);
       }
 
@@ -2296,12 +3357,352 @@ impl<'a> TreeBuilder<'a> {
     self.handle_in_body_mode(token)
   }
 
-  fn handle_in_select_mode(&mut self, _token: Token) {
-    todo!("handle_in_select_mode");
+  fn handle_in_select_mode(&mut self, mut token: Token) {
+    if let Token::Text(ref str) = token {
+      if str == "\0" {
+        self.parse_error(ParseError::UnexpectedNullCharacter);
+        return;
+      }
+
+      self.insert_str(str);
+      return;
+    }
+
+    if let Token::Comment(text) = token {
+      self.insert_comment(text);
+      return;
+    }
+
+    if let Token::DOCTYPE { .. } = token {
+      self.unexpected(&token);
+      return;
+    }
+
+    if token.is_start_tag() && token.tag_name() == "html" {
+      return self.handle_in_body_mode(token);
+    }
+
+    if token.is_start_tag() && token.tag_name() == "option" {
+      if self.current_node().local_name() == "option" {
+        self.open_elements.pop();
+      }
+
+      self.insert_html_element(token);
+      return;
+    }
+
+    if token.is_start_tag() && token.tag_name() == "optgroup" {
+      if self.current_node().local_name() == "option" {
+        self.open_elements.pop();
+      }
+
+      if self.current_node().local_name() == "optgroup" {
+        self.open_elements.pop();
+      }
+
+      self.insert_html_element(token);
+      return;
+    }
+
+    if token.is_start_tag() && token.tag_name() == "hr" {
+      if self.current_node().local_name() == "option" {
+        self.open_elements.pop();
+      }
+
+      if self.current_node().local_name() == "optgroup" {
+        self.open_elements.pop();
+      }
+
+      self.acknowledge_self_closing(&mut token);
+      self.insert_html_element(token);
+      self.open_elements.pop();
+      return;
+    }
+
+    if token.is_end_tag() && token.tag_name() == "optgroup" {
+      // <optgroup><option></optgroup> のように、optionが閉じられていない場合
+      let len = self.open_elements.len();
+      if self.current_node().local_name() == "option"
+        && len >= 2
+        && self.open_elements.get(len - 2).local_name() == "optgroup"
+      {
+        self.open_elements.pop();
+      }
+
+      if self.current_node().local_name() == "optgroup" {
+        self.open_elements.pop();
+      } else {
+        self.unexpected(&token);
+      }
+      return;
+    }
+
+    if token.is_end_tag() && token.tag_name() == "option" {
+      if self.current_node().local_name() == "option" {
+        self.open_elements.pop();
+      } else {
+        self.unexpected(&token);
+      }
+      return;
+    }
+
+    if token.is_end_tag() && token.tag_name() == "select" {
+      if !self.open_elements.has_element_name_in_select_scope("select") {
+        self.unexpected(&token);
+        return;
+      }
+
+      self.open_elements.pop_until("select");
+      self.reset_insertion_mode_appropriately();
+      return;
+    }
+
+    // select要素の中のselect開始タグは、終了タグとして扱う
+    if token.is_start_tag() && token.tag_name() == "select" {
+      self.unexpected(&token);
+
+      if !self.open_elements.has_element_name_in_select_scope("select") {
+        return;
+      }
+
+      self.open_elements.pop_until("select");
+      self.reset_insertion_mode_appropriately();
+      return;
+    }
+
+    if token.is_start_tag()
+      && token.match_tag_name_in(&["input", "keygen", "textarea"])
+    {
+      self.unexpected(&token);
+
+      if !self.open_elements.has_element_name_in_select_scope("select") {
+        return;
+      }
+
+      self.open_elements.pop_until("select");
+      self.reset_insertion_mode_appropriately();
+      return self.process(token);
+    }
+
+    if token.is_start_tag() && token.match_tag_name_in(&["script", "template"])
+    {
+      return self.handle_in_head_mode(token);
+    }
+
+    if token.is_end_tag() && token.tag_name() == "template" {
+      return self.handle_in_head_mode(token);
+    }
+
+    if token.is_eof() {
+      return self.handle_in_body_mode(token);
+    }
+
+    self.unexpected(&token);
+  }
+
+  fn handle_in_select_in_table_mode(&mut self, token: Token) {
+    let table_related = [
+      "caption", "table", "tbody", "tfoot", "thead", "tr", "td", "th",
+    ];
+
+    if token.is_start_tag() && token.match_tag_name_in(&table_related) {
+      self.unexpected(&token);
+      self.open_elements.pop_until("select");
+      self.reset_insertion_mode_appropriately();
+      return self.process(token);
+    }
+
+    if token.is_end_tag() && token.match_tag_name_in(&table_related) {
+      self.unexpected(&token);
+
+      if !self.open_elements.has_element_name_in_table_scope(token.tag_name()) {
+        return;
+      }
+
+      self.open_elements.pop_until("select");
+      self.reset_insertion_mode_appropriately();
+      return self.process(token);
+    }
+
+    self.handle_in_select_mode(token)
   }
 
-  fn handle_in_select_in_table_mode(&mut self, _token: Token) {
-    todo!("handle_in_select_in_table_mode");
+  fn close_template(&mut self) {
+    self.open_elements.pop_until("template");
+    self.active_formatting_elements.clear_up_to_last_marker();
+    self.template_insert_modes.pop();
+    self.reset_insertion_mode_appropriately();
+  }
+
+  // 開始タグの種類から、template要素の中身がどの文脈にあるかを決める
+  fn switch_template_insert_mode(&mut self, mode: InsertMode, token: Token) {
+    self.template_insert_modes.pop();
+    self.template_insert_modes.push(mode.clone());
+    self.switch_to(mode);
+    self.process(token);
+  }
+
+  fn handle_in_template_mode(&mut self, token: Token) {
+    match token {
+      Token::Text(_) | Token::Comment(_) | Token::DOCTYPE { .. } => {
+        return self.handle_in_body_mode(token);
+      }
+      _ => {}
+    }
+
+    if token.is_start_tag()
+      && token.match_tag_name_in(&[
+        "base", "basefont", "bgsound", "link", "meta", "noframes", "script",
+        "style", "template", "title",
+      ])
+    {
+      return self.handle_in_head_mode(token);
+    }
+
+    if token.is_end_tag() && token.tag_name() == "template" {
+      return self.handle_in_head_mode(token);
+    }
+
+    if token.is_start_tag()
+      && token
+        .match_tag_name_in(&["caption", "colgroup", "tbody", "tfoot", "thead"])
+    {
+      return self.switch_template_insert_mode(InsertMode::InTable, token);
+    }
+
+    if token.is_start_tag() && token.tag_name() == "col" {
+      return self
+        .switch_template_insert_mode(InsertMode::InColumnGroup, token);
+    }
+
+    if token.is_start_tag() && token.tag_name() == "tr" {
+      return self.switch_template_insert_mode(InsertMode::InTableBody, token);
+    }
+
+    if token.is_start_tag() && token.match_tag_name_in(&["td", "th"]) {
+      return self.switch_template_insert_mode(InsertMode::InRow, token);
+    }
+
+    if token.is_start_tag() {
+      return self.switch_template_insert_mode(InsertMode::InBody, token);
+    }
+
+    if token.is_end_tag() {
+      self.unexpected(&token);
+      return;
+    }
+
+    if token.is_eof() {
+      if !self.open_elements.contains("template") {
+        self.stop_parsing();
+        return;
+      }
+
+      self.unexpected(&token);
+      self.close_template();
+      self.process(token);
+    }
+  }
+
+  // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inforeign
+  fn handle_in_foreign_content(&mut self, mut token: Token) {
+    if let Token::Text(ref str) = token {
+      // CDATAセクションのテキストは、途中にU+0000を含むことがある
+      // テキストはトークンの範囲の末尾にそのまま現れるので、そこから各U+0000の位置を求める
+      let text_start = self.current_token_span.end.saturating_sub(str.len());
+      let mut null_count = 0;
+
+      for (index, _) in str.match_indices('\0') {
+        let offset = text_start + index;
+        self.parse_error_at(ParseError::UnexpectedNullCharacter, offset);
+        null_count += 1;
+      }
+
+      // U+0000とASCIIの空白以外の文字がある場合のみ
+      if str.bytes().any(|b| b != b'\0' && !b.is_ascii_whitespace()) {
+        self.frameset_ok = false;
+      }
+
+      if null_count > 0 {
+        self.insert_str(&str.replace("\0", "\u{FFFD}"));
+      } else {
+        self.insert_str(str);
+      }
+      return;
+    }
+
+    if let Token::Comment(text) = token {
+      self.insert_comment(text);
+      return;
+    }
+
+    if let Token::DOCTYPE { .. } = token {
+      self.parse_error(ParseError::UnexpectedDoctype);
+      return;
+    }
+
+    // HTMLの要素が現れたら、外部コンテンツを抜けてから処理し直す
+    if is_breakout_start_tag(&token)
+      || token.is_end_tag() && token.match_tag_name_in(&["br", "p"])
+    {
+      self.unexpected(&token);
+
+      while let Some(node) = self.open_elements.current_node() {
+        if node.is_html()
+          || is_mathml_text_integration_point(&node)
+          || is_html_integration_point(&self.sink, &node)
+        {
+          break;
+        }
+        self.open_elements.pop();
+      }
+
+      // 調整済み現在ノードによらず、現在の挿入モードの規則で処理する
+      return self.process_by_insert_mode(token);
+    }
+
+    if token.is_start_tag() {
+      let namespace = self.adjusted_current_node().unwrap().namespace();
+
+      match namespace {
+        Namespace::MathMl => adjust_mathml_attributes(&mut token),
+        Namespace::Svg => {
+          adjust_svg_tag_name(&mut token);
+          adjust_svg_attributes(&mut token);
+        }
+        _ => {}
+      }
+
+      let is_self_closing = token.is_self_closing();
+      self.acknowledge_self_closing(&mut token);
+      self.insert_foreign_element(token, namespace);
+
+      // スクリプトは実行しないので、SVGのscript要素も閉じるだけ
+      if is_self_closing {
+        self.open_elements.pop();
+      }
+      return;
+    }
+
+    if token.is_end_tag() {
+      let current_node = self.current_node();
+
+      if !current_node.local_name().eq_ignore_ascii_case(token.tag_name()) {
+        self.unexpected(&token);
+      }
+
+      for (index, node) in self.open_elements.iter().enumerate().rev() {
+        // HTMLの要素まで来たら、現在の挿入モードの規則で処理する
+        if node.is_html() {
+          return self.process_by_insert_mode(token);
+        }
+
+        if node.local_name().eq_ignore_ascii_case(token.tag_name()) {
+          self.open_elements.pop_before_index(index);
+          return;
+        }
+      }
+    }
   }
 
   fn handle_text_mode(&mut self, token: Token) {
@@ -2318,7 +3719,10 @@ impl<'a> TreeBuilder<'a> {
     }
 
     if token.is_end_tag() && token.tag_name() == "script" {
-      todo!("handle_text_mode: script end tag");
+      // スクリプトは実行しないので、要素を閉じるだけ
+      self.open_elements.pop();
+      self.switch_to(self.original_insert_mode.clone().unwrap());
+      return;
     }
 
     if token.is_end_tag() {
@@ -2327,3 +3731,15 @@ impl<'a> TreeBuilder<'a> {
     }
   }
 }
+
+// テキストを、先頭のASCII空白文字とそれ以降に分ける
+fn split_leading_whitespace(s: &str) -> (&str, &str) {
+  let rest = s.trim_start_matches(|c: char| c.is_ascii_whitespace());
+  s.split_at(s.len() - rest.len())
+}
+
+fn is_hidden_input(token: &Token) -> bool {
+  token.attributes().iter().any(|attribute| {
+    attribute.name == "type" && attribute.value.eq_ignore_ascii_case("hidden")
+  })
+}
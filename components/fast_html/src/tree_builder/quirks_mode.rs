@@ -0,0 +1,129 @@
+use fast_dom::document::QuirksMode;
+
+// 公開識別子がこれらのいずれかで始まる場合、quirksモードになる
+const QUIRKS_PUBLIC_ID_PREFIXES: [&str; 55] = [
+  "+//silmaril//dtd html pro v0r11 19970101//",
+  "-//as//dtd html 3.0 aswedit + extensions//",
+  "-//advasoft ltd//dtd html 3.0 aswedit + extensions//",
+  "-//ietf//dtd html 2.0 level 1//",
+  "-//ietf//dtd html 2.0 level 2//",
+  "-//ietf//dtd html 2.0 strict level 1//",
+  "-//ietf//dtd html 2.0 strict level 2//",
+  "-//ietf//dtd html 2.0 strict//",
+  "-//ietf//dtd html 2.0//",
+  "-//ietf//dtd html 2.1e//",
+  "-//ietf//dtd html 3.0//",
+  "-//ietf//dtd html 3.2 final//",
+  "-//ietf//dtd html 3.2//",
+  "-//ietf//dtd html 3//",
+  "-//ietf//dtd html level 0//",
+  "-//ietf//dtd html level 1//",
+  "-//ietf//dtd html level 2//",
+  "-//ietf//dtd html level 3//",
+  "-//ietf//dtd html strict level 0//",
+  "-//ietf//dtd html strict level 1//",
+  "-//ietf//dtd html strict level 2//",
+  "-//ietf//dtd html strict level 3//",
+  "-//ietf//dtd html strict//",
+  "-//ietf//dtd html//",
+  "-//metrius//dtd metrius presentational//",
+  "-//microsoft//dtd internet explorer 2.0 html strict//",
+  "-//microsoft//dtd internet explorer 2.0 html//",
+  "-//microsoft//dtd internet explorer 2.0 tables//",
+  "-//microsoft//dtd internet explorer 3.0 html strict//",
+  "-//microsoft//dtd internet explorer 3.0 html//",
+  "-//microsoft//dtd internet explorer 3.0 tables//",
+  "-//netscape comm. corp.//dtd html//",
+  "-//netscape comm. corp.//dtd strict html//",
+  "-//o'reilly and associates//dtd html 2.0//",
+  "-//o'reilly and associates//dtd html extended 1.0//",
+  "-//o'reilly and associates//dtd html extended relaxed 1.0//",
+  "-//sq//dtd html 2.0 hotmetal + extensions//",
+  "-//softquad software//dtd hotmetal pro 6.0::19990601::extensions to html 4.0//",
+  "-//softquad//dtd hotmetal pro 4.0::19971010::extensions to html 4.0//",
+  "-//spyglass//dtd html 2.0 extended//",
+  "-//sun microsystems corp.//dtd hotjava html//",
+  "-//sun microsystems corp.//dtd hotjava strict html//",
+  "-//w3c//dtd html 3 1995-03-24//",
+  "-//w3c//dtd html 3.2 draft//",
+  "-//w3c//dtd html 3.2 final//",
+  "-//w3c//dtd html 3.2//",
+  "-//w3c//dtd html 3.2s draft//",
+  "-//w3c//dtd html 4.0 frameset//",
+  "-//w3c//dtd html 4.0 transitional//",
+  "-//w3c//dtd html experimental 19960712//",
+  "-//w3c//dtd html experimental 970421//",
+  "-//w3c//dtd w3 html//",
+  "-//w3o//dtd w3 html 3.0//",
+  "-//webtechs//dtd mozilla html 2.0//",
+  "-//webtechs//dtd mozilla html//",
+];
+
+// 公開識別子がこれらのいずれかと一致する場合、quirksモードになる
+const QUIRKS_PUBLIC_IDS: [&str; 3] = [
+  "-//w3o//dtd w3 html strict 3.0//en//",
+  "-/w3c/dtd html 4.0 transitional/en",
+  "html",
+];
+
+const QUIRKS_SYSTEM_ID: &str =
+  "http://www.ibm.com/data/dtd/v11/ibmxhtml1-transitional.dtd";
+
+// システム識別子の有無によって、quirksモードかlimited-quirksモードかが変わる
+const HTML401_PUBLIC_ID_PREFIXES: [&str; 2] = [
+  "-//w3c//dtd html 4.01 frameset//",
+  "-//w3c//dtd html 4.01 transitional//",
+];
+
+// 公開識別子がこれらのいずれかで始まる場合、limited-quirksモードになる
+const LIMITED_QUIRKS_PUBLIC_ID_PREFIXES: [&str; 2] = [
+  "-//w3c//dtd xhtml 1.0 frameset//",
+  "-//w3c//dtd xhtml 1.0 transitional//",
+];
+
+fn starts_with_oneof(value: &str, prefixes: &[&str]) -> bool {
+  prefixes.iter().any(|prefix| value.starts_with(prefix))
+}
+
+// DOCTYPEトークンの内容から、文書のモードを決定する
+// 識別子はASCII大文字小文字を区別せずに比較する
+pub fn quirks_mode_of(
+  name: Option<&str>,
+  public_id: Option<&str>,
+  system_id: Option<&str>,
+  force_quirks: bool,
+) -> QuirksMode {
+  if force_quirks || name != Some("html") {
+    return QuirksMode::Quirks;
+  }
+
+  let public_id = public_id.map(|id| id.to_ascii_lowercase());
+  let system_id = system_id.map(|id| id.to_ascii_lowercase());
+
+  if let Some(system_id) = &system_id {
+    if system_id == QUIRKS_SYSTEM_ID {
+      return QuirksMode::Quirks;
+    }
+  }
+
+  if let Some(public_id) = &public_id {
+    if QUIRKS_PUBLIC_IDS.contains(&public_id.as_str())
+      || starts_with_oneof(public_id, &QUIRKS_PUBLIC_ID_PREFIXES)
+    {
+      return QuirksMode::Quirks;
+    }
+
+    if starts_with_oneof(public_id, &HTML401_PUBLIC_ID_PREFIXES) {
+      return match system_id {
+        Some(_) => QuirksMode::LimitedQuirks,
+        None => QuirksMode::Quirks,
+      };
+    }
+
+    if starts_with_oneof(public_id, &LIMITED_QUIRKS_PUBLIC_ID_PREFIXES) {
+      return QuirksMode::LimitedQuirks;
+    }
+  }
+
+  QuirksMode::NoQuirks
+}
@@ -1,45 +1,72 @@
-use std::{
-  ops::{Deref, DerefMut},
-  rc::Rc,
-};
+use std::ops::{Deref, DerefMut};
 
-use fast_dom::node::NodePtr;
+use fast_dom::namespace::Namespace;
+
+use crate::sink::NodeHandle;
 
 use ecow::EcoVec;
 
-const SCOPE_BASE_LIST: [&str; 9] = [
-  "applet", "caption", "html", "table", "td", "th", "marquee", "object",
-  "template",
+type ScopeList = EcoVec<(Namespace, &'static str)>;
+
+const SCOPE_BASE_LIST: [(Namespace, &str); 18] = [
+  (Namespace::Html, "applet"),
+  (Namespace::Html, "caption"),
+  (Namespace::Html, "html"),
+  (Namespace::Html, "table"),
+  (Namespace::Html, "td"),
+  (Namespace::Html, "th"),
+  (Namespace::Html, "marquee"),
+  (Namespace::Html, "object"),
+  (Namespace::Html, "template"),
+  (Namespace::MathMl, "mi"),
+  (Namespace::MathMl, "mo"),
+  (Namespace::MathMl, "mn"),
+  (Namespace::MathMl, "ms"),
+  (Namespace::MathMl, "mtext"),
+  (Namespace::MathMl, "annotation-xml"),
+  (Namespace::Svg, "foreignObject"),
+  (Namespace::Svg, "desc"),
+  (Namespace::Svg, "title"),
+];
+
+const TABLE_SCOPE_LIST: [(Namespace, &str); 3] = [
+  (Namespace::Html, "html"),
+  (Namespace::Html, "table"),
+  (Namespace::Html, "template"),
 ];
 
-#[derive(Debug)]
-pub struct StackOfOpenElements(pub EcoVec<NodePtr>);
+// 名前空間も含めて、scopeの境界になる要素かどうか
+fn is_scope_boundary<H: NodeHandle>(element: &H, list: &ScopeList) -> bool {
+  list.contains(&(element.namespace(), element.local_name()))
+}
+
+pub struct StackOfOpenElements<H>(pub EcoVec<H>);
 
-impl Deref for StackOfOpenElements {
-  type Target = EcoVec<NodePtr>;
+impl<H> Deref for StackOfOpenElements<H> {
+  type Target = EcoVec<H>;
   fn deref(&self) -> &Self::Target {
     &self.0
   }
 }
 
-impl DerefMut for StackOfOpenElements {
+impl<H> DerefMut for StackOfOpenElements<H> {
   fn deref_mut(&mut self) -> &mut Self::Target {
     &mut self.0
   }
 }
 
-impl StackOfOpenElements {
+impl<H: NodeHandle> StackOfOpenElements<H> {
   pub fn new() -> Self {
     Self(EcoVec::new())
   }
 
   /* getter ------------------------------------- */
 
-  pub fn get(&self, index: usize) -> NodePtr {
+  pub fn get(&self, index: usize) -> H {
     self.0[index].clone()
   }
 
-  pub fn current_node(&self) -> Option<NodePtr> {
+  pub fn current_node(&self) -> Option<H> {
     if let Some(node) = self.0.last() {
       return Some(node.clone());
     }
@@ -54,41 +81,37 @@ impl StackOfOpenElements {
 
   pub fn any<F>(&self, test: F) -> bool
   where
-    F: Fn(&NodePtr) -> bool,
+    F: Fn(&H) -> bool,
   {
     self.0.iter().any(test)
   }
 
   pub fn contains(&self, tag_name: &str) -> bool {
-    self.any(|node| node.as_element().tag_name() == tag_name)
-  }
-
-  pub fn contains_in(&self, tag_names: &[&str]) -> bool {
-    self.any(|node| tag_names.contains(&node.as_element().tag_name().as_str()))
+    self.any(|node| node.match_tag_name_in(&[tag_name]))
   }
 
   // tag_namesのいずれでもないnodeを持つ場合にtrueを返す
   pub fn contains_not_in(&self, tag_names: &[&str]) -> bool {
-    self.any(|node| !tag_names.contains(&node.as_element().tag_name().as_str()))
+    self.any(|node| !node.match_tag_name_in(tag_names))
   }
 
-  pub fn contains_node(&self, node: &NodePtr) -> bool {
-    self.any(|node_2| Rc::ptr_eq(node_2, node))
+  pub fn contains_node(&self, node: &H) -> bool {
+    self.any(|node_2| node_2.same_node(node))
   }
 
   /* scope -------------------------------------- */
 
   pub fn has_element_in_specific_scope(
     &self,
-    target_node: &NodePtr,
-    list: EcoVec<&str>,
+    target_node: &H,
+    list: ScopeList,
   ) -> bool {
     for node in self.0.iter().rev() {
-      if Rc::ptr_eq(node, target_node) {
+      if node.same_node(target_node) {
         return true;
       }
 
-      if list.contains(&node.as_element().tag_name().as_str()) {
+      if is_scope_boundary(node, &list) {
         return false;
       }
     }
@@ -99,16 +122,14 @@ impl StackOfOpenElements {
   pub fn has_element_name_in_specific_scope(
     &self,
     tag_name: &str,
-    list: EcoVec<&str>,
+    list: ScopeList,
   ) -> bool {
-    for node in self.0.iter().rev() {
-      let element = node.as_element();
-
-      if element.tag_name() == tag_name {
+    for element in self.0.iter().rev() {
+      if element.is_html() && element.local_name() == tag_name {
         return true;
       }
 
-      if list.contains(&element.tag_name().as_str()) {
+      if is_scope_boundary(element, &list) {
         return false;
       }
     }
@@ -119,16 +140,14 @@ impl StackOfOpenElements {
   pub fn has_oneof_element_names_in_specific_scope(
     &self,
     tag_names: &[&str],
-    list: EcoVec<&str>,
+    list: ScopeList,
   ) -> bool {
-    for node in self.0.iter().rev() {
-      let element = node.as_element();
-
-      if tag_names.contains(&element.tag_name().as_str()) {
+    for element in self.0.iter().rev() {
+      if element.is_html() && tag_names.contains(&element.local_name()) {
         return true;
       }
 
-      if list.contains(&element.tag_name().as_str()) {
+      if is_scope_boundary(element, &list) {
         return false;
       }
     }
@@ -136,7 +155,7 @@ impl StackOfOpenElements {
     false
   }
 
-  pub fn has_element_in_scope(&self, target_node: &NodePtr) -> bool {
+  pub fn has_element_in_scope(&self, target_node: &H) -> bool {
     self
       .has_element_in_specific_scope(target_node, EcoVec::from(SCOPE_BASE_LIST))
   }
@@ -161,34 +180,48 @@ impl StackOfOpenElements {
 
   pub fn has_element_name_in_button_scope(&self, tag_name: &str) -> bool {
     let mut list = EcoVec::from(SCOPE_BASE_LIST);
-    list.push("button");
+    list.push((Namespace::Html, "button"));
     self.has_element_name_in_specific_scope(tag_name, list)
   }
 
   pub fn has_element_name_in_list_item_scope(&self, tag_name: &str) -> bool {
     let mut list = EcoVec::from(SCOPE_BASE_LIST);
-    list.push("ol");
-    list.push("ul");
+    list.push((Namespace::Html, "ol"));
+    list.push((Namespace::Html, "ul"));
     self.has_element_name_in_specific_scope(tag_name, list)
   }
 
+  // table scopeは、ほかのscopeと違って基本のリストを含まない
   pub fn has_element_name_in_table_scope(&self, tag_name: &str) -> bool {
-    let mut list = EcoVec::from(SCOPE_BASE_LIST);
-    list.push("html");
-    list.push("table");
-    list.push("template");
-    self.has_element_name_in_specific_scope(tag_name, list)
+    self.has_element_name_in_specific_scope(
+      tag_name,
+      EcoVec::from(TABLE_SCOPE_LIST),
+    )
   }
 
   pub fn has_oneof_element_names_in_table_scope(
     &self,
     tag_names: &[&str],
   ) -> bool {
-    let mut list = EcoVec::from(SCOPE_BASE_LIST);
-    list.push("html");
-    list.push("table");
-    list.push("template");
-    self.has_oneof_element_names_in_specific_scope(tag_names, list)
+    self.has_oneof_element_names_in_specific_scope(
+      tag_names,
+      EcoVec::from(TABLE_SCOPE_LIST),
+    )
+  }
+
+  // select scopeは、optgroupとoption以外のすべての要素で区切られる
+  pub fn has_element_name_in_select_scope(&self, tag_name: &str) -> bool {
+    for element in self.0.iter().rev() {
+      if element.match_tag_name_in(&[tag_name]) {
+        return true;
+      }
+
+      if !element.match_tag_name_in(&["optgroup", "option"]) {
+        return false;
+      }
+    }
+
+    false
   }
 
   /* pop ---------------------------------------- */
@@ -196,7 +229,7 @@ impl StackOfOpenElements {
   // tag_nameがpopされるまでpopする
   pub fn pop_until(&mut self, tag_name: &str) {
     while let Some(node) = self.current_node() {
-      if node.as_element().tag_name() == tag_name {
+      if node.match_tag_name_in(&[tag_name]) {
         self.0.pop();
         break;
       }
@@ -214,7 +247,7 @@ impl StackOfOpenElements {
   // tag_namesのいずれかがpopされるまでpopする
   pub fn pop_until_some_in(&mut self, tag_names: &[&str]) {
     while let Some(node) = self.current_node() {
-      if tag_names.contains(&node.as_element().tag_name().as_str()) {
+      if node.match_tag_name_in(tag_names) {
         self.0.pop();
         break;
       }
@@ -224,7 +257,7 @@ impl StackOfOpenElements {
 
   pub fn pop_while_not_in(&mut self, tag_names: &[&str]) {
     while let Some(node) = self.current_node() {
-      if tag_names.contains(&node.as_element().tag_name().as_str()) {
+      if node.match_tag_name_in(tag_names) {
         break;
       }
       self.0.pop();
@@ -249,13 +282,10 @@ impl StackOfOpenElements {
 
   pub fn remove_first_matching_node<F>(&mut self, test: F)
   where
-    F: Fn(&NodePtr) -> bool,
+    F: Fn(&H) -> bool,
   {
-    for (i, node) in self.0.iter().rev().enumerate() {
-      if test(node) {
-        self.0.remove(i);
-        return;
-      }
+    if let Some(index) = self.0.iter().rposition(test) {
+      self.0.remove(index);
     }
   }
 }
@@ -0,0 +1,112 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+use fast_html::tokenizer::token::Token;
+use fast_html::tokenizer::Tokenizer;
+
+use assert_json_diff::*;
+use serde_json::json;
+
+fn tokenize(html: &str, allow_cdata: bool) -> Vec<Token> {
+  let mut tokenizer = Tokenizer::new(html.as_bytes());
+  tokenizer.set_allow_cdata(allow_cdata);
+
+  let mut tokens = Vec::new();
+
+  loop {
+    let token = tokenizer.next_token();
+    if let Token::EOF = token {
+      break;
+    }
+    tokens.push(token);
+  }
+
+  tokens
+}
+
+// 連続するテキストトークンを連結する
+fn concat_text(tokens: &[Token]) -> String {
+  tokens
+    .iter()
+    .map(|token| match token {
+      Token::Text(s) => s.to_string(),
+      token => panic!("Unexpected token: {:?}", token),
+    })
+    .collect()
+}
+
+#[test]
+fn cdata_section_in_foreign_content() {
+  let tokens = tokenize("<![CDATA[x < y && <b>]]>", true);
+  assert_eq!(concat_text(&tokens), "x < y && <b>");
+}
+
+#[test]
+fn cdata_section_with_brackets() {
+  let tokens = tokenize("<![CDATA[a]b]]c]]]>", true);
+  assert_eq!(concat_text(&tokens), "a]b]]c]");
+}
+
+#[test]
+fn eof_in_cdata() {
+  let tokens = tokenize("<![CDATA[abc]", true);
+  assert_eq!(concat_text(&tokens), "abc]");
+}
+
+#[test]
+fn cdata_in_html_content() {
+  let tokens = tokenize("<![CDATA[abc]]>", false);
+  assert_eq!(tokens, vec![Token::Comment("[CDATA[abc]]".into())]);
+}
+
+#[test]
+fn cdata_in_html_document() {
+  let html = r#"<p><![CDATA[text]]></p>"#;
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "type": "comment",
+              "value": "[CDATA[text]]"
+            }
+          ],
+          "tag": "p",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  assert_json_eq!(actual, expected);
+}
+
+#[test]
+fn null_in_cdata_section() {
+  let html = "<svg><![CDATA[\0a\0]]></svg>";
+
+  let expected = "| <html>
+|   <head>
+|   <body>
+|     <svg svg>
+|       \"\u{FFFD}a\u{FFFD}\"
+";
+
+  let (document, errors) = get_document_from_html(html);
+
+  assert_eq!(dom_to_html5lib_tree(&document), expected);
+  // U+0000ごとに、それぞれの位置でエラーになる
+  let offsets = errors
+    .iter()
+    .filter(|error| error.code() == "unexpected-null-character")
+    .map(|error| error.location().offset)
+    .collect::<Vec<_>>();
+  assert_eq!(offsets, vec![14, 16]);
+}
@@ -0,0 +1,109 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+use fast_html::tokenizer::token::Token;
+use fast_html::tokenizer::Tokenizer;
+
+use assert_json_diff::*;
+use serde_json::json;
+
+// EOFまでのテキストトークンを連結する
+fn tokenize_text(html: &str) -> String {
+  let mut tokenizer = Tokenizer::new(html.as_bytes());
+  let mut text = String::new();
+
+  loop {
+    match tokenizer.next_token() {
+      Token::Text(s) => text.push_str(&s),
+      Token::EOF => break,
+      token => panic!("Unexpected token: {:?}", token),
+    }
+  }
+
+  text
+}
+
+#[test]
+fn named_character_reference() {
+  assert_eq!(tokenize_text("&lt;h1&gt;"), "<h1>");
+  assert_eq!(tokenize_text("&copy; 2024"), "© 2024");
+  assert_eq!(tokenize_text("&NotEqualTilde;"), "\u{2242}\u{338}");
+}
+
+#[test]
+fn named_character_reference_without_semicolon() {
+  assert_eq!(tokenize_text("&copy2024"), "©2024");
+  assert_eq!(tokenize_text("&notit;"), "¬it;");
+}
+
+#[test]
+fn decimal_character_reference() {
+  assert_eq!(tokenize_text("&#169;"), "©");
+  assert_eq!(tokenize_text("&#0000065;&#66"), "AB");
+}
+
+#[test]
+fn hexadecimal_character_reference() {
+  assert_eq!(tokenize_text("&#x1F600;"), "😀");
+  assert_eq!(tokenize_text("&#XA9;"), "©");
+  assert_eq!(tokenize_text("&#x41 tail"), "A tail");
+}
+
+#[test]
+fn numeric_character_reference_replacement() {
+  assert_eq!(tokenize_text("&#0;"), "\u{FFFD}");
+  assert_eq!(tokenize_text("&#x110000;"), "\u{FFFD}");
+  assert_eq!(tokenize_text("&#99999999999999999999;"), "\u{FFFD}");
+  assert_eq!(tokenize_text("&#xD800;"), "\u{FFFD}");
+  assert_eq!(tokenize_text("&#x80;&#x99;"), "€™");
+  assert_eq!(tokenize_text("&#x81;"), "\u{81}");
+  assert_eq!(tokenize_text("&#xFFFF;"), "\u{FFFF}");
+}
+
+#[test]
+fn absence_of_digits_in_numeric_character_reference() {
+  assert_eq!(tokenize_text("&#;"), "&#;");
+  assert_eq!(tokenize_text("&#x;"), "&#x;");
+  assert_eq!(tokenize_text("&#"), "&#");
+}
+
+#[test]
+fn ambiguous_ampersand() {
+  assert_eq!(tokenize_text("&foo;bar"), "&foo;bar");
+  assert_eq!(tokenize_text("a && b"), "a && b");
+  assert_eq!(tokenize_text("trailing &"), "trailing &");
+}
+
+#[test]
+fn character_reference_in_attribute() {
+  let html = r#"<a title="a &amp; b" href="?x=1&amp;y=2&copy=3" data-n='&#x41;'>&#169;</a>"#;
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "attributes": {
+            "title": "a & b",
+            "href": "?x=1&y=2&copy=3",
+            "data-n": "A"
+          },
+          "children": [
+            {
+              "type": "text",
+              "value": "©"
+            }
+          ],
+          "tag": "a",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  assert_json_eq!(actual, expected);
+}
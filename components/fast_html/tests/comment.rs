@@ -0,0 +1,153 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+use fast_html::tokenizer::token::Token;
+use fast_html::tokenizer::Tokenizer;
+
+use assert_json_diff::*;
+use serde_json::json;
+
+fn tokenize(html: &str) -> Vec<Token> {
+  let mut tokenizer = Tokenizer::new(html.as_bytes());
+  let mut tokens = Vec::new();
+
+  loop {
+    let token = tokenizer.next_token();
+    if let Token::EOF = token {
+      break;
+    }
+    tokens.push(token);
+  }
+
+  tokens
+}
+
+fn tokenize_comment(html: &str) -> String {
+  match tokenize(html).as_slice() {
+    [Token::Comment(text)] => text.to_string(),
+    tokens => panic!("Expected a single comment: {:?}", tokens),
+  }
+}
+
+#[test]
+fn simple_comment() {
+  assert_eq!(tokenize_comment("<!-- comment -->"), " comment ");
+  assert_eq!(tokenize_comment("<!---->"), "");
+  assert_eq!(tokenize_comment("<!--a-b--c-->"), "a-b--c");
+  assert_eq!(tokenize_comment("<!--a--->"), "a-");
+}
+
+#[test]
+fn abruptly_closed_comment() {
+  assert_eq!(tokenize_comment("<!-->"), "");
+  assert_eq!(tokenize_comment("<!--->"), "");
+}
+
+#[test]
+fn incorrectly_closed_comment() {
+  assert_eq!(tokenize_comment("<!-- comment --!>"), " comment ");
+  assert_eq!(tokenize_comment("<!--a--!-->"), "a--!");
+  assert_eq!(tokenize_comment("<!--a--!b-->"), "a--!b");
+}
+
+#[test]
+fn nested_comment() {
+  assert_eq!(tokenize_comment("<!-- <!-- nested -->"), " <!-- nested ");
+  assert_eq!(tokenize_comment("<!--<!---->"), "<!--");
+  assert_eq!(tokenize_comment("<!--<<!-- x -->"), "<<!-- x ");
+  assert_eq!(tokenize_comment("<!--<!-x-->"), "<!-x");
+}
+
+#[test]
+fn eof_in_comment() {
+  assert_eq!(tokenize_comment("<!--"), "");
+  assert_eq!(tokenize_comment("<!---"), "");
+  assert_eq!(tokenize_comment("<!-- a"), " a");
+  assert_eq!(tokenize_comment("<!-- a -"), " a ");
+  assert_eq!(tokenize_comment("<!-- a --"), " a ");
+  assert_eq!(tokenize_comment("<!-- a --!"), " a ");
+  assert_eq!(tokenize_comment("<!-- <"), " <");
+}
+
+#[test]
+fn comment_at_document_level() {
+  let html = r#"<!-- before doctype -->
+<!DOCTYPE html>
+<!-- before html -->
+<html><body></body></html>
+<!-- after html -->"#;
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "type": "comment",
+          "value": " before doctype "
+        },
+        {
+          "type": "comment",
+          "value": " before html "
+        },
+        {
+          "children": [
+            {
+              "tag": "head",
+              "type": "element"
+            },
+            {
+              "tag": "body",
+              "type": "element"
+            }
+          ],
+          "tag": "html",
+          "type": "element"
+        },
+        {
+          "type": "comment",
+          "value": " after html "
+        }
+      ],
+      "type": "document"
+    }
+  );
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_to_json(&document);
+
+  assert_json_eq!(actual, expected);
+}
+
+#[test]
+fn comment_after_html_end_tag() {
+  // </body>がなくても、</html>の後のコメントは文書の最後の子になる
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "tag": "head",
+              "type": "element"
+            },
+            {
+              "tag": "body",
+              "type": "element"
+            }
+          ],
+          "tag": "html",
+          "type": "element"
+        },
+        {
+          "type": "comment",
+          "value": " foo "
+        }
+      ],
+      "type": "document"
+    }
+  );
+
+  let (document, _) = get_document_from_html("<html></html><!-- foo -->");
+  let actual = dom_to_json(&document);
+
+  assert_json_eq!(actual, expected);
+}
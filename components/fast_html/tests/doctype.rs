@@ -1,10 +1,18 @@
 extern crate fast_html;
 
+use fast_dom::document::QuirksMode;
+use fast_html::debugger::*;
 use fast_html::tokenizer::token::Token;
 use fast_html::tokenizer::Tokenizer;
 
 use ecow::EcoString;
 
+fn document_mode_of(html: &str) -> QuirksMode {
+  let (document, _) = get_document_from_html(html);
+  let mode = document.as_maybe_document().unwrap().mode();
+  mode
+}
+
 #[test]
 fn html5_doctype() {
   let html = r#"<!DOCTYPE html>"#;
@@ -14,8 +22,127 @@ fn html5_doctype() {
 
   let excepted = Token::DOCTYPE {
     name: Some(EcoString::from("html")),
+    public_identifier: None,
+    system_identifier: None,
+    force_quirks: false,
+  };
+
+  assert_eq!(actual, excepted);
+}
+
+#[test]
+fn html401_strict_doctype() {
+  let html = r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 4.01//EN" 'http://www.w3.org/TR/html4/strict.dtd'>"#;
+
+  let mut tokenizer = Tokenizer::new(html.as_bytes());
+  let actual = tokenizer.next_token();
+
+  let excepted = Token::DOCTYPE {
+    name: Some(EcoString::from("html")),
+    public_identifier: Some(EcoString::from("-//W3C//DTD HTML 4.01//EN")),
+    system_identifier: Some(EcoString::from(
+      "http://www.w3.org/TR/html4/strict.dtd",
+    )),
     force_quirks: false,
   };
 
   assert_eq!(actual, excepted);
 }
+
+#[test]
+fn system_only_doctype() {
+  let html = r#"<!doctype html SYSTEM "about:legacy-compat">"#;
+
+  let mut tokenizer = Tokenizer::new(html.as_bytes());
+  let actual = tokenizer.next_token();
+
+  let excepted = Token::DOCTYPE {
+    name: Some(EcoString::from("html")),
+    public_identifier: None,
+    system_identifier: Some(EcoString::from("about:legacy-compat")),
+    force_quirks: false,
+  };
+
+  assert_eq!(actual, excepted);
+}
+
+#[test]
+fn bogus_doctype() {
+  let html = r#"<!DOCTYPE html FOO "bar"><p>"#;
+
+  let mut tokenizer = Tokenizer::new(html.as_bytes());
+  let actual = tokenizer.next_token();
+
+  let excepted = Token::DOCTYPE {
+    name: Some(EcoString::from("html")),
+    public_identifier: None,
+    system_identifier: None,
+    force_quirks: true,
+  };
+
+  assert_eq!(actual, excepted);
+  assert_eq!(tokenizer.next_token(), Token::new_start_tag_of("p"));
+}
+
+#[test]
+fn eof_in_doctype_public_identifier() {
+  let html = r#"<!DOCTYPE html PUBLIC "-//W3C"#;
+
+  let mut tokenizer = Tokenizer::new(html.as_bytes());
+  let actual = tokenizer.next_token();
+
+  let excepted = Token::DOCTYPE {
+    name: Some(EcoString::from("html")),
+    public_identifier: Some(EcoString::from("-//W3C")),
+    system_identifier: None,
+    force_quirks: true,
+  };
+
+  assert_eq!(actual, excepted);
+  assert_eq!(tokenizer.next_token(), Token::EOF);
+}
+
+#[test]
+fn no_quirks_mode() {
+  assert_eq!(document_mode_of("<!DOCTYPE html><p>"), QuirksMode::NoQuirks);
+  assert_eq!(
+    document_mode_of(
+      r#"<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01//EN" "http://www.w3.org/TR/html4/strict.dtd">"#
+    ),
+    QuirksMode::NoQuirks
+  );
+}
+
+#[test]
+fn quirks_mode() {
+  assert_eq!(document_mode_of("<p>no doctype"), QuirksMode::Quirks);
+  assert_eq!(document_mode_of("<!DOCTYPE foo>"), QuirksMode::Quirks);
+  assert_eq!(
+    document_mode_of(
+      r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 3.2 Final//EN">"#
+    ),
+    QuirksMode::Quirks
+  );
+  assert_eq!(
+    document_mode_of(
+      r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 4.01 Transitional//EN">"#
+    ),
+    QuirksMode::Quirks
+  );
+}
+
+#[test]
+fn limited_quirks_mode() {
+  assert_eq!(
+    document_mode_of(
+      r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Transitional//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd">"#
+    ),
+    QuirksMode::LimitedQuirks
+  );
+  assert_eq!(
+    document_mode_of(
+      r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 4.01 Transitional//EN" "http://www.w3.org/TR/html4/loose.dtd">"#
+    ),
+    QuirksMode::LimitedQuirks
+  );
+}
@@ -59,7 +59,7 @@ fn document_missing_head() {
     }
   );
 
-  let document = get_document_from_html(html);
+  let (document, _) = get_document_from_html(html);
   let actual = dom_to_json(&document);
 
   assert_json_eq!(actual, expected);
@@ -0,0 +1,171 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+use fast_html::encoding::*;
+
+use encoding_rs::{EUC_JP, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+use assert_json_diff::*;
+use serde_json::json;
+
+fn encode(html: &str, encoding: &'static encoding_rs::Encoding) -> Vec<u8> {
+  encoding.encode(html).0.into_owned()
+}
+
+fn encode_utf16(html: &str, little_endian: bool) -> Vec<u8> {
+  let mut bytes = if little_endian {
+    vec![0xFF, 0xFE]
+  } else {
+    vec![0xFE, 0xFF]
+  };
+
+  for unit in html.encode_utf16() {
+    if little_endian {
+      bytes.extend(unit.to_le_bytes());
+    } else {
+      bytes.extend(unit.to_be_bytes());
+    }
+  }
+
+  bytes
+}
+
+#[test]
+fn bom() {
+  let bytes = [b"\xEF\xBB\xBF".as_slice(), "<p>あ</p>".as_bytes()].concat();
+  let decoded = decode(&bytes, Some("shift_jis"));
+  assert_eq!(decoded.encoding, UTF_8);
+  assert_eq!(decoded.confidence, Confidence::Certain);
+  assert_eq!(decoded.text, "<p>あ</p>");
+
+  let bytes = encode_utf16("<p>あ</p>", true);
+  let decoded = decode(&bytes, None);
+  assert_eq!(decoded.encoding, UTF_16LE);
+  assert_eq!(decoded.text, "<p>あ</p>");
+
+  let bytes = encode_utf16("<p>あ</p>", false);
+  let decoded = decode(&bytes, None);
+  assert_eq!(decoded.encoding, UTF_16BE);
+  assert_eq!(decoded.text, "<p>あ</p>");
+}
+
+#[test]
+fn transport_layer_encoding() {
+  let html = r#"<meta charset="euc-jp"><p>日本語</p>"#;
+  let bytes = encode(html, SHIFT_JIS);
+  let decoded = decode(&bytes, Some("Shift_JIS"));
+
+  assert_eq!(decoded.encoding, SHIFT_JIS);
+  assert_eq!(decoded.confidence, Confidence::Certain);
+  assert_eq!(decoded.text, html);
+}
+
+#[test]
+fn meta_charset() {
+  let html = r#"<!DOCTYPE html>
+<!-- <meta charset="windows-1252"> -->
+<html><head><META CHARSET=Shift_JIS></head><body>日本語</body></html>"#;
+  let bytes = encode(html, SHIFT_JIS);
+  let decoded = decode(&bytes, None);
+
+  assert_eq!(decoded.encoding, SHIFT_JIS);
+  assert_eq!(decoded.confidence, Confidence::Tentative);
+  assert_eq!(decoded.text, html);
+}
+
+#[test]
+fn meta_http_equiv() {
+  let html = r#"<head><title>a</title><meta http-equiv="Content-Type" content="text/html; charset='euc-jp'"></head>日本語"#;
+  let bytes = encode(html, EUC_JP);
+  let decoded = decode(&bytes, None);
+  assert_eq!(decoded.encoding, EUC_JP);
+  assert_eq!(decoded.text, html);
+
+  // http-equivがない場合、contentは無視される
+  let html = r#"<meta content="text/html; charset=euc-jp">"#;
+  assert_eq!(decode(html.as_bytes(), None).encoding, UTF_8);
+}
+
+#[test]
+fn meta_content_before_charset() {
+  let html = r#"<meta http-equiv="content-type" content="text/html; charset=shift_jis" charset="utf-8"><p>日本語</p>"#;
+  let bytes = encode(html, SHIFT_JIS);
+  let decoded = decode(&bytes, None);
+
+  assert_eq!(decoded.encoding, SHIFT_JIS);
+  assert_eq!(decoded.text, html);
+}
+
+#[test]
+fn meta_utf16_is_utf8() {
+  let html = "<meta charset=utf-16le><p>あ</p>";
+  let decoded = decode(html.as_bytes(), None);
+
+  assert_eq!(decoded.encoding, UTF_8);
+  assert_eq!(decoded.text, html);
+}
+
+#[test]
+fn meta_after_prescan_limit() {
+  let html = format!("<p>{}</p><meta charset=euc-jp>", "a".repeat(1024));
+  assert_eq!(decode(html.as_bytes(), None).encoding, UTF_8);
+}
+
+#[test]
+fn fallback() {
+  let decoded = decode("<p>日本語</p>".as_bytes(), None);
+  assert_eq!(decoded.encoding, UTF_8);
+
+  let decoded = decode(b"<p>caf\xE9</p>", None);
+  assert_eq!(decoded.encoding, WINDOWS_1252);
+  assert_eq!(decoded.text, "<p>café</p>");
+}
+
+// 指定がなくても、日本語のエンコーディングは推定できる
+#[test]
+fn detect_japanese() {
+  let html = "<p>こんにちは、世界。カタカナ</p>";
+
+  let bytes = encode(html, SHIFT_JIS);
+  let decoded = decode(&bytes, None);
+  assert_eq!(decoded.encoding, SHIFT_JIS);
+  assert_eq!(decoded.confidence, Confidence::Tentative);
+  assert_eq!(decoded.text, html);
+
+  let bytes = encode(html, EUC_JP);
+  let decoded = decode(&bytes, None);
+  assert_eq!(decoded.encoding, EUC_JP);
+  assert_eq!(decoded.text, html);
+
+  // Shift_JISとして読めても、かなを含まなければwindows-1252にする
+  let decoded = decode(b"<p>caf\xE9s</p>", None);
+  assert_eq!(decoded.encoding, WINDOWS_1252);
+  assert_eq!(decoded.text, "<p>cafés</p>");
+}
+
+#[test]
+fn document_from_bytes() {
+  let html = "<meta charset=shift_jis><p>こんにちは&amp;世界</p>";
+  let (document, _) = get_document_from_bytes(&encode(html, SHIFT_JIS), None);
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "type": "text",
+              "value": "こんにちは&世界"
+            }
+          ],
+          "tag": "p",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  assert_json_eq!(dom_body_to_json(&document), expected);
+}
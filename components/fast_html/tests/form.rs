@@ -0,0 +1,88 @@
+extern crate fast_html;
+
+use std::rc::Rc;
+
+use fast_dom::node::NodePtr;
+use fast_html::debugger::*;
+
+fn find_elements(node: &NodePtr, tag_name: &str) -> Vec<NodePtr> {
+  let mut elements = Vec::new();
+
+  for child in node.iterate_children() {
+    let child = NodePtr(child);
+    if child
+      .as_maybe_element()
+      .is_some_and(|element| element.tag_name() == tag_name)
+    {
+      elements.push(child.clone());
+    }
+    elements.extend(find_elements(&child, tag_name));
+  }
+
+  elements
+}
+
+fn names(elements: &[NodePtr]) -> Vec<String> {
+  elements
+    .iter()
+    .map(|element| {
+      element.as_element().get_attribute("name").unwrap_or_default().to_string()
+    })
+    .collect()
+}
+
+#[test]
+fn form_elements() {
+  let html = r#"<form><input name=a><img><fieldset name=b><select name=c></select></fieldset><input type=image name=d><output name=e></output></form>"#;
+
+  let (document, _) = get_document_from_html(html);
+  let form = &find_elements(&document, "form")[0];
+
+  assert_eq!(names(&form.form_elements()), vec!["a", "b", "c", "e"]);
+}
+
+// form要素の外に出ても、form要素ポインタが残っている間は関連付けられる
+#[test]
+fn form_elements_outside_of_form() {
+  let html = r#"<div><form></div><input name=a><button name=b></button>"#;
+
+  let (document, _) = get_document_from_html(html);
+  let form = &find_elements(&document, "form")[0];
+
+  assert_eq!(names(&form.form_elements()), vec!["a", "b"]);
+  assert!(form.iterate_children().next().is_none());
+}
+
+#[test]
+fn form_elements_in_table() {
+  let html = r#"<table><form><tr><td><input name=a></td></tr></table>"#;
+
+  let (document, _) = get_document_from_html(html);
+  let form = &find_elements(&document, "form")[0];
+
+  assert_eq!(names(&form.form_elements()), vec!["a"]);
+}
+
+#[test]
+fn form_attribute() {
+  let html = r#"<form id=f1><input name=a><input name=b form=f2></form><form id=f2></form><input name=c form=f1>"#;
+
+  let (document, _) = get_document_from_html(html);
+  let forms = find_elements(&document, "form");
+
+  assert_eq!(names(&forms[0].form_elements()), vec!["a", "c"]);
+  assert_eq!(names(&forms[1].form_elements()), vec!["b"]);
+}
+
+#[test]
+fn form_owner() {
+  let html = r#"<form><input><p>a</p></form><input>"#;
+
+  let (document, _) = get_document_from_html(html);
+  let form = &find_elements(&document, "form")[0];
+  let inputs = find_elements(&document, "input");
+
+  assert!(inputs[0].form_owner().is_some_and(|owner| Rc::ptr_eq(&owner, form)));
+  assert!(inputs[1].form_owner().is_none());
+  assert!(find_elements(&document, "p")[0].form_owner().is_none());
+}
@@ -0,0 +1,198 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+
+use assert_json_diff::*;
+use serde_json::json;
+
+#[test]
+fn text_in_table() {
+  let html = r#"a<table>b<tr><td>cell</td></tr></table>"#;
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "type": "text",
+          "value": "ab"
+        },
+        {
+          "children": [
+            {
+              "children": [
+                {
+                  "children": [
+                    {
+                      "children": [
+                        {
+                          "type": "text",
+                          "value": "cell"
+                        }
+                      ],
+                      "tag": "td",
+                      "type": "element"
+                    }
+                  ],
+                  "tag": "tr",
+                  "type": "element"
+                }
+              ],
+              "tag": "tbody",
+              "type": "element"
+            }
+          ],
+          "tag": "table",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  assert_json_eq!(actual, expected);
+}
+
+#[test]
+fn element_in_table() {
+  let html = r#"<div><table><b>bold</b><tr><td>cell</td></tr></table></div>"#;
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "children": [
+                {
+                  "type": "text",
+                  "value": "bold"
+                }
+              ],
+              "tag": "b",
+              "type": "element"
+            },
+            {
+              "children": [
+                {
+                  "children": [
+                    {
+                      "children": [
+                        {
+                          "children": [
+                            {
+                              "type": "text",
+                              "value": "cell"
+                            }
+                          ],
+                          "tag": "td",
+                          "type": "element"
+                        }
+                      ],
+                      "tag": "tr",
+                      "type": "element"
+                    }
+                  ],
+                  "tag": "tbody",
+                  "type": "element"
+                }
+              ],
+              "tag": "table",
+              "type": "element"
+            }
+          ],
+          "tag": "div",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  assert_json_eq!(actual, expected);
+}
+
+#[test]
+fn unclosed_element_in_table() {
+  // <tr>でtableの文脈まで戻るので、b要素は閉じられる
+  let html = r#"<table><b>x<tr><td>y</td></tr></table>"#;
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "type": "text",
+              "value": "x"
+            }
+          ],
+          "tag": "b",
+          "type": "element"
+        },
+        {
+          "children": [
+            {
+              "children": [
+                {
+                  "children": [
+                    {
+                      "children": [
+                        {
+                          "type": "text",
+                          "value": "y"
+                        }
+                      ],
+                      "tag": "td",
+                      "type": "element"
+                    }
+                  ],
+                  "tag": "tr",
+                  "type": "element"
+                }
+              ],
+              "tag": "tbody",
+              "type": "element"
+            }
+          ],
+          "tag": "table",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  assert_json_eq!(actual, expected);
+}
+
+#[test]
+fn whitespace_in_table() {
+  // ASCII空白だけならtableの中に残り、U+00A0は里親に出される
+  let html = "<table>\n<tr>&nbsp;<td>c</td></tr></table>";
+
+  let (document, _) = get_document_from_html(html);
+
+  let html_element = document.first_child().unwrap();
+  let body = html_element.last_child().unwrap();
+
+  let nbsp = body.first_child().unwrap();
+  assert_eq!(nbsp.as_text().get_data(), "\u{A0}");
+
+  let table = nbsp.next_sibling().unwrap();
+  assert_eq!(table.as_element().tag_name(), "table");
+
+  let newline = table.first_child().unwrap();
+  assert_eq!(newline.as_text().get_data(), "\n");
+}
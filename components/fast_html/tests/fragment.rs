@@ -0,0 +1,218 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+use fast_html::fragment::parse_fragment;
+
+use fast_dom::namespace::Namespace;
+use fast_dom::node::NodePtr;
+use fast_dom::tree::WeakTreeNode;
+
+use assert_json_diff::*;
+use serde_json::json;
+
+fn context(tag_name: &str, namespace: Namespace) -> NodePtr {
+  let document = fast_dom::create_document();
+  fast_dom::create_element_ns(
+    WeakTreeNode::from(&document.0),
+    tag_name,
+    namespace,
+  )
+}
+
+#[test]
+fn body_content() {
+  let (fragment, _) =
+    parse_fragment(&context("div", Namespace::Html), "<p>a<b>b</p>c");
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "type": "text",
+              "value": "a"
+            },
+            {
+              "children": [
+                {
+                  "type": "text",
+                  "value": "b"
+                }
+              ],
+              "tag": "b",
+              "type": "element"
+            }
+          ],
+          "tag": "p",
+          "type": "element"
+        },
+        {
+          "children": [
+            {
+              "type": "text",
+              "value": "c"
+            }
+          ],
+          "tag": "b",
+          "type": "element"
+        }
+      ],
+      "type": "document-fragment"
+    }
+  );
+
+  assert_json_eq!(dom_to_json(&fragment), expected);
+}
+
+#[test]
+fn rcdata_context() {
+  let (fragment, _) =
+    parse_fragment(&context("textarea", Namespace::Html), "<b>x</b>");
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "type": "text",
+          "value": "<b>x</b>"
+        }
+      ],
+      "type": "document-fragment"
+    }
+  );
+
+  assert_json_eq!(dom_to_json(&fragment), expected);
+}
+
+#[test]
+fn table_row_context() {
+  let (fragment, _) =
+    parse_fragment(&context("tr", Namespace::Html), "<td>1<td>2");
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "type": "text",
+              "value": "1"
+            }
+          ],
+          "tag": "td",
+          "type": "element"
+        },
+        {
+          "children": [
+            {
+              "type": "text",
+              "value": "2"
+            }
+          ],
+          "tag": "td",
+          "type": "element"
+        }
+      ],
+      "type": "document-fragment"
+    }
+  );
+
+  assert_json_eq!(dom_to_json(&fragment), expected);
+}
+
+#[test]
+fn foreign_context() {
+  let (fragment, _) =
+    parse_fragment(&context("svg", Namespace::Svg), "<path/><p>a");
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "namespace": "http://www.w3.org/2000/svg",
+          "tag": "path",
+          "type": "element"
+        },
+        {
+          "children": [
+            {
+              "type": "text",
+              "value": "a"
+            }
+          ],
+          "tag": "p",
+          "type": "element"
+        }
+      ],
+      "type": "document-fragment"
+    }
+  );
+
+  assert_json_eq!(dom_to_json(&fragment), expected);
+}
+
+#[test]
+fn html_end_tag_is_ignored() {
+  // </html>の後のコメントも、捨てられる文書ではなくフラグメントに入る
+  let expected = json!(
+    {
+      "children": [
+        {
+          "type": "text",
+          "value": "a"
+        },
+        {
+          "type": "comment",
+          "value": "x"
+        },
+        {
+          "type": "text",
+          "value": "b"
+        }
+      ],
+      "type": "document-fragment"
+    }
+  );
+
+  let (fragment, _) =
+    parse_fragment(&context("div", Namespace::Html), "a</html><!--x-->b");
+  assert_json_eq!(dom_to_json(&fragment), expected);
+}
+
+#[test]
+fn html_end_tag_after_body_is_ignored() {
+  let (fragment, errors) = parse_fragment(
+    &context("html", Namespace::Html),
+    "<body>a</body></html><!--x-->b",
+  );
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "tag": "head",
+          "type": "element"
+        },
+        {
+          "children": [
+            {
+              "type": "text",
+              "value": "ab"
+            }
+          ],
+          "tag": "body",
+          "type": "element"
+        },
+        {
+          "type": "comment",
+          "value": "x"
+        }
+      ],
+      "type": "document-fragment"
+    }
+  );
+
+  assert_json_eq!(dom_to_json(&fragment), expected);
+  assert!(!errors.is_empty());
+}
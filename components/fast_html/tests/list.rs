@@ -60,7 +60,7 @@ fn unordered_list() {
     }
   );
 
-  let document = get_document_from_html(html);
+  let (document, _) = get_document_from_html(html);
   let actual = dom_body_to_json(&document);
 
   assert_json_eq!(excepted, actual);
@@ -121,7 +121,7 @@ fn ordered_list() {
     }
   );
 
-  let document = get_document_from_html(html);
+  let (document, _) = get_document_from_html(html);
   let actual = dom_body_to_json(&document);
 
   assert_json_eq!(excepted, actual);
@@ -193,7 +193,7 @@ fn description_list() {
     }
   );
 
-  let document = get_document_from_html(html);
+  let (document, _) = get_document_from_html(html);
   let actual = dom_body_to_json(&document);
 
   assert_json_eq!(excepted, actual);
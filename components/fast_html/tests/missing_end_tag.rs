@@ -38,7 +38,7 @@ fn missing_p_end_tag() {
     }
   );
 
-  let document = get_document_from_html(html);
+  let (document, _) = get_document_from_html(html);
   let actual = dom_body_to_json(&document);
 
   assert_json_eq!(excepted, actual);
@@ -66,7 +66,7 @@ fn valid_nest() {
     }
   );
 
-  let document = get_document_from_html(html);
+  let (document, _) = get_document_from_html(html);
   let actual = dom_body_to_json(&document);
 
   assert_json_eq!(actual, expected);
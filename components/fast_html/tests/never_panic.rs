@@ -0,0 +1,137 @@
+extern crate fast_html;
+
+use fast_dom::node::NodePtr;
+use fast_html::debugger::*;
+
+fn assert_has_html_root(document: &NodePtr, html: &str) {
+  // html要素の前にコメントが入ることもある
+  assert!(
+    document.iterate_children().any(|child| child.as_maybe_element().is_some()),
+    "no root element for {:?}",
+    html
+  );
+}
+
+// fuzzディレクトリのfuzz targetを回せない環境向けに、タグの断片をランダムに並べた入力で確かめる
+#[test]
+fn random_tag_soup() {
+  let fragments = [
+    "<a>",
+    "</a>",
+    "<b>",
+    "</b>",
+    "<p>",
+    "</p>",
+    "<div>",
+    "</div>",
+    "<table>",
+    "</table>",
+    "<tr>",
+    "<td>",
+    "</td>",
+    "<th>",
+    "<caption>",
+    "<colgroup>",
+    "<col>",
+    "<tbody>",
+    "<select>",
+    "</select>",
+    "<option>",
+    "<optgroup>",
+    "<template>",
+    "</template>",
+    "<svg>",
+    "</svg>",
+    "<math>",
+    "<mi>",
+    "<foreignObject>",
+    "<desc>",
+    "<annotation-xml encoding=text/html>",
+    "<html>",
+    "<head>",
+    "<body>",
+    "</body>",
+    "</html>",
+    "<frameset>",
+    "<frame>",
+    "<form>",
+    "</form>",
+    "<input>",
+    "<nobr>",
+    "</br>",
+    "<image>",
+    "<textarea>",
+    "<xmp>",
+    "<noembed>",
+    "<noscript>",
+    "<noframes>",
+    "<style>",
+    "<script>",
+    "</script>",
+    "<title>",
+    "<plaintext>",
+    "<ruby>",
+    "<rb>",
+    "<rtc>",
+    "<rt>",
+    "<rp>",
+    "<li>",
+    "<dd>",
+    "<h1>",
+    "<button>",
+    "<!--",
+    "-->",
+    "<!DOCTYPE html>",
+    "<![CDATA[",
+    "]]>",
+    "&amp;",
+    "&#x0;",
+    "text",
+    " ",
+    "\n",
+    "\0",
+    "<",
+    "</",
+    "<!",
+    "=",
+    "\"",
+    "あ",
+  ];
+
+  // 結果を再現できるように、固定のシードで乱数を作る
+  let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+  let mut next = || {
+    seed ^= seed << 13;
+    seed ^= seed >> 7;
+    seed ^= seed << 17;
+    seed as usize
+  };
+
+  for _ in 0..2000 {
+    let len = next() % 24;
+    let html =
+      (0..len).map(|_| fragments[next() % fragments.len()]).collect::<String>();
+
+    let (document, _) = get_document_from_html(&html);
+    assert_has_html_root(&document, &html);
+  }
+}
+
+// 深さの上限を指定しなくても、深いツリーの出力と解放でスタックを使い切らない
+#[test]
+fn deep_tree() {
+  let html = format!("<svg>{}", "<g>".repeat(10000));
+  let (document, _) = get_document_from_html(&html);
+
+  let serialized = fast_html::serializer::serialize_children(&document);
+  assert_eq!(serialized.matches("</g>").count(), 10000);
+}
+
+#[test]
+fn long_sibling_list() {
+  let html = "<br>".repeat(100000);
+  let (document, _) = get_document_from_html(&html);
+
+  let serialized = fast_html::serializer::serialize_children(&document);
+  assert_eq!(serialized.matches("<br>").count(), 100000);
+}
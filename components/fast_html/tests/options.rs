@@ -0,0 +1,197 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+use fast_html::fragment::parse_fragment_with_options;
+use fast_html::options::ParserOptions;
+
+use fast_dom::document::QuirksMode;
+use fast_dom::namespace::Namespace;
+use fast_dom::tree::WeakTreeNode;
+
+use assert_json_diff::*;
+use serde_json::json;
+
+#[test]
+fn iframe_srcdoc() {
+  let options = ParserOptions {
+    iframe_srcdoc: true,
+    ..Default::default()
+  };
+  let (document, errors) = get_document_from_html_with_options("<p>a", options);
+
+  assert_eq!(
+    document.as_maybe_document().unwrap().mode(),
+    QuirksMode::NoQuirks
+  );
+  assert_eq!(errors, vec![]);
+}
+
+#[test]
+fn scripting_noscript_fragment() {
+  let document = fast_dom::create_document();
+  let context = fast_dom::create_element_ns(
+    WeakTreeNode::from(&document.0),
+    "noscript",
+    Namespace::Html,
+  );
+  let options = ParserOptions {
+    scripting: true,
+    ..Default::default()
+  };
+
+  let (fragment, _) =
+    parse_fragment_with_options(&context, "<p>a</p>", options);
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "type": "text",
+          "value": "<p>a</p>"
+        }
+      ],
+      "type": "document-fragment"
+    }
+  );
+
+  assert_json_eq!(expected, dom_to_json(&fragment));
+}
+
+#[test]
+fn scripting_noscript_in_head() {
+  let options = ParserOptions {
+    scripting: true,
+    ..Default::default()
+  };
+  let (document, _) = get_document_from_html_with_options(
+    "<head><noscript><link rel=a></noscript>",
+    options,
+  );
+
+  let expected = "| <html>\n|   <head>\n|     <noscript>\n|       \"<link rel=a>\"\n|   <body>\n";
+
+  assert_eq!(expected, dom_to_html5lib_tree(&document));
+}
+
+#[test]
+fn max_tree_depth() {
+  let options = ParserOptions {
+    max_tree_depth: Some(4),
+    ..Default::default()
+  };
+  let (document, _) = get_document_from_html_with_options(
+    "<div><div><div><div>a</div></div></div></div>",
+    options,
+  );
+
+  // html(1) > body(2) > div(3) > div(4) までで止まり、残りの要素は兄弟になる
+  // テキストは木を深くしないので、上限の深さの要素の中に入る
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "tag": "div",
+              "type": "element"
+            },
+            {
+              "tag": "div",
+              "type": "element"
+            },
+            {
+              "children": [
+                {
+                  "type": "text",
+                  "value": "a"
+                }
+              ],
+              "tag": "div",
+              "type": "element"
+            }
+          ],
+          "tag": "div",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  assert_json_eq!(expected, dom_body_to_json(&document));
+}
+
+#[test]
+fn max_attributes() {
+  let options = ParserOptions {
+    max_attributes: Some(2),
+    ..Default::default()
+  };
+  let (document, _) =
+    get_document_from_html_with_options("<p a=1 b=2 c=3></p>", options);
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "attributes": {
+            "a": "1",
+            "b": "2"
+          },
+          "tag": "p",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  assert_json_eq!(expected, dom_body_to_json(&document));
+}
+
+#[test]
+fn without_collecting_errors() {
+  let options = ParserOptions {
+    collect_errors: false,
+    ..Default::default()
+  };
+  let (_, errors) =
+    get_document_from_html_with_options("<p id=a id=b>&#0;</b>", options);
+
+  assert_eq!(errors, vec![]);
+}
+
+// template要素の中身は、template要素の子として深さを数える
+#[test]
+fn max_tree_depth_in_template() {
+  let options = ParserOptions {
+    max_tree_depth: Some(4),
+    ..Default::default()
+  };
+  let (document, _) = get_document_from_html_with_options(
+    "<template><div><div><div>a</div></div></div></template>",
+    options,
+  );
+
+  let expected = "| <html>\n|   <head>\n|     <template>\n|       content\n|         <div>\n|         <div>\n|         <div>\n|           \"a\"\n|   <body>\n";
+
+  assert_eq!(expected, dom_to_html5lib_tree(&document));
+}
+
+// 開いている要素が多くても、挿入先が浅ければそのまま入れる
+// 書式設定要素を組み替えるときも、上限の深さの要素の子は包まずに兄弟を入れる
+#[test]
+fn max_tree_depth_with_adoption_agency() {
+  let options = ParserOptions {
+    max_tree_depth: Some(5),
+    ..Default::default()
+  };
+  let (document, _) =
+    get_document_from_html_with_options("<a><div><div><div>x</a>", options);
+
+  let expected = "| <html>\n|   <head>\n|   <body>\n|     <a>\n|     <div>\n|       <a>\n|       <div>\n|         <a>\n|         <div>\n|           \"x\"\n|         <a>\n";
+
+  assert_eq!(expected, dom_to_html5lib_tree(&document));
+}
@@ -0,0 +1,148 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+use fast_html::error::{ParseError, SourceLocation};
+
+fn location(offset: usize, line: usize, column: usize) -> SourceLocation {
+  SourceLocation {
+    offset,
+    line,
+    column,
+  }
+}
+
+#[test]
+fn no_parse_error() {
+  let html =
+    "<!DOCTYPE html><html><head></head><body><p>text</p></body></html>";
+  let (_, errors) = get_document_from_html(html);
+
+  assert_eq!(errors, vec![]);
+}
+
+#[test]
+fn tokenizer_error_with_location() {
+  let html = "<!DOCTYPE html>\n<p>&#0;</p>\n<p id=a id=b></p>";
+  let (_, errors) = get_document_from_html(html);
+
+  assert_eq!(
+    errors,
+    vec![
+      ParseError::NullCharacterReference(location(23, 2, 8)),
+      ParseError::DuplicateAttribute(location(41, 3, 14)),
+    ]
+  );
+}
+
+#[test]
+fn eof_in_tag() {
+  let html = "<!DOCTYPE html><div class=";
+  let (_, errors) = get_document_from_html(html);
+
+  let codes = errors.iter().map(|error| error.code()).collect::<Vec<_>>();
+  assert_eq!(codes, vec!["eof-in-tag"]);
+}
+
+#[test]
+fn column_counts_characters() {
+  let html = "<!DOCTYPE html>\n<p>あいう</p>\0";
+  let (_, errors) = get_document_from_html(html);
+
+  let error = errors.first().unwrap();
+  assert_eq!(error.code(), "unexpected-null-character");
+  assert_eq!(error.location().line, 2);
+  assert_eq!(error.location().column, 11);
+  assert_eq!(error.to_string(), "unexpected-null-character at 2:11");
+}
+
+#[test]
+fn tree_construction_error() {
+  let html = "<!DOCTYPE html><p>text</div>";
+  let (_, errors) = get_document_from_html(html);
+
+  assert_eq!(
+    errors,
+    vec![ParseError::UnexpectedEndTag(location(22, 1, 23))]
+  );
+  assert_eq!(errors[0].code(), "unexpected-end-tag");
+}
+
+// 暗黙に閉じられる要素が開いていても、閉じる要素が現在ノードになればエラーではない
+#[test]
+fn end_tag_after_implied_end_tags() {
+  let html = "<!DOCTYPE html><ruby>a<rt>b</ruby><span><b>c</span>";
+  let (_, errors) = get_document_from_html(html);
+
+  assert_eq!(
+    errors,
+    vec![ParseError::UnexpectedEndTag(location(44, 1, 45))]
+  );
+}
+
+// 里親に出されるテキストのエラーは、テキストの位置に報告する
+#[test]
+fn unexpected_character_in_table() {
+  let html = "<!DOCTYPE html><table> x<tr><td>a</td></tr></table>";
+  let (_, errors) = get_document_from_html(html);
+
+  assert_eq!(
+    errors,
+    vec![ParseError::UnexpectedCharacterInTable(location(22, 1, 23))]
+  );
+}
+
+#[test]
+fn missing_doctype() {
+  let (_, errors) = get_document_from_html("<p>text");
+
+  let codes = errors.iter().map(|error| error.code()).collect::<Vec<_>>();
+  assert_eq!(codes, vec!["expected-doctype"]);
+}
+
+#[test]
+fn unexpected_text_has_stable_code() {
+  let html = "<!DOCTYPE html><html></html>some long text";
+  let (_, errors) = get_document_from_html(html);
+
+  let codes = errors.iter().map(|error| error.code()).collect::<Vec<_>>();
+  assert_eq!(codes, vec!["unexpected-text"]);
+}
+
+#[test]
+fn non_void_element_with_trailing_solidus() {
+  let html = "<!DOCTYPE html><div/><br/><svg><path/></svg></div>";
+  let (_, errors) = get_document_from_html(html);
+
+  assert_eq!(
+    errors,
+    vec![ParseError::NonVoidHtmlElementStartTagWithTrailingSolidus(
+      location(15, 1, 16)
+    )]
+  );
+}
+
+#[test]
+fn control_characters_and_noncharacters() {
+  let html = "<!DOCTYPE html>\u{1}\t\n\u{7F}<p title=\u{85}>\u{FDD0}\u{1FFFF}";
+  let (_, errors) = get_document_from_html(html);
+
+  assert_eq!(
+    errors,
+    vec![
+      ParseError::ControlCharacterInInputStream(location(15, 1, 16)),
+      ParseError::ControlCharacterInInputStream(location(18, 2, 1)),
+      ParseError::ControlCharacterInInputStream(location(28, 2, 11)),
+      ParseError::NoncharacterInInputStream(location(31, 2, 13)),
+      ParseError::NoncharacterInInputStream(location(34, 2, 14)),
+    ]
+  );
+}
+
+#[test]
+fn null_characters_in_tag() {
+  let html = "<!DOCTYPE html><p\0 a\0=\"\0\" b='\0' c=\0><!--\0--></p\u{FFFD}>";
+  let (_, errors) = get_document_from_html(html);
+
+  let codes = errors.iter().map(|error| error.code()).collect::<Vec<_>>();
+  assert_eq!(codes, vec!["unexpected-null-character"; 6]);
+}
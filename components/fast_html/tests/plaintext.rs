@@ -0,0 +1,45 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+
+use assert_json_diff::*;
+use serde_json::json;
+
+#[test]
+fn plaintext_element() {
+  let html = r#"<p>before<plaintext><b>bold</b></plaintext>&amp;"#;
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "type": "text",
+              "value": "before"
+            }
+          ],
+          "tag": "p",
+          "type": "element"
+        },
+        {
+          "children": [
+            {
+              "type": "text",
+              "value": "<b>bold</b></plaintext>&amp;"
+            }
+          ],
+          "tag": "plaintext",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  assert_json_eq!(actual, expected);
+}
@@ -0,0 +1,141 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+use fast_html::tokenizer::state::State;
+use fast_html::tokenizer::token::Token;
+use fast_html::tokenizer::Tokenizer;
+
+use assert_json_diff::*;
+use serde_json::json;
+
+// <script>開始タグを読んだあと、ScriptData状態で残りを読み切る
+fn tokenize_script(html: &str) -> (String, Vec<Token>) {
+  let mut tokenizer = Tokenizer::new(html.as_bytes());
+
+  let start_tag = tokenizer.next_token();
+  assert!(start_tag.is_start_tag() && start_tag.tag_name() == "script");
+
+  tokenizer.reconsume_in(State::ScriptData);
+
+  let mut text = String::new();
+  let mut rest = Vec::new();
+
+  loop {
+    match tokenizer.next_token() {
+      Token::Text(s) if rest.is_empty() => text.push_str(&s),
+      Token::EOF => break,
+      token => rest.push(token),
+    }
+  }
+
+  (text, rest)
+}
+
+#[test]
+fn script_with_less_than_sign() {
+  let (text, rest) = tokenize_script("<script>if (a < b) {}</script>");
+
+  assert_eq!(text, "if (a < b) {}");
+  assert_eq!(rest, vec![Token::new_end_tag_of("script")]);
+}
+
+#[test]
+fn script_with_partial_end_tag() {
+  let (text, rest) =
+    tokenize_script("<script>var s = '</scr' + 'ipt>';</script>");
+
+  assert_eq!(text, "var s = '</scr' + 'ipt>';");
+  assert_eq!(rest, vec![Token::new_end_tag_of("script")]);
+}
+
+#[test]
+fn script_with_escaped_comment() {
+  let (text, rest) =
+    tokenize_script("<script><!-- document.write('x'); --></script>");
+
+  assert_eq!(text, "<!-- document.write('x'); -->");
+  assert_eq!(rest, vec![Token::new_end_tag_of("script")]);
+}
+
+#[test]
+fn script_with_double_escaped_script() {
+  let html = "<script><!--<script>x</script>--></script>";
+  let (text, rest) = tokenize_script(html);
+
+  assert_eq!(text, "<!--<script>x</script>-->");
+  assert_eq!(rest, vec![Token::new_end_tag_of("script")]);
+}
+
+#[test]
+fn script_with_uppercase_end_tag() {
+  let (text, rest) = tokenize_script("<script>a</SCRIPT>");
+
+  assert_eq!(text, "a");
+  assert_eq!(rest, vec![Token::new_end_tag_of("script")]);
+}
+
+#[test]
+fn script_in_head_and_body() {
+  let html = r#"<html><head><script>var a = "<!--";</script></head><body><script>if (a < b) {}</script><p>after</p></body></html>"#;
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "children": [
+                {
+                  "children": [
+                    {
+                      "type": "text",
+                      "value": "var a = \"<!--\";"
+                    }
+                  ],
+                  "tag": "script",
+                  "type": "element"
+                }
+              ],
+              "tag": "head",
+              "type": "element"
+            },
+            {
+              "children": [
+                {
+                  "children": [
+                    {
+                      "type": "text",
+                      "value": "if (a < b) {}"
+                    }
+                  ],
+                  "tag": "script",
+                  "type": "element"
+                },
+                {
+                  "children": [
+                    {
+                      "type": "text",
+                      "value": "after"
+                    }
+                  ],
+                  "tag": "p",
+                  "type": "element"
+                }
+              ],
+              "tag": "body",
+              "type": "element"
+            }
+          ],
+          "tag": "html",
+          "type": "element"
+        }
+      ],
+      "type": "document"
+    }
+  );
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_to_json(&document);
+
+  assert_json_eq!(actual, expected);
+}
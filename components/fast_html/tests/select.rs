@@ -0,0 +1,272 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+
+use assert_json_diff::*;
+use serde_json::json;
+
+#[test]
+fn select_with_implied_end_tags() {
+  let html = r#"<select><option>A<option>B<optgroup label="g"><option>C</optgroup><option>D</select><p>after"#;
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "children": [
+                {
+                  "type": "text",
+                  "value": "A"
+                }
+              ],
+              "tag": "option",
+              "type": "element"
+            },
+            {
+              "children": [
+                {
+                  "type": "text",
+                  "value": "B"
+                }
+              ],
+              "tag": "option",
+              "type": "element"
+            },
+            {
+              "attributes": {
+                "label": "g"
+              },
+              "children": [
+                {
+                  "children": [
+                    {
+                      "type": "text",
+                      "value": "C"
+                    }
+                  ],
+                  "tag": "option",
+                  "type": "element"
+                }
+              ],
+              "tag": "optgroup",
+              "type": "element"
+            },
+            {
+              "children": [
+                {
+                  "type": "text",
+                  "value": "D"
+                }
+              ],
+              "tag": "option",
+              "type": "element"
+            }
+          ],
+          "tag": "select",
+          "type": "element"
+        },
+        {
+          "children": [
+            {
+              "type": "text",
+              "value": "after"
+            }
+          ],
+          "tag": "p",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  assert_json_eq!(actual, expected);
+}
+
+#[test]
+fn nested_select_closes_select() {
+  let html = r#"<select><option>A<select>B<keygen>"#;
+
+  let (document, errors) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "children": [
+                {
+                  "type": "text",
+                  "value": "A"
+                }
+              ],
+              "tag": "option",
+              "type": "element"
+            }
+          ],
+          "tag": "select",
+          "type": "element"
+        },
+        {
+          "type": "text",
+          "value": "B"
+        },
+        {
+          "tag": "keygen",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  assert_json_eq!(actual, expected);
+  assert!(
+    errors.iter().any(|error| error.code() == "unexpected-start-tag"
+      && error.location().offset == 17)
+  );
+}
+
+#[test]
+fn select_in_table() {
+  let html = r#"<table><tr><td><select><option>A</td><td>x</td></tr></table>"#;
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "children": [
+                {
+                  "children": [
+                    {
+                      "children": [
+                        {
+                          "children": [
+                            {
+                              "children": [
+                                {
+                                  "type": "text",
+                                  "value": "A"
+                                }
+                              ],
+                              "tag": "option",
+                              "type": "element"
+                            }
+                          ],
+                          "tag": "select",
+                          "type": "element"
+                        }
+                      ],
+                      "tag": "td",
+                      "type": "element"
+                    },
+                    {
+                      "children": [
+                        {
+                          "type": "text",
+                          "value": "x"
+                        }
+                      ],
+                      "tag": "td",
+                      "type": "element"
+                    }
+                  ],
+                  "tag": "tr",
+                  "type": "element"
+                }
+              ],
+              "tag": "tbody",
+              "type": "element"
+            }
+          ],
+          "tag": "table",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  assert_json_eq!(actual, expected);
+}
+
+#[test]
+fn option_in_body() {
+  let html =
+    r#"<p><option>x<option>y</p><select><hr><option>z</option></select>"#;
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  let expected = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "children": [
+                {
+                  "type": "text",
+                  "value": "x"
+                }
+              ],
+              "tag": "option",
+              "type": "element"
+            },
+            {
+              "children": [
+                {
+                  "type": "text",
+                  "value": "y"
+                }
+              ],
+              "tag": "option",
+              "type": "element"
+            }
+          ],
+          "tag": "p",
+          "type": "element"
+        },
+        {
+          "children": [
+            {
+              "tag": "hr",
+              "type": "element"
+            },
+            {
+              "children": [
+                {
+                  "type": "text",
+                  "value": "z"
+                }
+              ],
+              "tag": "option",
+              "type": "element"
+            }
+          ],
+          "tag": "select",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  assert_json_eq!(actual, expected);
+}
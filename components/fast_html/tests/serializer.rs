@@ -0,0 +1,115 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+use fast_html::fragment::parse_fragment;
+use fast_html::options::ParserOptions;
+use fast_html::serializer::*;
+
+use fast_dom::namespace::Namespace;
+use fast_dom::tree::WeakTreeNode;
+
+fn body_inner_html(html: &str) -> String {
+  let (document, _) = get_document_from_html(html);
+  let html = document.first_child().unwrap();
+  let body = html.last_child().unwrap();
+  serialize_children(&fast_dom::node::NodePtr(body))
+}
+
+#[test]
+fn document() {
+  let (document, _) =
+    get_document_from_html("<!DOCTYPE html><title>a</title><p>b");
+
+  assert_eq!(
+    serialize(&document),
+    "<!DOCTYPE html><html><head><title>a</title></head><body><p>b</p></body></html>"
+  );
+}
+
+// DOCTYPEの前のコメントも、入力と同じ位置に出力する
+#[test]
+fn comment_before_doctype() {
+  let html =
+    "<!--a--><!DOCTYPE html><!--b--><html><head></head><body></body></html>";
+  let (document, _) = get_document_from_html(html);
+
+  assert_eq!(serialize(&document), html);
+}
+
+#[test]
+fn attribute_order() {
+  let html = r#"<p title="t" id="x" data-a="1" class="c d">a</p>"#;
+  assert_eq!(body_inner_html(html), html);
+}
+
+#[test]
+fn escape_text_and_attributes() {
+  assert_eq!(
+    body_inner_html("<p title='a \"b\" &amp; <c>'>1 &lt; 2 &amp;&amp; 3&nbsp;&gt; 2</p>"),
+    "<p title=\"a &quot;b&quot; &amp; &lt;c&gt;\">1 &lt; 2 &amp;&amp; 3&nbsp;&gt; 2</p>"
+  );
+}
+
+#[test]
+fn void_elements() {
+  assert_eq!(
+    body_inner_html("<p>a<br>b<img src=x.png></p><hr>"),
+    "<p>a<br>b<img src=\"x.png\"></p><hr>"
+  );
+}
+
+#[test]
+fn raw_text_elements() {
+  assert_eq!(
+    body_inner_html("<p>x</p><script>if (a < b && c) {}</script><!--x-->"),
+    "<p>x</p><script>if (a < b && c) {}</script><!--x-->"
+  );
+}
+
+// スクリプトが有効な場合、noscriptの中身は生のテキストとして解析され、そのまま出力される
+#[test]
+fn noscript_with_scripting() {
+  let html = "<p>x</p><noscript><p>a & b</p></noscript>";
+  let options = ParserOptions {
+    scripting: true,
+    ..Default::default()
+  };
+
+  let (document, _) =
+    get_document_from_html_with_options(html, options.clone());
+  let body = document.first_child().unwrap().last_child().unwrap();
+
+  assert_eq!(
+    serialize_children_with_options(&fast_dom::node::NodePtr(body), options),
+    html
+  );
+}
+
+// テキストやコメントは、ノード自身を出力する
+#[test]
+fn text_and_comment_nodes() {
+  let (document, _) = get_document_from_html("<p>a &amp; b<!--c--></p>");
+  let body = document.first_child().unwrap().last_child().unwrap();
+  let p = body.first_child().unwrap();
+
+  let text = fast_dom::node::NodePtr(p.first_child().unwrap());
+  let comment = fast_dom::node::NodePtr(p.last_child().unwrap());
+
+  assert_eq!(serialize(&text), "a &amp; b");
+  assert_eq!(serialize(&comment), "<!--c-->");
+}
+
+#[test]
+fn template_and_foreign_content() {
+  let document = fast_dom::create_document();
+  let context = fast_dom::create_element_ns(
+    WeakTreeNode::from(&document.0),
+    "div",
+    Namespace::Html,
+  );
+
+  let html = "<template><b>t</b></template><svg viewBox=\"0 0 1 1\"><use xlink:href=\"#a\"></use></svg>";
+  let (fragment, _) = parse_fragment(&context, html);
+
+  assert_eq!(serialize_children(&fragment), html);
+}
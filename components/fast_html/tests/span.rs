@@ -0,0 +1,89 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+use fast_html::line_index::LineIndex;
+use fast_html::tokenizer::token::Token;
+use fast_html::tokenizer::Tokenizer;
+
+use fast_dom::span::Span;
+
+fn slice_of(html: &str, span: Span) -> &str {
+  &html[span.start..span.end]
+}
+
+#[test]
+fn token_span() {
+  let html = r#"<p class="a">Hello, &amp; world<!-- c --></p>"#;
+
+  let mut tokenizer = Tokenizer::new(html.as_bytes());
+  let mut slices = Vec::new();
+
+  loop {
+    let (token, span) = tokenizer.next_token_with_span();
+    if let Token::EOF = token {
+      assert_eq!(span, Span::at(html.len()));
+      break;
+    }
+    slices.push(slice_of(html, span));
+  }
+
+  assert_eq!(
+    slices,
+    vec![
+      r#"<p class="a">"#,
+      "Hello, ",
+      "&amp;",
+      " world",
+      "<!-- c -->",
+      "</p>"
+    ]
+  );
+}
+
+#[test]
+fn node_span() {
+  let html = "<!DOCTYPE html>\n<div id=x>\n  text<!--c--><b>bold</b>\n</div>";
+  let (document, _) = get_document_from_html(html);
+
+  // 最初の子はDOCTYPE
+  let html_element = document.last_child().unwrap();
+  let body = html_element.last_child().unwrap();
+  let div = body.first_child().unwrap();
+
+  assert_eq!(slice_of(html, div.span().unwrap()), "<div id=x>");
+
+  let children = div.iterate_children().collect::<Vec<_>>();
+  assert_eq!(slice_of(html, children[0].span().unwrap()), "\n  text");
+  assert_eq!(slice_of(html, children[1].span().unwrap()), "<!--c-->");
+  assert_eq!(slice_of(html, children[2].span().unwrap()), "<b>");
+
+  let bold_text = children[2].first_child().unwrap();
+  assert_eq!(slice_of(html, bold_text.span().unwrap()), "bold");
+}
+
+#[test]
+fn implied_element_span() {
+  let html = "<p>text";
+  let (document, _) = get_document_from_html(html);
+
+  let html_element = document.first_child().unwrap();
+  let head = html_element.first_child().unwrap();
+  let body = html_element.last_child().unwrap();
+
+  assert_eq!(html_element.span(), Some(Span::at(0)));
+  assert_eq!(head.span(), Some(Span::at(0)));
+  assert_eq!(body.span(), Some(Span::at(0)));
+}
+
+#[test]
+fn line_index() {
+  let html = "<p>\n  あい<b>\n</p>";
+  let index = LineIndex::new(html);
+
+  let location = index.location(html.find("<b>").unwrap());
+  assert_eq!((location.line, location.column), (2, 5));
+
+  let (start, end) = index.span_location(Span::new(0, html.len()));
+  assert_eq!((start.line, start.column), (1, 1));
+  assert_eq!((end.line, end.column), (3, 5));
+}
@@ -0,0 +1,153 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+use fast_html::tokenizer::token::Token;
+use fast_html::tokenizer::Tokenizer;
+use fast_html::tree_builder::TreeBuilder;
+
+use fast_dom::document::Document;
+use fast_dom::node::{DOMNode, DOMNodeData, NodePtr};
+use fast_dom::tree::TreeNode;
+
+use assert_json_diff::assert_json_eq;
+
+fn streaming_tree_builder() -> TreeBuilder<'static> {
+  let document = NodePtr(TreeNode::new(DOMNode::new(DOMNodeData::Document(
+    Document::new(),
+  ))));
+  TreeBuilder::new(Tokenizer::streaming(), document)
+}
+
+fn parse_in_chunks(html: &str, chunk_size: usize) -> NodePtr {
+  let mut tree_builder = streaming_tree_builder();
+
+  for chunk in html.as_bytes().chunks(chunk_size) {
+    tree_builder.feed(chunk);
+  }
+
+  let (document, _) = tree_builder.finish();
+  document
+}
+
+#[test]
+fn any_chunk_size() {
+  let html = r#"<!DOCTYPE html>
+<html lang="ja">
+  <head><title>テスト &amp; 見本</title></head>
+  <body>
+    <!-- コメント -->
+    <p class="lead" data-x='1'>Tom &amp Jerry &#x41;&#66; &notit;</p>
+    <pre>
+line</pre>
+    <script>if (a < b) { c(); }</script>
+  </body>
+</html>"#;
+
+  let (expected, _) = get_document_from_html(html);
+
+  for chunk_size in 1..=html.len() {
+    let document = parse_in_chunks(html, chunk_size);
+    assert_json_eq!(dom_to_json(&document), dom_to_json(&expected));
+  }
+}
+
+#[test]
+fn tokens_split_across_chunks() {
+  let chunks: [&[u8]; 6] = [
+    b"<di",
+    b"v cl",
+    b"ass=\"a\">&am",
+    b"p;\xE3\x81",
+    b"\x82</",
+    b"div>",
+  ];
+
+  let mut tokenizer = Tokenizer::streaming();
+  let mut tokens = Vec::new();
+
+  for chunk in chunks {
+    tokenizer.feed(chunk);
+    while let Some(token) = tokenizer.try_next_token() {
+      tokens.push(token);
+    }
+  }
+
+  tokenizer.finish();
+  loop {
+    let token = tokenizer.next_token();
+    if let Token::EOF = token {
+      break;
+    }
+    tokens.push(token);
+  }
+
+  let mut expected =
+    Tokenizer::new("<div class=\"a\">&amp;あ</div>".as_bytes());
+  for token in tokens {
+    assert_eq!(token, expected.next_token());
+  }
+  assert_eq!(expected.next_token(), Token::EOF);
+}
+
+#[test]
+fn build_progressively() {
+  let mut tree_builder = streaming_tree_builder();
+
+  tree_builder.feed(b"<ul><li>one</li><li>t");
+  {
+    let body = tree_builder.document().first_child().unwrap().last_child();
+    let ul = body.unwrap().first_child().unwrap();
+    assert_eq!(ul.iterate_children().count(), 2);
+  }
+
+  tree_builder.feed(b"wo</li></ul>");
+  let (document, _) = tree_builder.finish();
+
+  assert_json_eq!(
+    dom_body_to_json(&document),
+    dom_body_to_json(
+      &get_document_from_html("<ul><li>one</li><li>two</li></ul>").0
+    )
+  );
+}
+
+#[test]
+fn long_tokens_across_chunks() {
+  let html = format!(
+    "<!--{}--><p title=\"{}\">{}</p>",
+    "a-".repeat(1 << 19),
+    "a&amp;".repeat(1 << 17),
+    "b".repeat(1 << 20),
+  );
+
+  let (expected, _) = get_document_from_html(&html);
+  let document = parse_in_chunks(&html, 4096);
+
+  assert_eq!(
+    dom_to_html5lib_tree(&document),
+    dom_to_html5lib_tree(&expected)
+  );
+}
+
+#[test]
+fn errors_do_not_depend_on_chunk_size() {
+  // テキストが想定されない位置にあると、テキストトークンごとにエラーになる
+  let html = "<!DOCTYPE html><table>text in table<tr></table>\
+    <svg><![CDATA[a\0b]]></svg>\u{1}\u{FDD0}</html> text after html";
+
+  let (expected, expected_errors) = get_document_from_html(html);
+
+  for chunk_size in 1..=html.len() {
+    let mut tree_builder = streaming_tree_builder();
+    for chunk in html.as_bytes().chunks(chunk_size) {
+      tree_builder.feed(chunk);
+    }
+    let (document, errors) = tree_builder.finish();
+
+    assert_eq!(
+      dom_to_html5lib_tree(&document),
+      dom_to_html5lib_tree(&expected)
+    );
+    assert_eq!(errors, expected_errors, "chunk size: {}", chunk_size);
+  }
+}
@@ -0,0 +1,66 @@
+extern crate fast_html;
+
+use fast_html::debugger::*;
+
+// .datファイルは、conformanceクレートと同じhtml5lib_datクレートで読む
+// #errorsの各行は、"(行,列): エラーコード"の形式
+fn run(source: &str) {
+  for test in html5lib_dat::parse(source) {
+    let (document, errors) = get_document_from_html(&test.data);
+    assert_eq!(
+      dom_to_html5lib_tree(&document),
+      test.document,
+      "\n#data\n{}",
+      test.data
+    );
+
+    let errors = errors
+      .iter()
+      .map(|error| {
+        let location = error.location();
+        format!("({},{}): {}", location.line, location.column, error.code())
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(errors, test.errors, "\n#data\n{}", test.data);
+  }
+}
+
+#[test]
+fn tables() {
+  run(include_str!("data/tables.dat"));
+}
+
+#[test]
+fn template() {
+  run(include_str!("data/template.dat"));
+}
+
+#[test]
+fn foreign() {
+  run(include_str!("data/foreign.dat"));
+}
+
+#[test]
+fn form() {
+  run(include_str!("data/form.dat"));
+}
+
+#[test]
+fn raw_text() {
+  run(include_str!("data/raw_text.dat"));
+}
+
+#[test]
+fn frameset() {
+  run(include_str!("data/frameset.dat"));
+}
+
+#[test]
+fn in_body() {
+  run(include_str!("data/in_body.dat"));
+}
+
+#[test]
+fn ruby() {
+  run(include_str!("data/ruby.dat"));
+}
@@ -32,8 +32,65 @@ fn with_attributes() {
     }
   );
 
-  let document = get_document_from_html(html);
+  let (document, _) = get_document_from_html(html);
   let actual = dom_body_to_json(&document);
 
   assert_json_eq!(excepted, actual);
 }
+
+#[test]
+fn attribute_name_with_unexpected_characters() {
+  let html = r#"<p a"b="1" =c></p>"#;
+
+  let excepted = json!(
+    {
+      "children": [
+        {
+          "attributes": {
+            "a\"b": "1",
+            "=c": ""
+          },
+          "tag": "p",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  let (document, _) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  assert_json_eq!(excepted, actual);
+}
+
+#[test]
+fn attribute_names_are_lowercased() {
+  // 大文字と小文字だけが異なる属性は、重複として最初のものだけが残る
+  let html = r#"<a X=1 Y HREF="a" x=2></a>"#;
+
+  let excepted = json!(
+    {
+      "children": [
+        {
+          "attributes": {
+            "href": "a",
+            "x": "1",
+            "y": ""
+          },
+          "tag": "a",
+          "type": "element"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  let (document, errors) = get_document_from_html(html);
+  let actual = dom_body_to_json(&document);
+
+  assert_json_eq!(excepted, actual);
+  assert!(errors.iter().any(|error| error.code() == "duplicate-attribute"));
+}
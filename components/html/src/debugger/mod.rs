@@ -4,29 +4,21 @@ use serde_json::json;
 use std::borrow::Borrow;
 use std::collections::HashMap;
 
-use super::tokenizer::Tokenizer;
-use super::tree_builder::TreeBuilder;
+use super::sink::DomSink;
 
-use dom::document::Document;
 use dom::node::Node;
-use dom::node::NodeData;
 use dom::node::NodePtr;
 
+use fast_html::parser::{HtmlParser, Parser};
+
 use tree::TreeNode;
 
 /* -------------------------------------------- */
 
+// 木構築はfast_htmlと共通の実装で、domクレートのツリーを組み立てる
 pub fn get_document_from_html(html: &str) -> NodePtr {
-  let target = html.chars();
-
-  let empty_document = NodePtr(TreeNode::new(Node::new(NodeData::Document(
-    Document::new(),
-  ))));
-
-  let tokenizer = Tokenizer::new(target);
-  let tree_builder = TreeBuilder::new(tokenizer, empty_document);
-
-  tree_builder.run()
+  let (document, _) = Parser::<DomSink>::new().parse(html);
+  document
 }
 
 /* print DOM tree ----------------------------- */
@@ -167,6 +159,11 @@ fn dom_list_to_recursive_json(
       if !attributes.is_empty() {
         simple_node["attributes"] = json!(attributes);
       }
+    } else if let Some(comment) = node.as_maybe_comment() {
+      simple_node = json!({
+        "type": "comment",
+        "data": comment.get_data().as_str(),
+      });
     } else if node.as_maybe_document().is_some() {
       simple_node = json!({
         "type": "document",
@@ -1,3 +1,2 @@
 pub mod debugger;
-pub mod tokenizer;
-pub mod tree_builder;
+pub mod sink;
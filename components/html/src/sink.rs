@@ -0,0 +1,269 @@
+use std::rc::Rc;
+
+use ecow::{EcoString, EcoVec};
+
+use dom::comment::Comment;
+use dom::document::{Document, DocumentType};
+use dom::node::{Node, NodeData, NodePtr};
+use dom::text::Text;
+
+use fast_html::sink::{NodeHandle, TreeSink};
+use fast_html::tokenizer::token::Attribute;
+
+use fast_dom::document::QuirksMode;
+use fast_dom::namespace::Namespace;
+
+use tree::{TreeNode, WeakTreeNode};
+
+// domクレートのノードへの参照
+#[derive(Clone)]
+pub struct DomHandle {
+  node: NodePtr,
+  local_name: EcoString,
+  namespace: Namespace,
+}
+
+impl DomHandle {
+  // 要素でない場合は、HTML名前空間とみなす
+  fn from_node(node: TreeNode<Node>) -> Self {
+    let (local_name, namespace) = node
+      .as_maybe_element()
+      .map(|element| {
+        let namespace = Namespace::from_url(element.namespace_uri())
+          .unwrap_or(Namespace::Html);
+        (element.tag_name(), namespace)
+      })
+      .unwrap_or_default();
+
+    Self {
+      node: NodePtr(node),
+      local_name,
+      namespace,
+    }
+  }
+
+  pub fn node(&self) -> &NodePtr {
+    &self.node
+  }
+}
+
+impl NodeHandle for DomHandle {
+  fn same_node(&self, other: &Self) -> bool {
+    Rc::ptr_eq(&self.node, &other.node)
+  }
+
+  fn local_name(&self) -> &str {
+    &self.local_name
+  }
+
+  fn namespace(&self) -> Namespace {
+    self.namespace
+  }
+
+  fn is_element(&self) -> bool {
+    self.node.as_maybe_element().is_some()
+  }
+
+  fn is_text(&self) -> bool {
+    self.node.as_maybe_text().is_some()
+  }
+
+  fn is_document(&self) -> bool {
+    self.node.as_maybe_document().is_some()
+  }
+}
+
+// fast_htmlの木構築で、domクレートのツリーを組み立てる
+pub struct DomSink {
+  document: NodePtr,
+}
+
+impl Default for DomSink {
+  fn default() -> Self {
+    Self::new(NodePtr(TreeNode::new(Node::new(NodeData::Document(
+      Document::new(),
+    )))))
+  }
+}
+
+impl DomSink {
+  // documentには空のDocumentを渡す
+  pub fn new(document: NodePtr) -> Self {
+    Self { document }
+  }
+
+  fn new_node(&self, data: NodeData) -> DomHandle {
+    let node = Node::new(data);
+    node.set_document(WeakTreeNode::from(&self.document.0));
+    DomHandle::from_node(TreeNode::new(node))
+  }
+}
+
+impl TreeSink for DomSink {
+  type Handle = DomHandle;
+  type Output = NodePtr;
+
+  /* document ----------------------------------- */
+
+  fn document(&self) -> DomHandle {
+    DomHandle::from_node(self.document.0.clone())
+  }
+
+  fn finish(self) -> NodePtr {
+    self.document
+  }
+
+  fn set_doctype(
+    &self,
+    name: EcoString,
+    public_identifier: EcoString,
+    system_identifier: EcoString,
+  ) {
+    if let Some(document) = self.document.as_maybe_document() {
+      document.set_doctype(DocumentType::new(
+        name,
+        public_identifier,
+        system_identifier,
+      ));
+    }
+  }
+
+  // domクレートの文書は後方互換モードを持たない
+  fn set_quirks_mode(&self, _mode: QuirksMode) {}
+
+  /* create ------------------------------------- */
+
+  fn create_element(
+    &self,
+    local_name: &str,
+    namespace: Namespace,
+  ) -> DomHandle {
+    let node = dom::create_element_ns(
+      WeakTreeNode::from(&self.document.0),
+      local_name,
+      namespace.url(),
+    );
+
+    // template要素の子は、メインのツリーではなくDocumentFragmentに入れる
+    if namespace == Namespace::Html && local_name == "template" {
+      let contents = self.new_node(NodeData::DocumentFragment);
+      node.as_element().set_template_contents(contents.node);
+    }
+
+    DomHandle::from_node(node.0)
+  }
+
+  fn create_comment(&self, data: EcoString) -> DomHandle {
+    self.new_node(NodeData::Comment(Comment::new(data)))
+  }
+
+  fn create_text(&self, data: EcoString) -> DomHandle {
+    self.new_node(NodeData::Text(Text::new(data)))
+  }
+
+  /* attribute ---------------------------------- */
+
+  fn attributes(&self, element: &DomHandle) -> EcoVec<Attribute> {
+    let element = element.node.as_element();
+    let mut attributes = EcoVec::new();
+
+    if let Some(id) = element.id().borrow().as_ref() {
+      attributes.push(Attribute {
+        name: "id".into(),
+        value: id.clone(),
+      });
+    }
+
+    let class_list = element.class_list().borrow().values();
+    if !class_list.is_empty() {
+      attributes.push(Attribute {
+        name: "class".into(),
+        value: class_list.into(),
+      });
+    }
+
+    attributes.extend(element.attributes().borrow().iter().map(
+      |(name, value)| Attribute {
+        name: name.clone(),
+        value: value.clone(),
+      },
+    ));
+
+    attributes
+  }
+
+  fn get_attribute(
+    &self,
+    element: &DomHandle,
+    name: &str,
+  ) -> Option<EcoString> {
+    let element = element.node.as_element();
+
+    // id・class属性は、ほかの属性とは別に持っている
+    match name {
+      "id" => element.id().borrow().clone(),
+      "class" => {
+        let class_list = element.class_list().borrow().values();
+        (!class_list.is_empty()).then(|| class_list.into())
+      }
+      _ => element.attributes().borrow().get(name).cloned(),
+    }
+  }
+
+  fn set_attribute(&self, element: &DomHandle, name: &str, value: &str) {
+    element.node.as_element().set_attribute(name, value);
+  }
+
+  /* tree --------------------------------------- */
+
+  fn parent(&self, node: &DomHandle) -> Option<DomHandle> {
+    node.node.parent().map(DomHandle::from_node)
+  }
+
+  fn last_child(&self, node: &DomHandle) -> Option<DomHandle> {
+    node.node.last_child().map(DomHandle::from_node)
+  }
+
+  fn prev_sibling(&self, node: &DomHandle) -> Option<DomHandle> {
+    node.node.prev_sibling().map(DomHandle::from_node)
+  }
+
+  fn template_contents(&self, element: &DomHandle) -> Option<DomHandle> {
+    element
+      .node
+      .as_maybe_element()
+      .and_then(|element| element.template_contents())
+      .map(|contents| DomHandle::from_node(contents.0))
+  }
+
+  fn append(&self, parent: &DomHandle, child: DomHandle) {
+    parent.node.append_child(child.node.0);
+  }
+
+  fn insert_before(
+    &self,
+    parent: &DomHandle,
+    sibling: &DomHandle,
+    child: DomHandle,
+  ) {
+    parent.node.insert_before(child.node.0, Some(sibling.node.0.clone()));
+  }
+
+  fn remove_from_parent(&self, node: &DomHandle) {
+    node.node.detach();
+  }
+
+  fn reparent_children(&self, from: &DomHandle, to: &DomHandle) {
+    from.node.transfer_children_to(to.node.0.clone());
+  }
+
+  /* text --------------------------------------- */
+
+  fn text_data(&self, text: &DomHandle) -> EcoString {
+    text.node.as_text().get_data()
+  }
+
+  fn set_text_data(&self, text: &DomHandle, data: EcoString) {
+    text.node.as_text().characters.set_data(&data);
+  }
+}
@@ -1,44 +0,0 @@
-#[derive(Debug, Clone)]
-pub enum State {
-  Data,
-
-  RCDATA,
-  RCDATALessThanSign,
-  RCDATAEndTagOpen,
-  RCDATAEndTagName,
-
-  RAWTEXT,
-  RAWTEXTLessThanSign,
-  RAWTEXTEndTagOpen,
-  RAWTEXTEndTagName,
-
-  CharacterReference,
-  NamedCharacterReference,
-  NumericCharacterReference,
-
-  TagName,
-  TagOpen,
-  EndTagOpen,
-  SelfClosingStartTag,
-
-  MarkupDeclarationOpen,
-
-  CommentStart,
-  BogusComment,
-
-  BeforeAttributeName,
-  AttributeName,
-  AfterAttributeName,
-
-  BeforeAttributeValue,
-  AttributeValueDoubleQuoted,
-  AttributeValueSingleQuoted,
-  AttributeValueUnQuoted,
-  AfterAttributeValueQuoted,
-
-  DOCTYPE,
-  BeforeDOCTYPEName,
-  DOCTYPEName,
-  AfterDOCTYPEName,
-  BogusDOCTYPE,
-}
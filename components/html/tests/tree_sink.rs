@@ -0,0 +1,118 @@
+extern crate html;
+
+use html::debugger::*;
+use html::sink::DomSink;
+
+use fast_dom::namespace::Namespace;
+use fast_html::sink::{NodeHandle, TreeSink};
+
+use assert_json_diff::*;
+use serde_json::json;
+
+// fast_htmlと共通の木構築で、書式設定要素の組み替えやコメントも扱える
+#[test]
+fn misnested_formatting_elements() {
+  let html = r#"<p><b>bold<i>both</b>italic</i></p><!--note-->"#;
+
+  let excepted = json!(
+    {
+      "children": [
+        {
+          "children": [
+            {
+              "children": [
+                {
+                  "data": "bold",
+                  "type": "text"
+                },
+                {
+                  "children": [
+                    {
+                      "data": "both",
+                      "type": "text"
+                    }
+                  ],
+                  "tag": "i",
+                  "type": "element"
+                }
+              ],
+              "tag": "b",
+              "type": "element"
+            },
+            {
+              "children": [
+                {
+                  "data": "italic",
+                  "type": "text"
+                }
+              ],
+              "tag": "i",
+              "type": "element"
+            }
+          ],
+          "tag": "p",
+          "type": "element"
+        },
+        {
+          "data": "note",
+          "type": "comment"
+        }
+      ],
+      "tag": "body",
+      "type": "element"
+    }
+  );
+
+  let document = get_document_from_html(html);
+  let actual = dom_in_body_to_json_obj(&document);
+
+  assert_json_eq!(excepted, actual);
+}
+
+// ツリーを辿って得た要素も、作ったときの名前空間を持つ
+#[test]
+fn traversed_element_keeps_namespace() {
+  let sink = DomSink::default();
+
+  let svg = sink.create_element("svg", Namespace::Svg);
+  let title = sink.create_element("title", Namespace::Svg);
+  sink.append(&svg, title.clone());
+  sink.append(&sink.document(), svg);
+
+  let parent = sink.parent(&title).unwrap();
+  assert_eq!(parent.local_name(), "svg");
+  assert_eq!(parent.namespace(), Namespace::Svg);
+
+  let child = sink.last_child(&parent).unwrap();
+  assert_eq!(child.namespace(), Namespace::Svg);
+  assert!(!child.match_tag_name_in(&["title"]));
+}
+
+// template要素の子は、要素の子ではなくtemplate contentsに入る
+#[test]
+fn template_contents() {
+  let document = get_document_from_html("<template><p>x</p></template>");
+
+  let html = document.first_child().unwrap();
+  let head = html.first_child().unwrap();
+  let template = head.first_child().unwrap();
+  assert!(template.first_child().is_none());
+
+  let contents = template.as_element().template_contents().unwrap();
+  let p = contents.first_child().unwrap();
+  assert_eq!(p.as_element().tag_name(), "p");
+  assert_eq!(p.first_child().unwrap().as_text().get_data(), "x");
+}
+
+// DOCTYPEの公開識別子とシステム識別子を保持する
+#[test]
+fn doctype_identifiers() {
+  let document = get_document_from_html(
+    r#"<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01//EN" "http://www.w3.org/TR/html4/strict.dtd">"#,
+  );
+
+  let doctype = document.as_maybe_document().unwrap().doctype().unwrap();
+  assert_eq!(doctype.name, "html");
+  assert_eq!(doctype.public_id, "-//W3C//DTD HTML 4.01//EN");
+  assert_eq!(doctype.system_id, "http://www.w3.org/TR/html4/strict.dtd");
+}
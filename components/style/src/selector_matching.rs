@@ -103,7 +103,11 @@ fn is_match_simple_selector(
 ) -> bool {
   match selector {
     SimpleSelector::Universal => true,
-    SimpleSelector::Type(t) => element.tag_name().eq_ignore_ascii_case(t),
+    // 大文字小文字を区別しないのは、HTML名前空間の要素のみ
+    SimpleSelector::Type(t) if element.is_html() => {
+      element.local_name().eq_ignore_ascii_case(t)
+    }
+    SimpleSelector::Type(t) => element.local_name() == t,
     SimpleSelector::Class(c) => {
       element.class_list().borrow().contains(&EcoString::from(c))
     }
@@ -161,6 +165,8 @@ mod tests {
 
   use fast_dom::create_document;
   use fast_dom::create_element;
+  use fast_dom::create_element_ns;
+  use fast_dom::namespace::Namespace;
   use fast_dom::tree::WeakTreeNode;
 
   fn assert_style_rule_matched_element(rule: &CSSRule, element: &NodePtr) {
@@ -224,6 +230,26 @@ mod tests {
     assert_style_rule_matched_element(&rules, &element);
   }
 
+  #[test]
+  fn match_foreign_type_case_sensitive() {
+    let element = create_element_ns(
+      WeakTreeNode::from(&create_document().0),
+      "foreignObject",
+      Namespace::Svg,
+    );
+    let css = "foreignObject { color: red; } foreignobject { color: blue; }";
+
+    let stylesheet = parse_css(css).unwrap();
+
+    let mut rules = stylesheet.rules.iter();
+
+    let first_rule = rules.next().unwrap();
+    let second_rule = rules.next().unwrap();
+
+    assert_style_rule_matched_element(first_rule, &element);
+    assert_style_rule_not_matched_element(second_rule, &element);
+  }
+
   #[test]
   fn match_attribute_has() {
     let element =
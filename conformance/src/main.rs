@@ -0,0 +1,390 @@
+mod targets;
+mod tokenizer_test;
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use targets::Parser;
+use tokenizer_test::normalize;
+
+// 無限ループするテストがあっても、ほかのテストを続けられるように打ち切る
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+  Pass,
+  Fail,
+  // パーサーが対応していない(フラグメントやスクリプトなど)
+  Skip,
+  // panic、スタックオーバーフローまたはタイムアウト
+  Crash,
+}
+
+impl Outcome {
+  fn name(&self) -> &'static str {
+    match self {
+      Outcome::Pass => "pass",
+      Outcome::Fail => "fail",
+      Outcome::Skip => "skip",
+      Outcome::Crash => "crash",
+    }
+  }
+
+  fn from_name(name: &str) -> Option<Self> {
+    [Outcome::Pass, Outcome::Fail, Outcome::Skip, Outcome::Crash]
+      .into_iter()
+      .find(|outcome| outcome.name() == name)
+  }
+}
+
+#[derive(Debug, Default)]
+struct Summary {
+  pass: usize,
+  fail: usize,
+  skip: usize,
+  crash: usize,
+}
+
+impl Summary {
+  fn add(&mut self, outcome: Outcome) {
+    match outcome {
+      Outcome::Pass => self.pass += 1,
+      Outcome::Fail => self.fail += 1,
+      Outcome::Skip => self.skip += 1,
+      Outcome::Crash => self.crash += 1,
+    }
+  }
+
+  fn merge(&mut self, other: &Summary) {
+    self.pass += other.pass;
+    self.fail += other.fail;
+    self.skip += other.skip;
+    self.crash += other.crash;
+  }
+
+  fn total(&self) -> usize {
+    self.pass + self.fail + self.skip + self.crash
+  }
+}
+
+struct Options {
+  parsers: Vec<Parser>,
+  filters: Vec<String>,
+  verbose: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Suite {
+  TreeConstruction,
+  Tokenizer,
+}
+
+impl Suite {
+  const ALL: [Suite; 2] = [Suite::TreeConstruction, Suite::Tokenizer];
+
+  fn name(&self) -> &'static str {
+    match self {
+      Suite::TreeConstruction => "tree-construction",
+      Suite::Tokenizer => "tokenizer",
+    }
+  }
+
+  fn extension(&self) -> &'static str {
+    match self {
+      Suite::TreeConstruction => "dat",
+      Suite::Tokenizer => "test",
+    }
+  }
+}
+
+// 1つのテストケース
+// expectedとrunの結果は、比べやすいように文字列にしておく
+struct Case {
+  title: String,
+  expected: String,
+  // パーサーが対応していない場合はNoneを返す
+  run: Box<dyn Fn(Parser) -> Option<String>>,
+}
+
+/* -------------------------------------------- */
+
+fn load_cases(suite: Suite, path: &Path) -> Vec<Case> {
+  let source = fs::read_to_string(path).unwrap();
+
+  match suite {
+    Suite::TreeConstruction => html5lib_dat::parse(&source)
+      .into_iter()
+      .map(|test| Case {
+        title: test.data.clone(),
+        expected: test.document.clone(),
+        run: Box::new(move |parser| parser.parse_tree(&test)),
+      })
+      .collect(),
+    Suite::Tokenizer => tokenizer_test::parse(&source)
+      .into_iter()
+      .flat_map(|test| {
+        let expected = Value::Array(normalize(&test.output)).to_string();
+
+        test.initial_states.clone().into_iter().map(move |state| {
+          let title = format!("{} ({:?})", test.description, test.input);
+          let input = test.input.clone();
+          let last_start_tag = test.last_start_tag.clone();
+
+          Case {
+            title,
+            expected: expected.clone(),
+            run: Box::new(move |parser| {
+              parser
+                .tokenize(&input, &state, last_start_tag.as_deref())
+                .map(|actual| Value::Array(normalize(&actual)).to_string())
+            }),
+          }
+        })
+      })
+      .collect(),
+  }
+}
+
+fn report_failure(verbose: bool, title: &str, expected: &str, actual: &str) {
+  if verbose {
+    println!(
+      "    FAIL {}\n--- expected\n{}\n--- actual\n{}",
+      title, expected, actual
+    );
+  }
+}
+
+/* worker ------------------------------------- */
+
+// 子プロセスとして、start番目以降のテストケースを順に実行する
+// 1ケースごとに、結果を1行のJSONで標準出力に書く
+fn run_worker(suite: Suite, parser: Parser, path: &Path, start: usize) {
+  let mut stdout = std::io::stdout().lock();
+
+  for case in load_cases(suite, path).iter().skip(start) {
+    let (outcome, actual) =
+      match panic::catch_unwind(AssertUnwindSafe(|| (case.run)(parser))) {
+        Err(_) => (Outcome::Crash, None),
+        Ok(None) => (Outcome::Skip, None),
+        Ok(Some(actual)) if actual == case.expected => (Outcome::Pass, None),
+        Ok(Some(actual)) => (Outcome::Fail, Some(actual)),
+      };
+
+    let line = json!({ "outcome": outcome.name(), "actual": actual });
+    writeln!(stdout, "{}", line).unwrap();
+    stdout.flush().unwrap();
+  }
+}
+
+/* -------------------------------------------- */
+
+// ファイルごとに子プロセスでテストケースを実行する
+// 子プロセスが途中で終了した場合(スタックオーバーフローなど)や時間内に結果を返さない場合は、
+// そのケースをクラッシュとして数え、プロセスを終了させてから次のケースで起動し直す
+fn run_file(
+  suite: Suite,
+  path: &Path,
+  parser: Parser,
+  options: &Options,
+) -> Summary {
+  let cases = load_cases(suite, path);
+  let mut summary = Summary::default();
+  let mut next = 0;
+
+  while next < cases.len() {
+    let mut child = Command::new(std::env::current_exe().unwrap())
+      .arg("--worker")
+      .arg(suite.name())
+      .arg(parser.name())
+      .arg(path)
+      .arg(next.to_string())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::null())
+      .spawn()
+      .unwrap();
+
+    // 読み込みを待つ間もタイムアウトできるように、別スレッドで1行ずつ読む
+    // 子プロセスが終了すると、出力が閉じてスレッドも終わる
+    let stdout = child.stdout.take().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+      for line in BufReader::new(stdout).lines() {
+        if line.is_err() || sender.send(line.unwrap()).is_err() {
+          break;
+        }
+      }
+    });
+
+    while next < cases.len() {
+      let Ok(line) = receiver.recv_timeout(TIMEOUT) else {
+        break;
+      };
+
+      let result = serde_json::from_str::<Value>(&line).unwrap();
+      let outcome = Outcome::from_name(result["outcome"].as_str().unwrap())
+        .unwrap_or(Outcome::Crash);
+      let case = &cases[next];
+
+      match outcome {
+        Outcome::Fail => {
+          let actual = result["actual"].as_str().unwrap_or_default();
+          report_failure(options.verbose, &case.title, &case.expected, actual);
+        }
+        Outcome::Crash => {
+          report_failure(
+            options.verbose,
+            &case.title,
+            &case.expected,
+            "(crashed)",
+          );
+        }
+        _ => {}
+      }
+
+      summary.add(outcome);
+      next += 1;
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+
+    if next < cases.len() {
+      let case = &cases[next];
+      report_failure(options.verbose, &case.title, &case.expected, "(crashed)");
+      summary.add(Outcome::Crash);
+      next += 1;
+    }
+  }
+
+  summary
+}
+
+/* -------------------------------------------- */
+
+fn list_files(dir: &Path, extension: &str, options: &Options) -> Vec<PathBuf> {
+  let mut files = fs::read_dir(dir)
+    .unwrap()
+    .map(|entry| entry.unwrap().path())
+    .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+    .filter(|path| {
+      let name = path.file_name().unwrap().to_string_lossy();
+      options.filters.is_empty()
+        || options.filters.iter().any(|filter| name.contains(filter.as_str()))
+    })
+    .collect::<Vec<_>>();
+
+  files.sort();
+  files
+}
+
+fn run_suite(suite: Suite, root: &Path, options: &Options) {
+  let files = list_files(&root.join(suite.name()), suite.extension(), options);
+
+  if files.is_empty() {
+    return;
+  }
+
+  println!("# {}", suite.name());
+
+  for parser in &options.parsers {
+    let mut total = Summary::default();
+
+    for path in &files {
+      let name = path.file_name().unwrap().to_string_lossy();
+      let summary = run_file(suite, path, *parser, options);
+
+      println!(
+        "{:<10} {:<48} {:>5} / {:<5} failed {:>4}  crashed {:>4}  skipped {:>4}",
+        parser.name(),
+        name,
+        summary.pass,
+        summary.total(),
+        summary.fail,
+        summary.crash,
+        summary.skip,
+      );
+
+      total.merge(&summary);
+    }
+
+    println!(
+      "{:<10} {:<48} {:>5} / {:<5} failed {:>4}  crashed {:>4}  skipped {:>4}\n",
+      parser.name(),
+      "TOTAL",
+      total.pass,
+      total.total(),
+      total.fail,
+      total.crash,
+      total.skip,
+    );
+  }
+}
+
+// 使い方: cargo run -p conformance -- [--parser html|fast_html] [--verbose] [ファイル名の一部...]
+fn parse_args() -> Options {
+  let mut options = Options {
+    parsers: Parser::ALL.to_vec(),
+    filters: Vec::new(),
+    verbose: false,
+  };
+
+  let mut args = std::env::args().skip(1);
+
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--parser" => {
+        let name = args.next().expect("--parser needs a parser name");
+        options.parsers = Parser::ALL
+          .into_iter()
+          .filter(|parser| parser.name() == name)
+          .collect();
+      }
+      "--verbose" => options.verbose = true,
+      _ => options.filters.push(arg),
+    }
+  }
+
+  options
+}
+
+// 子プロセスとして起動された場合: --worker <suite> <parser> <path> <start>
+fn parse_worker_args() -> Option<(Suite, Parser, PathBuf, usize)> {
+  let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+  let [flag, suite, parser, path, start] = args.as_slice() else {
+    return None;
+  };
+
+  if flag != "--worker" {
+    return None;
+  }
+
+  let suite = Suite::ALL.into_iter().find(|s| s.name() == suite)?;
+  let parser = Parser::ALL.into_iter().find(|p| p.name() == parser)?;
+
+  Some((suite, parser, PathBuf::from(path), start.parse().ok()?))
+}
+
+fn main() {
+  // todo!()などによるpanicは結果として数えるので、メッセージは出力しない
+  panic::set_hook(Box::new(|_| {}));
+
+  if let Some((suite, parser, path, start)) = parse_worker_args() {
+    return run_worker(suite, parser, &path, start);
+  }
+
+  let options = parse_args();
+  let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("html5lib-tests");
+
+  for suite in Suite::ALL {
+    run_suite(suite, &root, &options);
+  }
+}
@@ -0,0 +1,123 @@
+use serde_json::{json, Map, Value};
+
+use fast_dom::namespace::Namespace;
+use fast_dom::tree::WeakTreeNode;
+
+use fast_html::debugger::{
+  dom_to_html5lib_tree, get_document_from_html_with_options,
+};
+use fast_html::fragment::parse_fragment_with_options;
+use fast_html::options::ParserOptions;
+use fast_html::tokenizer::state::State;
+use fast_html::tokenizer::token::Token;
+use fast_html::tokenizer::Tokenizer;
+
+use html5lib_dat::TreeTest;
+
+pub fn parse_tree(test: &TreeTest) -> Option<String> {
+  let options = ParserOptions {
+    scripting: test.scripting == Some(true),
+    ..Default::default()
+  };
+
+  let Some(context) = &test.document_fragment else {
+    let (document, _) =
+      get_document_from_html_with_options(&test.data, options);
+    return Some(dom_to_html5lib_tree(&document));
+  };
+
+  // "svg path"のように、名前空間が前に付くことがある
+  let (namespace, tag_name) = match context.split_once(' ') {
+    Some(("svg", tag_name)) => (Namespace::Svg, tag_name),
+    Some(("math", tag_name)) => (Namespace::MathMl, tag_name),
+    _ => (Namespace::Html, context.as_str()),
+  };
+
+  let document = fast_dom::create_document();
+  let context_element = fast_dom::create_element_ns(
+    WeakTreeNode::from(&document.0),
+    tag_name,
+    namespace,
+  );
+
+  let (fragment, _) =
+    parse_fragment_with_options(&context_element, &test.data, options);
+  Some(dom_to_html5lib_tree(&fragment))
+}
+
+pub fn tokenize(
+  input: &str,
+  initial_state: &str,
+  last_start_tag: Option<&str>,
+) -> Option<Vec<Value>> {
+  let state = match initial_state {
+    "Data state" => State::Data,
+    "PLAINTEXT state" => State::PLAINTEXT,
+    "RCDATA state" => State::RCDATA,
+    "RAWTEXT state" => State::RAWTEXT,
+    "Script data state" => State::ScriptData,
+    "CDATA section state" => State::CDATASection,
+    _ => return None,
+  };
+
+  let mut tokenizer = Tokenizer::new(input.as_bytes());
+  tokenizer.reconsume_in(state);
+
+  if let Some(tag_name) = last_start_tag {
+    tokenizer.set_last_start_tag(tag_name);
+  }
+
+  let mut tokens = Vec::new();
+
+  loop {
+    let token = tokenizer.next_token();
+    if token.is_eof() {
+      break;
+    }
+    tokens.push(token_to_json(token));
+  }
+
+  Some(tokens)
+}
+
+fn token_to_json(token: Token) -> Value {
+  match token {
+    Token::DOCTYPE {
+      name,
+      public_identifier,
+      system_identifier,
+      force_quirks,
+    } => json!([
+      "DOCTYPE",
+      name.as_deref(),
+      public_identifier.as_deref(),
+      system_identifier.as_deref(),
+      !force_quirks
+    ]),
+    Token::Tag {
+      tag_name,
+      attributes,
+      self_closing,
+      is_end_tag,
+      ..
+    } => {
+      if is_end_tag {
+        return json!(["EndTag", tag_name.as_str()]);
+      }
+
+      let attributes = attributes
+        .iter()
+        .map(|attr| (attr.name.to_string(), json!(attr.value.as_str())))
+        .collect::<Map<_, _>>();
+
+      if self_closing {
+        json!(["StartTag", tag_name.as_str(), attributes, true])
+      } else {
+        json!(["StartTag", tag_name.as_str(), attributes])
+      }
+    }
+    Token::Text(text) => json!(["Character", text.as_str()]),
+    Token::Comment(comment) => json!(["Comment", comment.as_str()]),
+    Token::EOF => Value::Null,
+  }
+}
@@ -0,0 +1,129 @@
+use serde_json::Value;
+
+use dom::node::{Node, NodeData};
+use tree::TreeNode;
+
+use fast_dom::namespace::Namespace;
+use fast_html::tree_builder::adjust_foreign_attribute;
+
+use html::debugger::get_document_from_html;
+
+use html5lib_dat::TreeTest;
+
+// htmlクレートはフラグメントの解析に対応していない
+pub fn parse_tree(test: &TreeTest) -> Option<String> {
+  // フラグメントの解析と、スクリプトが有効な場合の解析には対応していない
+  if test.document_fragment.is_some() || test.scripting == Some(true) {
+    return None;
+  }
+
+  let document = get_document_from_html(&test.data);
+  let mut out = String::new();
+
+  if let Some(Some(doctype)) =
+    document.as_maybe_document().map(|document| document.doctype())
+  {
+    if doctype.public_id.is_empty() && doctype.system_id.is_empty() {
+      out.push_str(&format!("| <!DOCTYPE {}>\n", doctype.name));
+    } else {
+      out.push_str(&format!(
+        "| <!DOCTYPE {} \"{}\" \"{}\">\n",
+        doctype.name, doctype.public_id, doctype.system_id
+      ));
+    }
+  }
+
+  html5lib_tree_core(&document, 0, &mut out);
+  Some(out)
+}
+
+// htmlクレートはfast_htmlのトークナイザを使うので、トークナイザのテストは行わない
+pub fn tokenize(
+  _input: &str,
+  _initial_state: &str,
+  _last_start_tag: Option<&str>,
+) -> Option<Vec<Value>> {
+  None
+}
+
+// fast_html::debuggerと同じく、名前空間に属する要素・属性の名前の前に付ける略称
+fn html5lib_namespace_prefix(namespace: Namespace) -> &'static str {
+  match namespace {
+    Namespace::Html => "",
+    Namespace::MathMl => "math ",
+    Namespace::Svg => "svg ",
+    Namespace::XLink => "xlink ",
+    Namespace::Xml => "xml ",
+    Namespace::Xmlns => "xmlns ",
+  }
+}
+
+// domクレートの属性は修飾名で持っているので、外部コンテンツの要素では名前空間を付け直す
+fn html5lib_attribute_name(namespace: Namespace, name: &str) -> String {
+  match adjust_foreign_attribute(name).filter(|_| namespace != Namespace::Html)
+  {
+    Some((_, local_name, namespace)) => {
+      format!("{}{}", html5lib_namespace_prefix(namespace), local_name)
+    }
+    None => name.to_string(),
+  }
+}
+
+// fast_html::debugger::dom_to_html5lib_treeと同じ形式で、domクレートのツリーを文字列にする
+fn html5lib_tree_core(root: &TreeNode<Node>, depth: usize, out: &mut String) {
+  for child in root.iterate_children() {
+    let indent = format!("| {}", "  ".repeat(depth));
+
+    match child.data() {
+      Some(NodeData::Element(element)) => {
+        let namespace = Namespace::from_url(element.namespace_uri())
+          .unwrap_or(Namespace::Html);
+
+        out.push_str(&format!(
+          "{}<{}{}>\n",
+          indent,
+          html5lib_namespace_prefix(namespace),
+          element.tag_name()
+        ));
+
+        let mut attributes = element
+          .attributes()
+          .borrow()
+          .iter()
+          .map(|(key, value)| {
+            (html5lib_attribute_name(namespace, key), value.to_string())
+          })
+          .collect::<Vec<_>>();
+
+        let class_attribute = element.class_list().borrow().join(" ");
+        if !class_attribute.is_empty() {
+          attributes.push(("class".to_string(), class_attribute));
+        }
+
+        if let Some(id) = element.id().borrow().as_ref() {
+          attributes.push(("id".to_string(), id.to_string()));
+        }
+
+        attributes.sort();
+
+        for (key, value) in attributes {
+          out.push_str(&format!("{}  {}=\"{}\"\n", indent, key, value));
+        }
+
+        if let Some(contents) = element.template_contents() {
+          out.push_str(&format!("{}  content\n", indent));
+          html5lib_tree_core(&contents, depth + 2, out);
+        }
+      }
+      Some(NodeData::Text(text)) => {
+        out.push_str(&format!("{}\"{}\"\n", indent, text.get_data()));
+      }
+      Some(NodeData::Comment(comment)) => {
+        out.push_str(&format!("{}<!-- {} -->\n", indent, comment.get_data()));
+      }
+      _ => {}
+    }
+
+    html5lib_tree_core(&child, depth + 1, out);
+  }
+}
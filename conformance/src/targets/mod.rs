@@ -0,0 +1,49 @@
+mod fast_html;
+mod html;
+
+use serde_json::Value;
+
+use html5lib_dat::TreeTest;
+
+// テストを実行する対象のパーサー
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parser {
+  Html,
+  FastHtml,
+}
+
+impl Parser {
+  pub const ALL: [Parser; 2] = [Parser::Html, Parser::FastHtml];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Parser::Html => "html",
+      Parser::FastHtml => "fast_html",
+    }
+  }
+
+  // html5lib形式のツリーを返す
+  // パーサーが対応していないテストの場合はNoneを返す
+  pub fn parse_tree(&self, test: &TreeTest) -> Option<String> {
+    match self {
+      Parser::Html => html::parse_tree(test),
+      Parser::FastHtml => fast_html::parse_tree(test),
+    }
+  }
+
+  // html5lib形式のトークン列を返す
+  // パーサーが対応していない初期状態などの場合はNoneを返す
+  pub fn tokenize(
+    &self,
+    input: &str,
+    initial_state: &str,
+    last_start_tag: Option<&str>,
+  ) -> Option<Vec<Value>> {
+    match self {
+      Parser::Html => html::tokenize(input, initial_state, last_start_tag),
+      Parser::FastHtml => {
+        fast_html::tokenize(input, initial_state, last_start_tag)
+      }
+    }
+  }
+}
@@ -0,0 +1,125 @@
+use serde_json::Value;
+
+// html5lib-testsのtokenizer形式(.test)のテストケース
+// https://github.com/html5lib/html5lib-tests/tree/master/tokenizer
+
+#[derive(Debug, Clone)]
+pub struct TokenizerTest {
+  pub description: String,
+  pub input: String,
+  pub output: Vec<Value>,
+  pub initial_states: Vec<String>,
+  pub last_start_tag: Option<String>,
+}
+
+pub fn parse(source: &str) -> Vec<TokenizerTest> {
+  let json: Value = serde_json::from_str(source).unwrap();
+
+  // xmlViolation.testなど、testsの代わりにxmlViolationTestsを持つファイルもある
+  let Some(tests) = json.get("tests").and_then(|tests| tests.as_array()) else {
+    return Vec::new();
+  };
+
+  tests.iter().map(to_test).collect()
+}
+
+fn to_test(test: &Value) -> TokenizerTest {
+  let double_escaped = test["doubleEscaped"].as_bool().unwrap_or(false);
+
+  let unescape_str = |str: &str| {
+    if double_escaped {
+      unescape(str)
+    } else {
+      str.to_string()
+    }
+  };
+
+  let initial_states = match test["initialStates"].as_array() {
+    Some(states) => {
+      states.iter().map(|state| state.as_str().unwrap().to_string()).collect()
+    }
+    None => vec!["Data state".to_string()],
+  };
+
+  let output = test["output"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|token| unescape_value(token, &unescape_str))
+    .collect();
+
+  TokenizerTest {
+    description: test["description"].as_str().unwrap_or("").to_string(),
+    input: unescape_str(test["input"].as_str().unwrap()),
+    output,
+    initial_states,
+    last_start_tag: test["lastStartTag"].as_str().map(String::from),
+  }
+}
+
+fn unescape_value<F>(value: &Value, unescape_str: &F) -> Value
+where
+  F: Fn(&str) -> String,
+{
+  match value {
+    Value::String(str) => Value::String(unescape_str(str)),
+    Value::Array(values) => Value::Array(
+      values.iter().map(|value| unescape_value(value, unescape_str)).collect(),
+    ),
+    Value::Object(map) => Value::Object(
+      map
+        .iter()
+        .map(|(key, value)| {
+          (unescape_str(key), unescape_value(value, unescape_str))
+        })
+        .collect(),
+    ),
+    _ => value.clone(),
+  }
+}
+
+// doubleEscapedなテストでは、\uXXXXの形でエスケープされた文字を元に戻す
+// 単独のサロゲートは文字にできないので、U+FFFDに置き換える
+fn unescape(str: &str) -> String {
+  let mut units = Vec::new();
+  let mut chars = str.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    if c == '\\' && chars.peek() == Some(&'u') {
+      chars.next();
+      let hex = chars.by_ref().take(4).collect::<String>();
+      units.push(u16::from_str_radix(&hex, 16).unwrap());
+    } else {
+      let mut buf = [0; 2];
+      units.extend_from_slice(c.encode_utf16(&mut buf));
+    }
+  }
+
+  String::from_utf16_lossy(&units)
+}
+
+// 比較の前に、ParseErrorを取り除き、連続するCharacterトークンをまとめる
+pub fn normalize(tokens: &[Value]) -> Vec<Value> {
+  let mut normalized: Vec<Value> = Vec::new();
+
+  for token in tokens {
+    if token == "ParseError" {
+      continue;
+    }
+
+    if token[0] == "Character" {
+      if let Some(last) = normalized.last_mut() {
+        if last[0] == "Character" {
+          let data =
+            last[1].as_str().unwrap().to_string() + token[1].as_str().unwrap();
+          last[1] = Value::String(data);
+          continue;
+        }
+      }
+    }
+
+    normalized.push(token.clone());
+  }
+
+  normalized
+}
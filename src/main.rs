@@ -15,10 +15,14 @@ fn run_html(html: &str) {
 }
 
 fn run_fast_html(html: &str) {
-  let document = fast_html::debugger::get_document_from_html(html);
+  let (document, errors) = fast_html::debugger::get_document_from_html(html);
 
   fast_html::debugger::print_dom_tree(&document);
 
+  for error in errors {
+    println!("{}", error);
+  }
+
   println!("-------------------");
 
   let json = fast_html::debugger::dom_to_json_string(&document);
@@ -0,0 +1,100 @@
+// html5lib-testsのtree-construction形式(.dat)のテストケース
+// https://github.com/html5lib/html5lib-tests/tree/master/tree-construction
+
+#[derive(Debug, Clone)]
+pub struct TreeTest {
+  pub data: String,
+  // #errorsの各行("(1,5): expected-doctype"など)
+  pub errors: Vec<String>,
+  // フラグメントのテストの場合のみ、コンテキスト要素("td"や"svg path"など)を持つ
+  pub document_fragment: Option<String>,
+  // #script-on/#script-offの指定
+  pub scripting: Option<bool>,
+  pub document: String,
+}
+
+pub fn parse(source: &str) -> Vec<TreeTest> {
+  let mut tests = Vec::new();
+  let mut sections: Vec<(String, Vec<&str>)> = Vec::new();
+
+  let lines = source.split('\n').collect::<Vec<_>>();
+
+  for (index, line) in lines.iter().enumerate() {
+    // #dataは、ファイルの先頭か空行の直後にある場合のみ新しいテストの始まり
+    let is_new_test =
+      *line == "#data" && (index == 0 || lines[index - 1].is_empty());
+
+    if is_new_test && !sections.is_empty() {
+      tests.push(to_test(std::mem::take(&mut sections)));
+    }
+
+    if line.starts_with('#') && (is_new_test || !sections.is_empty()) {
+      let is_section_header = is_new_test
+        || matches!(
+          *line,
+          "#errors"
+            | "#new-errors"
+            | "#document-fragment"
+            | "#script-on"
+            | "#script-off"
+            | "#document"
+        );
+
+      if is_section_header {
+        sections.push((line.to_string(), Vec::new()));
+        continue;
+      }
+    }
+
+    if let Some((_, content)) = sections.last_mut() {
+      content.push(line);
+    }
+  }
+
+  if !sections.is_empty() {
+    tests.push(to_test(sections));
+  }
+
+  tests
+}
+
+fn to_test(sections: Vec<(String, Vec<&str>)>) -> TreeTest {
+  let mut test = TreeTest {
+    data: String::new(),
+    errors: Vec::new(),
+    document_fragment: None,
+    scripting: None,
+    document: String::new(),
+  };
+
+  for (name, mut content) in sections {
+    // テスト同士を区切る空行は、最後の#documentの中身に含めない
+    if name == "#document" {
+      while content.last().is_some_and(|line| line.is_empty()) {
+        content.pop();
+      }
+    }
+
+    if name == "#errors" {
+      test.errors = content
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect();
+      continue;
+    }
+
+    let content = content.join("\n");
+
+    match name.as_str() {
+      "#data" => test.data = content,
+      "#document-fragment" => test.document_fragment = Some(content),
+      "#script-on" => test.scripting = Some(true),
+      "#script-off" => test.scripting = Some(false),
+      "#document" => test.document = content + "\n",
+      _ => {}
+    }
+  }
+
+  test
+}
@@ -91,6 +91,32 @@ impl<T: TreeNodeHooks<T> + Debug> From<&TreeNode<T>> for WeakTreeNode<T> {
   }
 }
 
+// 子と後ろの兄弟は強い参照で持っているので、そのまま解放すると、
+// 深いツリーや長い兄弟のリストでは再帰が深くなってスタックを使い切る
+// 他から参照されていないノードは、リンクを外してから1つずつ解放する
+impl<T: TreeNodeHooks<T> + Debug> Drop for Node<T> {
+  fn drop(&mut self) {
+    let mut nodes = Vec::new();
+    take_links(self, &mut nodes);
+
+    while let Some(node) = nodes.pop() {
+      if let Ok(mut node) = Rc::try_unwrap(node.0) {
+        take_links(&mut node, &mut nodes);
+      }
+    }
+  }
+}
+
+fn take_links<T: TreeNodeHooks<T> + Debug>(
+  node: &mut Node<T>,
+  nodes: &mut Vec<TreeNode<T>>,
+) {
+  // last_childは、兄弟のリストの末尾と同じノードを指している
+  node.last_child.get_mut().take();
+  nodes.extend(node.first_child.get_mut().take());
+  nodes.extend(node.next_sibling.get_mut().take());
+}
+
 impl<T: TreeNodeHooks<T> + Debug> Node<T> {
   pub fn new(data: T) -> Self {
     Self {
@@ -143,13 +169,15 @@ impl<T: TreeNodeHooks<T> + Debug> TreeNode<T> {
       next_sibling.prev_sibling.replace(self.prev_sibling.borrow().clone());
     }
     if let Some(parent) = self.parent() {
-      let first_child = self.first_child().unwrap();
-      let last_child = self.last_child().unwrap();
-
-      if Rc::ptr_eq(self, &first_child) {
-        parent.first_child.replace(self.next_sibling());
-      } else if Rc::ptr_eq(self, &last_child) {
-        parent.last_child.replace(self.prev_sibling());
+      if let Some(first_child) = parent.first_child() {
+        if Rc::ptr_eq(self, &first_child) {
+          parent.first_child.replace(self.next_sibling());
+        }
+      }
+      if let Some(last_child) = parent.last_child() {
+        if Rc::ptr_eq(self, &last_child) {
+          parent.last_child.replace(self.prev_sibling());
+        }
       }
     }
 
@@ -158,11 +186,11 @@ impl<T: TreeNodeHooks<T> + Debug> TreeNode<T> {
     self.prev_sibling.replace(None);
   }
 
+  // 子ノードをすべてnew_parentの末尾に移す
   pub fn transfer_children_to(&self, new_parent: TreeNode<T>) {
-    new_parent.first_child.replace(self.first_child());
-    new_parent.last_child.replace(self.last_child());
-    self.first_child.replace(None);
-    self.last_child.replace(None);
+    while let Some(child) = self.first_child() {
+      new_parent.append_child(child);
+    }
   }
 
   pub fn find_first_anecestor<F>(&self, predicate: F) -> Option<TreeNode<T>>
@@ -228,6 +256,7 @@ impl<T: TreeNodeHooks<T> + Debug> TreeNode<T> {
         self.first_child.replace(Some(child.clone()));
       }
 
+      child.next_sibling.replace(Some(ref_child.clone()));
       ref_child.prev_sibling.replace(Some(WeakTreeNode::from(child.clone())));
     } else {
       self.append_child(child);